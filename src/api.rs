@@ -1,5 +1,5 @@
 use axum::{
-    extract::{State, Path},
+    extract::{State, Path, Query},
     response::Json,
     routing::{get, post},
     Router,
@@ -9,13 +9,24 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use futures::future::join_all;
 use tokio::sync::RwLock;
-use crate::wallet::Wallet;
+use rand::Rng;
 use crate::node::Node;
 use crate::economy::NetworkFund;
-use crate::memory::BrainEvent;  
 use crate::brain::Brain; 
+use crate::rng::SimRng;
+use crate::config::MiningConfig;
+use crate::checkpoint::CheckpointHandle;
+use crate::energy_ledger::{EnergyBudget, EnergyFlow, EnergyLedger};
+use crate::token_ledger::{Entry, TokenLedger, TokenReason};
+use crate::token_amount::TokenAmount;
+use crate::clock::SimClock;
+use crate::chain::{Block, Chain};
+use crate::payload::{BlockBodies, InclusionProof};
+use crate::network::Network;
+use crate::interaction::{BusStats, Message, MessageType, NetworkBus};
+use crate::peers::PeerInfo;
+use crate::message_log::MessageQuery;
 use serde_json::json;
-use tokio::sync::RwLockReadGuard;
 use crate::brain::BrainSnapshot;
   
   
@@ -25,13 +36,22 @@ pub struct AppState {
     pub fund: Arc<Mutex<NetworkFund>>,
     pub brain: Arc<RwLock<Brain>>,
     pub snapshot: Arc<RwLock<BrainSnapshot>>,
+    pub rng: Arc<Mutex<SimRng>>,
+    pub mining: MiningConfig,
+    pub checkpoints: CheckpointHandle,
+    pub energy: EnergyLedger,
+    pub tokens: TokenLedger,
+    pub clock: SimClock,
+    pub bodies: BlockBodies,
+    pub network: Network,
+    pub bus: Arc<NetworkBus>,
 } 
 
 #[derive(Serialize)]
 struct NodeInfo {
     name: String,
     energy: f64,
-    balance: f64,
+    balance: TokenAmount,
     efficiency: f64,
     altruism: f64,
     resilience: f64,
@@ -40,7 +60,7 @@ struct NodeInfo {
 #[derive(Serialize)]
 pub struct WalletInfo {
     pub name: String,
-    pub balance: f64,
+    pub balance: TokenAmount,
 } 
 pub async fn get_brain_memory(State(state): State<AppState>) -> Json<serde_json::Value> {
     // 1️⃣ Берём клон ссылки на память мозга (Arc<Mutex<Memory>>)
@@ -50,9 +70,10 @@ pub async fn get_brain_memory(State(state): State<AppState>) -> Json<serde_json:
     };
 
     // 2️⃣ Достаём копию последних событий с помощью get_recent() — он уже безопасный
-    let events = {
+    // долгая память — удачные действия, пережившие провалы
+    let (events, long) = {
         let memory = memory_arc.lock().await;
-        memory.get_recent(30).await
+        (memory.get_recent(30).await, memory.get_long(30).await)
     };
 
     // 3️⃣ Форматируем JSON
@@ -72,7 +93,8 @@ pub async fn get_brain_memory(State(state): State<AppState>) -> Json<serde_json:
 
     Json(json!({
         "status": "ok",
-        "recent_memory": formatted
+        "recent_memory": formatted,
+        "long_memory": long
     }))
 }
 
@@ -83,6 +105,7 @@ pub async fn get_brain_state(State(state): State<AppState>) -> Json<serde_json::
 
     Json(json!({
         "status": "ok",
+        "policy": snapshot.policy,
         "aggressiveness": snapshot.aggressiveness,
         "avg_recent_result": snapshot.avg_recent_result,
         "last_update": snapshot.last_update,
@@ -100,9 +123,243 @@ Router::new()
         .route("/update/:id", post(update_node))
         .route("/wallets", get(get_wallets))
         .route("/brain/memory", get(get_brain_memory))
+        .route("/checkpoint", post(create_checkpoint))
+        .route("/energy/budget", get(get_energy_budget))
+        .route("/tokens/supply", get(get_token_supply))
+        .route("/tokens/ledger", get(get_token_ledger))
+        .route("/transfer", post(transfer))
+        .route("/proof/block/:hash/:index", get(get_item_proof))
+        .route("/proof/token/:seq", get(get_token_proof))
+        .route("/network", get(get_network))
+        .route("/peers", get(get_peers))
+        .route("/bus", get(get_bus))
+        .route("/bus/topics", post(set_bus_topics))
+        .route("/help/:id", post(request_help))
+        .route("/messages", get(get_messages))
         .with_state(state)
 }
 
+/// 🌐 Сетевой режим: адрес, пиры и ноды других процессов
+async fn get_network(State(state): State<AppState>) -> Json<serde_json::Value> {
+    Json(state.network.status())
+}
+
+/// 📇 Таблица пиров: откуда известен, подключён ли, RTT, штраф и бан
+async fn get_peers(State(state): State<AppState>) -> Json<Vec<PeerInfo>> {
+    Json(state.network.peers())
+}
+
+/// 🚌 Шина: очередь, отправленные и потерянные сообщения, входящие очереди нод
+async fn get_bus(State(state): State<AppState>) -> Json<BusStats> {
+    Json(state.bus.stats())
+}
+
+#[derive(Deserialize)]
+struct TopicsRequest {
+    node: String,
+    topics: Vec<String>,
+}
+
+/// 🏷️ Заменить подписки ноды на темы шины
+async fn set_bus_topics(State(state): State<AppState>, Json(payload): Json<TopicsRequest>) -> Json<serde_json::Value> {
+    match state.bus.set_topics(&payload.node, &payload.topics) {
+        Ok(()) => Json(json!({ "status": "ok", "node": payload.node, "topics": payload.topics })),
+        Err(e) => Json(json!({ "status": "error", "error": e })),
+    }
+}
+
+/// 📨 Журнал сообщений шины: `?node=&type=&from_tick=&to_tick=&since=&until=&offset=&limit=`
+async fn get_messages(State(state): State<AppState>, Query(query): Query<MessageQuery>) -> Json<serde_json::Value> {
+    match state.bus.messages().query(&query) {
+        Ok(page) => {
+            let mut body = json!({ "status": "ok" });
+            if let (Some(body), Ok(serde_json::Value::Object(page))) = (body.as_object_mut(), serde_json::to_value(page)) {
+                body.extend(page);
+            }
+            Json(body)
+        }
+        Err(e) => Json(json!({ "status": "error", "error": e })),
+    }
+}
+
+/// 🙋 Нода просит помощи и ждёт первого предложения (или истечения срока запроса).
+/// Исполнен ли перевод, видно по событию `help_settled` и подтверждению помощнику.
+async fn request_help(State(state): State<AppState>, Path(id): Path<usize>) -> Json<serde_json::Value> {
+    let node = state.nodes.lock().await.get(id).cloned();
+    let Some(node) = node else {
+        return Json(json!({ "status": "error", "error": "нода не найдена" }));
+    };
+    let (name, identity) = {
+        let n = node.lock().await;
+        (n.name.clone(), n.identity.clone())
+    };
+    let msg = Message::new(&name, None, MessageType::HelpRequest, 0.0, Some("Мне нужна энергия ⚡"));
+    match state.bus.request(msg, &identity, state.clock.tick()).await {
+        Ok(reply) => Json(json!({
+            "status": "ok",
+            "node": name,
+            "request": reply.reply_to,
+            "offered_by": reply.from,
+            "amount": reply.value,
+        })),
+        Err(e) => Json(json!({ "status": "error", "node": name, "error": e.to_string() })),
+    }
+}
+
+/// 💾 Снять контрольную точку: планировщик сохранит мир на ближайшей границе тика
+async fn create_checkpoint(State(state): State<AppState>) -> Json<serde_json::Value> {
+    match state.checkpoints.request().await {
+        Ok((tick, path)) => Json(json!({
+            "status": "ok",
+            "tick": tick,
+            "path": path.display().to_string()
+        })),
+        Err(e) => Json(json!({
+            "status": "error",
+            "error": e
+        })),
+    }
+}
+
+/// 🔋 Откуда пришла и куда ушла энергия с начала прогона
+async fn get_energy_budget(State(state): State<AppState>) -> Json<EnergyBudget> {
+    let nodes = state.nodes.lock().await;
+    let mut total = 0.0;
+    for n in nodes.iter() {
+        let node = n.lock().await;
+        total += node.energy.lock().await.level;
+    }
+    Json(state.energy.budget(state.clock.tick(), total))
+}
+
+/// 🪙 Предложение токенов и сверка с остатками на счетах
+async fn get_token_supply(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let mut wallets = TokenAmount::ZERO;
+    for n in state.nodes.lock().await.iter() {
+        wallets = wallets.saturating_add(n.lock().await.wallet.get_balance().await);
+    }
+    let fund = state.fund.lock().await.get_balance().await;
+    let totals = state.tokens.totals();
+    let supply = state.tokens.supply();
+
+    Json(json!({
+        "tick": state.clock.tick(),
+        "minted": totals.minted,
+        "burned": totals.burned,
+        "supply": supply,
+        "wallets": wallets,
+        "fund": fund,
+        // целые единицы сходятся точно
+        "balanced": wallets.checked_add(fund) == Some(supply),
+        "by_reason": totals.by_reason
+    }))
+}
+
+#[derive(Deserialize)]
+struct LedgerQuery {
+    limit: Option<usize>,
+}
+
+/// 📗 Последние проводки книги токенов (`?limit=N`, по умолчанию 100)
+async fn get_token_ledger(
+    State(state): State<AppState>,
+    Query(query): Query<LedgerQuery>,
+) -> Json<Vec<Entry>> {
+    Json(state.tokens.recent(query.limit.unwrap_or(100)))
+}
+
+#[derive(Deserialize)]
+struct TransferRequest {
+    from: String,
+    to: String,
+    amount: TokenAmount,
+}
+
+/// 🔁 Перевести токены между кошельками нод
+async fn transfer(State(state): State<AppState>, Json(payload): Json<TransferRequest>) -> Json<serde_json::Value> {
+    let nodes = state.nodes.lock().await;
+    let mut from = None;
+    let mut to = None;
+    for n in nodes.iter() {
+        let node = n.lock().await;
+        if node.name == payload.from {
+            from = Some(node.wallet.clone());
+        }
+        if node.name == payload.to {
+            to = Some(node.wallet.clone());
+        }
+    }
+
+    let (Some(from), Some(to)) = (from, to) else {
+        return Json(json!({ "status": "error", "error": "нода не найдена" }));
+    };
+    if from.transfer_to(&to, payload.amount, TokenReason::Transfer).await {
+        Json(json!({
+            "status": "ok",
+            "from_balance": from.get_balance().await,
+            "to_balance": to.get_balance().await
+        }))
+    } else {
+        Json(json!({ "status": "error", "error": "перевод отклонён" }))
+    }
+}
+
+/// Общая `key_chain` (у всех нод она одна — берём у первой живой)
+async fn key_chain(state: &AppState) -> Option<Arc<Mutex<Chain>>> {
+    let first = state.nodes.lock().await.first().cloned()?;
+    let chain = first.lock().await.key_chain.clone();
+    Some(chain)
+}
+
+fn proof_json(block: &Block, proof: Option<InclusionProof>) -> Json<serde_json::Value> {
+    match proof {
+        Some(proof) => Json(json!({
+            "status": "ok",
+            "valid": proof.verify(),
+            "block": block,
+            "proof": proof
+        })),
+        None => Json(json!({ "status": "error", "error": "элемента нет в блоке" })),
+    }
+}
+
+/// 🧾 Доказательство включения элемента `index` в ключевой блок `hash`
+async fn get_item_proof(
+    State(state): State<AppState>,
+    Path((hash, index)): Path<(String, usize)>,
+) -> Json<serde_json::Value> {
+    let block = match key_chain(&state).await {
+        Some(chain) => chain.lock().await.block_by_hash(&hash),
+        None => Ok(None),
+    };
+    match block {
+        Ok(Some(block)) => proof_json(&block, state.bodies.prove(&block, index)),
+        Ok(None) => Json(json!({ "status": "error", "error": "блока нет в key_chain" })),
+        Err(e) => Json(json!({ "status": "error", "error": e.to_string() })),
+    }
+}
+
+/// 🧾 Доказательство включения проводки токенов с номером `seq`
+async fn get_token_proof(State(state): State<AppState>, Path(seq): Path<u64>) -> Json<serde_json::Value> {
+    let block = match key_chain(&state).await {
+        Some(chain) => {
+            // тела блоков ищутся по хэшам из индекса, читается только найденный блок
+            let chain = chain.lock().await;
+            let hashes = (0..chain.height()).filter_map(|height| chain.hash_at(height));
+            match state.bodies.find_token_tx(hashes, seq) {
+                Some((hash, index)) => chain.block_by_hash(&hash).map(|block| block.map(|b| (b, index))),
+                None => Ok(None),
+            }
+        }
+        None => Ok(None),
+    };
+    match block {
+        Ok(Some((block, index))) => proof_json(&block, state.bodies.prove(&block, index)),
+        Ok(None) => Json(json!({ "status": "error", "error": "проводка ещё не попала в блок" })),
+        Err(e) => Json(json!({ "status": "error", "error": e.to_string() })),
+    }
+}
+
 async fn root() -> &'static str {
     "🧬 Organism API is running"
 }
@@ -155,21 +412,25 @@ async fn mine_block(State(state): State<AppState>, Path(id): Path<usize>) -> Jso
         let n = node.lock().await;
 
         // ⛏️ Симуляция майнинга блока
-        let reward = 15.0;
-        let validator_cut = 3.0;
-        let fund_cut = 2.0;
+        let reward = state.mining.reward;
+        let validator_cut = state.mining.validator_cut;
+        let fund_cut = state.mining.fund_cut;
 
         // 💰 Майнер получает вознаграждение
-        n.wallet.reward(reward).await;
+        n.wallet.reward(reward, TokenReason::BlockReward).await;
 
         // 🔍 Выбираем случайного валидатора
-        if let Some(validator) = nodes.get(rand::random::<usize>() % nodes.len()) {
+        let validator_idx = state.rng.lock().await.gen_range(0..nodes.len());
+        if validator_idx == id {
+            // майнер сам оказался валидатором — повторный lock() повис бы
+            n.wallet.reward(validator_cut, TokenReason::ValidatorReward).await;
+        } else if let Some(validator) = nodes.get(validator_idx) {
             let v = validator.lock().await;
-            v.wallet.reward(validator_cut).await;
+            v.wallet.reward(validator_cut, TokenReason::ValidatorReward).await;
         }
 
         // 🏦 Добавляем в фонд
-        state.fund.lock().await.add(fund_cut);
+        state.fund.lock().await.add(fund_cut, TokenReason::MiningFundCut).await;
 
         let response = format!(
             "⛏️ Блок добыт нодой {}: +{:.2} токенов, фонд +{:.2}",
@@ -182,9 +443,9 @@ async fn mine_block(State(state): State<AppState>, Path(id): Path<usize>) -> Jso
 }
 
 /// Получить цепочку блоков
-async fn get_chain(State(state): State<AppState>) -> Json<Vec<String>> {
+async fn get_chain(State(state): State<AppState>, Path(id): Path<usize>) -> Json<Vec<String>> {
     let nodes = state.nodes.lock().await;
-    if let Some(node) = nodes.first() {
+    if let Some(node) = nodes.get(id) {
         let n = node.lock().await;
         let chain = n.get_chain_summary().await;
         Json(chain)
@@ -211,7 +472,7 @@ async fn update_node(
         let mut n = node.lock().await;
         if let Some(e) = payload.energy {
             let mut energy = n.energy.lock().await;
-            energy.level = e;
+            energy.set_level(e, EnergyFlow::Manual);
         }
         if let Some(v) = payload.efficiency {
             n.efficiency = v;
@@ -222,11 +483,13 @@ async fn update_node(
         if let Some(v) = payload.resilience {
             n.resilience = v;
         }
+        n.log_traits();
 
-        let fee = 1.0;
-        let fund_cut = 0.5; 
-        state.fund.lock().await.add(fund_cut);
-        n.wallet.reward(fee - fund_cut).await;
+        let fee = state.mining.update_fee;
+        let fund_cut = state.mining.update_fund_cut;
+        state.fund.lock().await.add(fund_cut, TokenReason::UpdateFundCut).await;
+        // update_fund_cut <= update_fee проверено при загрузке конфига
+        n.wallet.reward(fee.saturating_sub(fund_cut), TokenReason::UpdateFee).await;
 
         Json(format!("✅ Node {} updated", n.name))
     } else {
@@ -0,0 +1,247 @@
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::scheduler::Scheduler;
+use crate::world::World;
+use crate::token_amount::TokenAmount;
+
+/// Параметры пакетного (headless) прогона: `--headless`, `--ticks N`,
+/// `--report путь.json|csv`, `--sample-every N`
+#[derive(Clone, Debug)]
+pub struct BatchOptions {
+    /// сколько тиков пройти в этом прогоне; после `--restore` — сверх восстановленного тика
+    pub ticks: u64,
+    pub report_path: PathBuf,
+    pub sample_every: u64,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self { ticks: 1000, report_path: PathBuf::from("report.json"), sample_every: 1 }
+    }
+}
+
+/// Одна точка временного ряда
+#[derive(Clone, Debug, Serialize)]
+pub struct TickSample {
+    pub tick: u64,
+    pub population: usize,
+    pub avg_energy: f64,
+    pub fund_balance: TokenAmount,
+    pub data_chain_height: usize,
+    pub key_chain_height: usize,
+    pub aggressiveness: f64,
+}
+
+impl TickSample {
+    pub async fn collect(world: &World) -> Self {
+        let nodes = world.nodes.lock().await.clone();
+
+        let mut total_energy = 0.0;
+        let mut data_chain_height = 0;
+        let mut key_chain_height = 0;
+        for n in nodes.iter() {
+            let node = n.lock().await;
+            total_energy += node.energy.lock().await.level;
+            data_chain_height = data_chain_height.max(node.data_chain.lock().await.blocks.len());
+            key_chain_height = key_chain_height.max(node.key_chain.lock().await.blocks.len());
+        }
+
+        let avg_energy = if nodes.is_empty() { 0.0 } else { total_energy / nodes.len() as f64 };
+        let fund_balance = world.fund.lock().await.get_balance().await;
+        let aggressiveness = world.brain.read().await.aggressiveness;
+
+        Self {
+            tick: world.clock.tick(),
+            population: nodes.len(),
+            avg_energy,
+            fund_balance,
+            data_chain_height,
+            key_chain_height,
+            aggressiveness,
+        }
+    }
+}
+
+/// 📊 Итог пакетного прогона
+#[derive(Clone, Debug, Serialize)]
+pub struct RunReport {
+    pub seed: u64,
+    /// тик, с которого начался прогон (0 или тик контрольной точки)
+    pub start_tick: u64,
+    pub ticks_requested: u64,
+    /// тиков пройдено в этом прогоне
+    pub ticks_run: u64,
+    /// тик, на котором вымерла последняя нода
+    pub extinct_at: Option<u64>,
+    pub samples: Vec<TickSample>,
+}
+
+impl RunReport {
+    /// Записать отчёт: `.csv` — временной ряд таблицей, иначе JSON целиком
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let is_csv = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("csv"))
+            .unwrap_or(false);
+
+        let mut file = File::create(path)?;
+        if is_csv {
+            writeln!(
+                file,
+                "tick,population,avg_energy,fund_balance,data_chain_height,key_chain_height,aggressiveness"
+            )?;
+            for s in &self.samples {
+                writeln!(
+                    file,
+                    "{},{},{:.4},{:.4},{},{},{:.4}",
+                    s.tick,
+                    s.population,
+                    s.avg_energy,
+                    s.fund_balance,
+                    s.data_chain_height,
+                    s.key_chain_height,
+                    s.aggressiveness
+                )?;
+            }
+        } else {
+            let json = serde_json::to_string_pretty(self)?;
+            file.write_all(json.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// 🧪 Прогнать организм `options.ticks` тиков от текущего (или до вымирания) без API
+pub async fn run(mut scheduler: Scheduler, seed: u64, options: &BatchOptions) -> RunReport {
+    let world = scheduler.world.clone();
+    let start = scheduler.tick();
+    let end = start + options.ticks;
+    let mut report = RunReport {
+        seed,
+        start_tick: start,
+        ticks_requested: options.ticks,
+        ticks_run: 0,
+        extinct_at: None,
+        samples: vec![TickSample::collect(&world).await],
+    };
+
+    while scheduler.tick() < end {
+        scheduler.step().await;
+        let tick = scheduler.tick();
+        report.ticks_run = tick - start;
+
+        let population = world.nodes.lock().await.len();
+        if tick.is_multiple_of(options.sample_every) || population == 0 || tick == end {
+            report.samples.push(TickSample::collect(&world).await);
+        }
+
+        if population == 0 {
+            println!("☠️ Организм вымер на тике {}", tick);
+            report.extinct_at = Some(tick);
+            break;
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brain_policy::PolicyKind;
+    use crate::config::Config;
+    use crate::rng::Seed;
+
+    async fn scheduler(config: &Config) -> Scheduler {
+        let world = crate::test_world(config).await;
+        Scheduler::new(
+            config.scheduler.clone(),
+            config.economy,
+            config.consensus,
+            config.checkpoint.clone(),
+            Seed::new(1),
+            world,
+        )
+    }
+
+    fn small() -> Config {
+        let mut config = Config::default();
+        config.simulation.initial_nodes = 3;
+        config.population.cull_above = 8;
+        config.population.cull_keep = 6;
+        config
+    }
+
+    fn options(ticks: u64, sample_every: u64, report: &str) -> BatchOptions {
+        let report_path = std::env::temp_dir().join(format!("organism-batch-{}-{}", std::process::id(), report));
+        BatchOptions { ticks, report_path, sample_every }
+    }
+
+    #[tokio::test]
+    async fn samples_every_n_ticks_and_the_last_one() {
+        let report = run(scheduler(&small()).await, 1, &options(10, 4, "unused")).await;
+        assert_eq!((report.start_tick, report.ticks_run, report.extinct_at), (0, 10, None));
+        let ticks: Vec<u64> = report.samples.iter().map(|s| s.tick).collect();
+        assert_eq!(ticks, vec![0, 4, 8, 10]);
+    }
+
+    #[tokio::test]
+    async fn ticks_count_from_where_the_run_starts() {
+        let mut scheduler = scheduler(&small()).await;
+        for _ in 0..5 {
+            scheduler.step().await;
+        }
+        let world = scheduler.world.clone();
+        let report = run(scheduler, 1, &options(10, 4, "unused")).await;
+        assert_eq!((report.start_tick, report.ticks_requested, report.ticks_run), (5, 10, 10));
+        assert_eq!(world.clock.tick(), 15);
+        let ticks: Vec<u64> = report.samples.iter().map(|s| s.tick).collect();
+        assert_eq!(ticks, vec![5, 8, 12, 15]);
+    }
+
+    #[tokio::test]
+    async fn extinction_stops_the_run() {
+        let mut config = small();
+        // все ноды ниже порога смерти и без потомков — первый же цикл эволюции мозга их удаляет
+        config.population.death_energy = 1000.0;
+        config.node.replication_threshold = f64::INFINITY;
+        config.brain.policy = PolicyKind::Threshold;
+        let s = &mut config.scheduler;
+        (s.help_every, s.nodes_every, s.consensus_every, s.evolution_every, s.economy_every) = (0, 0, 0, 0, 0);
+        s.brain_every = 3;
+
+        let report = run(scheduler(&config).await, 1, &options(100, 10, "unused")).await;
+        assert_eq!((report.ticks_run, report.extinct_at), (3, Some(3)));
+        let last = report.samples.last().unwrap();
+        assert_eq!((last.tick, last.population, last.avg_energy), (3, 0, 0.0));
+        assert_eq!(report.samples.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn csv_report_has_a_row_per_sample() {
+        let options = options(6, 2, "report.csv");
+        let report = run(scheduler(&small()).await, 1, &options).await;
+        report.write(&options.report_path).unwrap();
+        let csv = std::fs::read_to_string(&options.report_path).unwrap();
+        let _ = std::fs::remove_file(&options.report_path);
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "tick,population,avg_energy,fund_balance,data_chain_height,key_chain_height,aggressiveness"
+        );
+        assert_eq!(lines.len(), 1 + report.samples.len());
+        for (line, sample) in lines[1..].iter().zip(report.samples.iter()) {
+            let cells: Vec<&str> = line.split(',').collect();
+            assert_eq!(cells.len(), 7);
+            assert_eq!(cells[0], sample.tick.to_string());
+            assert_eq!(cells[1], sample.population.to_string());
+            assert_eq!(cells[3].parse::<TokenAmount>().unwrap(), sample.fund_balance);
+        }
+        let ticks: Vec<&str> = lines[1..].iter().map(|l| l.split(',').next().unwrap()).collect();
+        assert_eq!(ticks, vec!["0", "2", "4", "6"]);
+    }
+}
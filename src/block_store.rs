@@ -0,0 +1,536 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::chain::{Block, ChainError};
+
+/// Запись в сегменте: длина (u32 LE) ‖ контрольная сумма (8 байт SHA-256) ‖ блок в JSON
+const RECORD_HEADER: usize = 4 + CHECKSUM_LEN;
+const CHECKSUM_LEN: usize = 8;
+
+/// Запись индекса: сегмент (u32) ‖ длина (u32) ‖ смещение (u64) ‖ хэш блока (32 байта)
+const INDEX_ENTRY: usize = 4 + 4 + 8 + 32;
+const INDEX_FILE: &str = "index.bin";
+
+/// Где лежит блок: номер сегмента, смещение записи и длина JSON
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Location {
+    segment: u32,
+    offset: u64,
+    len: u32,
+    hash: [u8; 32],
+}
+
+impl Location {
+    /// Смещение сразу после записи
+    fn end(&self) -> u64 {
+        self.offset + RECORD_HEADER as u64 + self.len as u64
+    }
+
+    fn encode(&self) -> [u8; INDEX_ENTRY] {
+        let mut out = [0u8; INDEX_ENTRY];
+        out[0..4].copy_from_slice(&self.segment.to_le_bytes());
+        out[4..8].copy_from_slice(&self.len.to_le_bytes());
+        out[8..16].copy_from_slice(&self.offset.to_le_bytes());
+        out[16..48].copy_from_slice(&self.hash);
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        Self {
+            segment: u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+            len: u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+            offset: u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+            hash: bytes[16..48].try_into().unwrap(),
+        }
+    }
+}
+
+/// Что пришлось исправить при открытии хранилища
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Recovery {
+    /// записи, найденные в сегментах, но не попавшие в индекс
+    pub reindexed: u64,
+    /// байты оборванной или испорченной записи в конце, которые отрезаны
+    pub truncated_bytes: u64,
+}
+
+/// 💽 Хранилище блоков одной цепи: блоки дописываются в конец сегментных
+/// файлов `seg-NNNNNNNN.log`, рядом — индекс `index.bin` с записями
+/// фиксированной длины (номер блока = номер записи), в памяти — ещё и по хэшу.
+///
+/// Порядок записи: сначала сегмент, потом индекс, и при `fsync` каждый шаг
+/// доходит до диска до следующего. Поэтому после сбоя индекс может только
+/// отставать от сегментов: при открытии недостающие записи дочитываются,
+/// а оборванная последняя запись (не хватает байтов или не сходится
+/// контрольная сумма) отрезается.
+pub struct BlockStore {
+    dir: PathBuf,
+    fsync: bool,
+    segment_bytes: u64,
+    index: Vec<Location>,
+    by_hash: HashMap<[u8; 32], u64>,
+    index_file: File,
+    /// сегмент, в который идёт запись
+    segment: u32,
+    segment_file: File,
+    segment_len: u64,
+    recovery: Recovery,
+}
+
+impl BlockStore {
+    /// Открыть (или создать) хранилище в каталоге `dir`. Новый сегмент
+    /// начинается, когда текущий перерастает `segment_bytes`.
+    pub fn open(dir: &Path, fsync: bool, segment_bytes: u64) -> Result<Self, ChainError> {
+        fs::create_dir_all(dir).map_err(ChainError::Io)?;
+        let mut segments = list_segments(dir)?;
+        if segments.is_empty() {
+            segments.push(0);
+        }
+        // пишем пока в первый сегмент; после проверки хвоста — в последний
+        let first = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(dir.join(segment_name(segments[0])))
+            .map_err(ChainError::Io)?;
+
+        let index_path = dir.join(INDEX_FILE);
+        let index_file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&index_path)
+            .map_err(ChainError::Io)?;
+        let raw = fs::read(&index_path).map_err(ChainError::Io)?;
+
+        // индексу верим, пока записи идут подряд и не выходят за конец сегментов
+        let mut sizes = HashMap::new();
+        for &id in segments.iter() {
+            sizes.insert(id, segment_size(dir, id)?);
+        }
+        let mut index: Vec<Location> = Vec::with_capacity(raw.len() / INDEX_ENTRY);
+        for chunk in raw.chunks_exact(INDEX_ENTRY) {
+            let loc = Location::decode(chunk);
+            let follows = match index.last() {
+                Some(prev) if prev.segment == loc.segment => loc.offset == prev.end(),
+                Some(prev) => loc.segment > prev.segment && loc.offset == 0,
+                None => loc.segment == segments[0] && loc.offset == 0,
+            };
+            if !follows || sizes.get(&loc.segment).is_none_or(|&size| loc.end() > size) {
+                break;
+            }
+            index.push(loc);
+        }
+
+        let by_hash = index.iter().enumerate().map(|(h, loc)| (loc.hash, h as u64)).collect();
+        let mut store = Self {
+            dir: dir.to_path_buf(),
+            fsync,
+            segment_bytes: segment_bytes.max(1),
+            index,
+            by_hash,
+            segment: segments[0],
+            segment_file: first,
+            segment_len: 0,
+            index_file,
+            recovery: Recovery::default(),
+        };
+        store.rewrite_index_len()?;
+        store.recover_tail(&segments)?;
+        Ok(store)
+    }
+
+    /// Дочитать записи после последней проиндексированной; первая битая
+    /// запись и всё после неё отрезаются
+    fn recover_tail(&mut self, segments: &[u32]) -> Result<(), ChainError> {
+        let (mut segment, mut offset) = match self.index.last() {
+            Some(loc) => (loc.segment, loc.end()),
+            None => (segments[0], 0),
+        };
+
+        loop {
+            let bytes = fs::read(self.segment_path(segment)).unwrap_or_default();
+            let mut torn = false;
+            while (offset as usize) < bytes.len() {
+                match parse_record(&bytes[offset as usize..]) {
+                    Some((block, len)) if block.index == self.index.len() as u64 => {
+                        let loc = Location { segment, offset, len, hash: decode_hash(&block.hash)? };
+                        self.push_index(loc)?;
+                        self.recovery.reindexed += 1;
+                        offset = loc.end();
+                    }
+                    _ => {
+                        torn = true;
+                        break;
+                    }
+                }
+            }
+
+            let next = segments.iter().copied().find(|&id| id > segment);
+            if torn {
+                self.recovery.truncated_bytes += bytes.len() as u64 - offset;
+                self.cut_after(segment, offset, segments)?;
+                break;
+            }
+            match next {
+                Some(id) => {
+                    segment = id;
+                    offset = 0;
+                }
+                None => break,
+            }
+        }
+
+        self.open_segment(segment)?;
+        if self.fsync {
+            self.index_file.sync_data().map_err(ChainError::Io)?;
+        }
+        Ok(())
+    }
+
+    /// Обрезать сегмент `segment` до `offset` и удалить все сегменты после него
+    fn cut_after(&mut self, segment: u32, offset: u64, segments: &[u32]) -> Result<(), ChainError> {
+        let file = OpenOptions::new().write(true).open(self.segment_path(segment)).map_err(ChainError::Io)?;
+        file.set_len(offset).map_err(ChainError::Io)?;
+        if self.fsync {
+            file.sync_data().map_err(ChainError::Io)?;
+        }
+        for &id in segments.iter().filter(|&&id| id > segment) {
+            let path = self.segment_path(id);
+            if let Ok(len) = fs::metadata(&path).map(|m| m.len()) {
+                self.recovery.truncated_bytes += len;
+            }
+            fs::remove_file(path).map_err(ChainError::Io)?;
+        }
+        self.sync_dir()
+    }
+
+    /// Итог восстановления при открытии
+    pub fn recovery(&self) -> Recovery {
+        self.recovery
+    }
+
+    /// Сколько блоков в хранилище
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Хэш блока с номером `height` (hex) — без чтения сегмента
+    pub fn hash_at(&self, height: u64) -> Option<String> {
+        self.index.get(height as usize).map(|loc| hex::encode(loc.hash))
+    }
+
+    /// Номер блока по его хэшу (hex)
+    pub fn height_of(&self, hash: &str) -> Option<u64> {
+        let key = decode_hash(hash).ok()?;
+        self.by_hash.get(&key).copied()
+    }
+
+    /// Прочитать блок с номером `height`
+    pub fn get(&self, height: u64) -> Result<Option<Block>, ChainError> {
+        let Some(loc) = self.index.get(height as usize).copied() else {
+            return Ok(None);
+        };
+        let mut file = File::open(self.segment_path(loc.segment)).map_err(ChainError::Io)?;
+        file.seek(SeekFrom::Start(loc.offset)).map_err(ChainError::Io)?;
+        let mut bytes = vec![0u8; RECORD_HEADER + loc.len as usize];
+        std::io::Read::read_exact(&mut file, &mut bytes).map_err(ChainError::Io)?;
+        self.check_record(height, &loc, &bytes).map(Some)
+    }
+
+    pub fn get_by_hash(&self, hash: &str) -> Result<Option<Block>, ChainError> {
+        match self.height_of(hash) {
+            Some(height) => self.get(height),
+            None => Ok(None),
+        }
+    }
+
+    /// Все блоки по порядку: каждый сегмент читается целиком один раз
+    pub fn read_all(&self) -> Result<Vec<Block>, ChainError> {
+        let mut blocks = Vec::with_capacity(self.index.len());
+        let mut loaded: Option<(u32, Vec<u8>)> = None;
+        for (height, loc) in self.index.iter().enumerate() {
+            if loaded.as_ref().map(|(id, _)| *id) != Some(loc.segment) {
+                let bytes = fs::read(self.segment_path(loc.segment)).map_err(ChainError::Io)?;
+                loaded = Some((loc.segment, bytes));
+            }
+            let bytes = &loaded.as_ref().unwrap().1;
+            let record = bytes
+                .get(loc.offset as usize..loc.end() as usize)
+                .ok_or_else(|| ChainError::Format(format!("блок #{} за концом сегмента", height)))?;
+            blocks.push(self.check_record(height as u64, loc, record)?);
+        }
+        Ok(blocks)
+    }
+
+    /// ✍️ Дописать блок в конец. Блок должен продолжать хранилище по номеру.
+    pub fn append(&mut self, block: &Block) -> Result<(), ChainError> {
+        if block.index != self.index.len() as u64 {
+            return Err(ChainError::BadIndex { position: self.index.len(), found: block.index });
+        }
+        let payload = serde_json::to_vec(block).map_err(|e| ChainError::Format(e.to_string()))?;
+        let len = u32::try_from(payload.len()).map_err(|_| ChainError::Format("блок больше 4 ГиБ".into()))?;
+        let mut record = Vec::with_capacity(RECORD_HEADER + payload.len());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&checksum(&payload));
+        record.extend_from_slice(&payload);
+
+        if self.segment_len > 0 && self.segment_len + record.len() as u64 > self.segment_bytes {
+            self.open_segment(self.segment + 1)?;
+            self.sync_dir()?;
+        }
+
+        let loc = Location { segment: self.segment, offset: self.segment_len, len, hash: decode_hash(&block.hash)? };
+        self.segment_file.write_all(&record).map_err(ChainError::Io)?;
+        if self.fsync {
+            self.segment_file.sync_data().map_err(ChainError::Io)?;
+        }
+        self.segment_len += record.len() as u64;
+
+        self.push_index(loc)?;
+        if self.fsync {
+            self.index_file.sync_data().map_err(ChainError::Io)?;
+        }
+        Ok(())
+    }
+
+    /// ✂️ Оставить первые `keep` блоков. Сначала укорачивается индекс, потом
+    /// сегменты — индекс никогда не указывает на удалённые байты.
+    pub fn truncate(&mut self, keep: usize) -> Result<(), ChainError> {
+        let Some(first) = self.index.get(keep).copied() else {
+            return Ok(());
+        };
+        for loc in self.index.drain(keep..) {
+            self.by_hash.remove(&loc.hash);
+        }
+        self.rewrite_index_len()?;
+
+        let segments = list_segments(&self.dir)?;
+        self.cut_after(first.segment, first.offset, &segments)?;
+        self.open_segment(first.segment)
+    }
+
+    fn push_index(&mut self, loc: Location) -> Result<(), ChainError> {
+        self.index_file.write_all(&loc.encode()).map_err(ChainError::Io)?;
+        self.by_hash.insert(loc.hash, self.index.len() as u64);
+        self.index.push(loc);
+        Ok(())
+    }
+
+    /// Длина файла индекса — ровно по принятым записям
+    fn rewrite_index_len(&mut self) -> Result<(), ChainError> {
+        self.index_file
+            .set_len((self.index.len() * INDEX_ENTRY) as u64)
+            .map_err(ChainError::Io)?;
+        if self.fsync {
+            self.index_file.sync_data().map_err(ChainError::Io)?;
+        }
+        Ok(())
+    }
+
+    /// Сделать `id` сегментом для записи (создаётся, если его нет)
+    fn open_segment(&mut self, id: u32) -> Result<(), ChainError> {
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(self.segment_path(id))
+            .map_err(ChainError::Io)?;
+        self.segment_len = file.metadata().map_err(ChainError::Io)?.len();
+        self.segment_file = file;
+        self.segment = id;
+        Ok(())
+    }
+
+    /// Созданный или удалённый файл переживает сбой, только если записан каталог
+    fn sync_dir(&self) -> Result<(), ChainError> {
+        if self.fsync {
+            File::open(&self.dir).and_then(|d| d.sync_all()).map_err(ChainError::Io)?;
+        }
+        Ok(())
+    }
+
+    fn segment_path(&self, id: u32) -> PathBuf {
+        self.dir.join(segment_name(id))
+    }
+
+    /// Проверить запись, на которую указывает индекс, и разобрать блок
+    fn check_record(&self, height: u64, loc: &Location, record: &[u8]) -> Result<Block, ChainError> {
+        let corrupt = |what: &str| ChainError::Format(format!("блок #{} в {}: {}", height, segment_name(loc.segment), what));
+        let (block, _) = parse_record(record).ok_or_else(|| corrupt("контрольная сумма не сходится"))?;
+        if decode_hash(&block.hash)? != loc.hash {
+            return Err(corrupt("хэш не совпадает с индексом"));
+        }
+        Ok(block)
+    }
+}
+
+/// Разобрать запись в начале `bytes`: `None`, если она оборвана или испорчена
+fn parse_record(bytes: &[u8]) -> Option<(Block, u32)> {
+    let header = bytes.get(..RECORD_HEADER)?;
+    let len = u32::from_le_bytes(header[0..4].try_into().ok()?);
+    let payload = bytes.get(RECORD_HEADER..RECORD_HEADER + len as usize)?;
+    if checksum(payload) != header[4..RECORD_HEADER] {
+        return None;
+    }
+    let block = serde_json::from_slice(payload).ok()?;
+    Some((block, len))
+}
+
+fn checksum(payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    Sha256::digest(payload)[..CHECKSUM_LEN].try_into().unwrap()
+}
+
+fn decode_hash(hash: &str) -> Result<[u8; 32], ChainError> {
+    hex::decode(hash)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| ChainError::Format(format!("хэш блока {} не является SHA-256", hash)))
+}
+
+fn segment_name(id: u32) -> String {
+    format!("seg-{:08}.log", id)
+}
+
+fn segment_size(dir: &Path, id: u32) -> Result<u64, ChainError> {
+    fs::metadata(dir.join(segment_name(id))).map(|m| m.len()).map_err(ChainError::Io)
+}
+
+/// Номера сегментов в каталоге по возрастанию
+fn list_segments(dir: &Path) -> Result<Vec<u32>, ChainError> {
+    let mut ids: Vec<u32> = fs::read_dir(dir)
+        .map_err(ChainError::Io)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let name = name.to_str()?;
+            name.strip_prefix("seg-")?.strip_suffix(".log")?.parse().ok()
+        })
+        .collect();
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::Identity;
+    use crate::rng::Seed;
+
+    /// Чистый каталог для хранилища теста
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("organism-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Генезис и `count` блоков поверх него
+    fn chain(count: u64) -> Vec<Block> {
+        let signer = Identity::derive("node0", &Seed::new(1).stream("node/node0"));
+        let mut blocks = vec![Block::genesis()];
+        for index in 1..=count {
+            let prev = blocks.last().unwrap().hash.clone();
+            blocks.push(Block::new(index, prev, index as i64, "d".into(), "k".into(), &signer, 1.0));
+        }
+        blocks
+    }
+
+    fn filled(dir: &Path, segment_bytes: u64, blocks: &[Block]) {
+        let mut store = BlockStore::open(dir, false, segment_bytes).unwrap();
+        for block in blocks {
+            store.append(block).unwrap();
+        }
+    }
+
+    #[test]
+    fn torn_tail_is_cut_off() {
+        let dir = scratch("torn-tail");
+        let blocks = chain(3);
+        filled(&dir, 1 << 20, &blocks);
+        let segment = dir.join(segment_name(0));
+        let size = fs::metadata(&segment).unwrap().len();
+
+        // процесс убит посреди записи: заголовок есть, данных не хватает
+        let mut file = OpenOptions::new().append(true).open(&segment).unwrap();
+        file.write_all(&[200, 0, 0, 0, 1, 2, 3]).unwrap();
+        drop(file);
+
+        let mut store = BlockStore::open(&dir, false, 1 << 20).unwrap();
+        assert_eq!(store.len(), 4);
+        assert_eq!(store.recovery().truncated_bytes, 7);
+        assert_eq!(fs::metadata(&segment).unwrap().len(), size);
+        assert_eq!(store.get(3).unwrap().unwrap().hash, blocks[3].hash);
+
+        // после обрезки хранилище продолжает писаться с того же места
+        let block = &chain(4)[4];
+        store.append(block).unwrap();
+        assert_eq!(store.height_of(&block.hash), Some(4));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn corrupt_last_record_is_dropped() {
+        let dir = scratch("corrupt-tail");
+        let blocks = chain(2);
+        filled(&dir, 1 << 20, &blocks);
+        let segment = dir.join(segment_name(0));
+        let mut bytes = fs::read(&segment).unwrap();
+        let last = bytes.len() - 2;
+        bytes[last] ^= 0xff;
+        fs::write(&segment, &bytes).unwrap();
+        // индекс указывает на испорченную запись — ей больше не верят
+        let index = dir.join(INDEX_FILE);
+        let entries = fs::read(&index).unwrap();
+        fs::write(&index, &entries[..2 * INDEX_ENTRY]).unwrap();
+
+        let store = BlockStore::open(&dir, false, 1 << 20).unwrap();
+        assert_eq!(store.len(), 2);
+        assert!(store.recovery().truncated_bytes > 0);
+        let hashes: Vec<_> = store.read_all().unwrap().into_iter().map(|b| b.hash).collect();
+        assert_eq!(hashes, [blocks[0].hash.clone(), blocks[1].hash.clone()]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn index_is_rebuilt_from_segments() {
+        let dir = scratch("reindex");
+        let blocks = chain(5);
+        // маленькие сегменты: блоки расходятся по нескольким файлам
+        filled(&dir, 600, &blocks);
+        assert!(list_segments(&dir).unwrap().len() > 1);
+
+        fs::write(dir.join(INDEX_FILE), b"").unwrap();
+        let store = BlockStore::open(&dir, false, 600).unwrap();
+        assert_eq!(store.len(), blocks.len());
+        assert_eq!(store.recovery().reindexed, blocks.len() as u64);
+        assert_eq!(store.recovery().truncated_bytes, 0);
+        for block in blocks.iter() {
+            assert_eq!(store.height_of(&block.hash), Some(block.index));
+            assert_eq!(store.get_by_hash(&block.hash).unwrap().map(|b| b.index), Some(block.index));
+        }
+        assert_eq!(fs::metadata(dir.join(INDEX_FILE)).unwrap().len(), (blocks.len() * INDEX_ENTRY) as u64);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn lagging_index_is_completed() {
+        let dir = scratch("lagging-index");
+        let blocks = chain(4);
+        filled(&dir, 1 << 20, &blocks);
+        // сбой между записью в сегмент и в индекс: два последних блока без индекса
+        let index = dir.join(INDEX_FILE);
+        let entries = fs::read(&index).unwrap();
+        fs::write(&index, &entries[..3 * INDEX_ENTRY]).unwrap();
+
+        let store = BlockStore::open(&dir, false, 1 << 20).unwrap();
+        assert_eq!(store.len(), 5);
+        assert_eq!(store.recovery().reindexed, 2);
+        assert_eq!(store.hash_at(4), Some(blocks[4].hash.clone()));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
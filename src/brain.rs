@@ -1,48 +1,50 @@
 use std::sync::Arc;
 use serde::{Serialize}; 
-use tokio::time::{timeout, interval, Duration}; 
 use crate::memory::{Memory, BrainEvent}; 
-use chrono::Utc; 
-use rand::{Rng, SeedableRng};
-use rand::rngs::StdRng;
+use crate::clock::SimClock;
+use crate::config::PopulationConfig;
+use crate::events::{DeathCause, Event, EventLog};
+use crate::energy_ledger::EnergyFlow;
+use crate::brain_policy::{self, BrainAction, BrainPolicy, Observation};
+use rand::Rng;
 
 use crate::interaction::NetworkBus;
 use crate::economy::NetworkFund; 
+use crate::rng::SimRng;
 use tokio::sync::Mutex; 
 
 use crate::node::Node; 
 use tokio::sync::RwLock;  
-use tokio::sync::{Semaphore};
-use futures::future::join_all;
-use rand::thread_rng;
 
  
 
 /// 🧠 Модуль сознания — координация действий между нодами. 
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, Debug)]
 pub struct NodeEnergyInfo {
     pub name: String,
     pub energy: f64,
     pub experience: f64,
 }
 
-#[derive(Serialize, Clone)]
-pub struct BrainState {
-    pub nodes: Vec<NodeEnergyInfo>,
-    pub summary_avg_energy: f64,
-}
 #[derive(Clone)]
 pub struct Brain {
     pub memory: Arc<Mutex<Memory>>,
     pub aggressiveness: f64,
     pub reward_history: Vec<f64>,
     pub tick_counter: u64, 
+    pub rng: Arc<Mutex<SimRng>>,
+    pub clock: SimClock,
+    pub population: PopulationConfig,
+    pub log: EventLog,
+    /// правило выбора действия (`[brain]`)
+    pub policy: Arc<Mutex<Box<dyn BrainPolicy>>>,
 } 
  
 
 #[derive(Clone, Debug, serde::Serialize)]
 pub struct BrainSnapshot {
+    pub policy: String,
     pub aggressiveness: f64,
     pub avg_recent_result: f64,
     pub recent_memory: Vec<BrainEvent>, 
@@ -55,6 +57,7 @@ impl BrainSnapshot {
         let memory_guard = brain_guard.memory.lock().await;
         let recent_memory = memory_guard.get_recent(10).await;
         let avg_result = memory_guard.average_result(10).await;
+        let policy = brain_guard.policy.lock().await.name().to_string();
 
         println!(
             "📊 [DEBUG] Snapshot: recent_memory.len = {}, avg_result = {:.2}",
@@ -63,10 +66,11 @@ impl BrainSnapshot {
         );
 
         Self {
+            policy,
             aggressiveness: brain_guard.aggressiveness,
             avg_recent_result: avg_result,
             recent_memory,
-            last_update: chrono::Utc::now().timestamp(),
+            last_update: brain_guard.clock.now(),
         }
     }
 }
@@ -74,183 +78,199 @@ impl BrainSnapshot {
 
 
 impl Brain {
-    pub fn new() -> Self {
+    pub fn new(
+        rng: SimRng,
+        clock: SimClock,
+        population: PopulationConfig,
+        log: EventLog,
+        policy: Box<dyn BrainPolicy>,
+    ) -> Self {
         Self {
-            memory: Arc::new(Mutex::new(Memory::new(100, 10000, 604800))), // short=100, long=10000
+            memory: Arc::new(Mutex::new(Memory::new(100, 10000, 604800, clock.clone()))), // short=100, long=10000
             aggressiveness: 1.0,
             reward_history: Vec::new(),
             tick_counter: 0, 
+            rng: Arc::new(Mutex::new(rng)),
+            clock,
+            population,
+            log,
+            policy: Arc::new(Mutex::new(policy)),
         }
     }
-    /// Основной неблокирующий цикл сознания.
-    /// Запускается как отдельная таска: Brain::run(...).await
-    pub async fn run(
+    /// Один цикл сознания: анализ → решение → действие → адаптация.
+    /// Вызывается планировщиком (`Scheduler`) в фазе мозга.
+    pub async fn step(
         &mut self,
         nodes: Arc<Mutex<Vec<Arc<Mutex<Node>>>>>,
         fund: Arc<Mutex<NetworkFund>>,
         net: Arc<NetworkBus>,
     ) {
-        
-
-        println!("🧠 [Brain::run] Цикл мозга запущен!");
-
-        let mut ticker = interval(Duration::from_secs(5)); 
-        loop {
-            ticker.tick().await;
+        self.tick_counter += 1;
   
-            // === 1️⃣ Сканирование узлов ===
-            let snapshot_nodes = {
-                let guard = nodes.lock().await;
-                guard.clone()
-            };
-
-            let mut energy_data = Vec::new();
-            for n in snapshot_nodes.iter() {
-                if let Ok(node) = n.try_lock() {
-                    let e = node.energy.lock().await;
-                    energy_data.push((node.name.clone(), e.level, node.experience));
-                }
-            }
+        // === 1️⃣ Сканирование узлов ===
+        let snapshot_nodes = {
+            let guard = nodes.lock().await;
+            guard.clone()
+        };
 
-            if energy_data.is_empty() {
-                println!("⚠️ Нет активных нод для анализа");
-                continue;
-            }
+        let Some(mut observation) = self.observe(&snapshot_nodes).await else {
+            println!("⚠️ Нет активных нод для анализа");
+            return;
+        };
 
-             
-            // === 2️⃣ Анализ состояния сети ===
-            let avg_energy = energy_data.iter().map(|(_, e, _)| *e).sum::<f64>() / energy_data.len() as f64;
-            self.memory.lock().await.add_event(
-                BrainEvent::new("analyze", "Средняя энергия сети", avg_energy)
-            ).await;
-            println!("✅ [DEBUG] Событие отправлено в память!");
-
-             
-            // === 3️⃣ Принятие решения ===
-            let evolve_chance = (avg_energy / 100.0).clamp(0.1, 0.9);
-            let action = if rand::random::<f64>() < evolve_chance {
-                "evolve"
-            } else if avg_energy > 40.0 {
-                "help"
-            } else {
-                "rest"
-            };
-            println!("🧩 Решение: {}", action);
-
-            let mut rng = StdRng::from_entropy();
-            // === 4️⃣ Исполнение действия ===
-            let result_metric = match action {
-                "help" => {
-                    self.redistribute_energy(&snapshot_nodes, &fund, avg_energy).await;
-                    rng.gen_range(0.7..1.0)
-                }
-                "evolve" => { 
-                    println!("🧩🧠 [Brain::run::spawn] evolve start");
-                    let nodes_clone = nodes.clone();
-                    let fund_clone = fund.clone();
-                    let net_clone = net.clone();
-                    let mut brain_clone = self.clone();
-
-                    tokio::spawn(async move {
-                        brain_clone.evolve_network(nodes_clone, fund_clone, net_clone).await;
-                        println!("🧠 [Brain::run::spawn] evolve_network завершена");
-                    });
+        // === 2️⃣ Анализ состояния сети ===
+        let avg_energy = observation.avg_energy;
+        self.memory.lock().await.add_event(
+            BrainEvent::new("analyze", "Средняя энергия сети", avg_energy)
+        ).await;
+        println!("✅ [DEBUG] Событие отправлено в память!");
+
+        // === 3️⃣ Принятие решения: политика из `[brain]` ===
+        observation.recent_result = self.memory.lock().await.average_result(10).await;
+        let action = {
+            let mut rng = self.rng.lock().await;
+            self.policy.lock().await.decide(&observation, &mut rng)
+        };
+        println!("🧩 Решение: {:?} ({})", action, self.policy.lock().await.name());
+        self.log.emit(Event::BrainDecision { action: action.name().to_string(), avg_energy });
 
-                    for n in snapshot_nodes.iter() {
-                        if let Ok(node) = n.try_lock() {
-                            let mut e = node.energy.lock().await;
-                            e.level += rng.gen_range(0.5..2.0);
-                        }
+        // === 4️⃣ Исполнение действия ===
+        match action {
+            BrainAction::Help { pairs, share } => {
+                self.redistribute_energy(&snapshot_nodes, pairs, share).await;
+            }
+            BrainAction::Evolve => { 
+                println!("🧩🧠 [Brain::step] evolve start");
+                // выполняем прямо в фазе мозга — порядок тиков детерминирован
+                self.evolve_network(nodes.clone(), fund.clone(), net.clone()).await;
+                println!("🧠 [Brain::step] evolve_network завершена");
+
+                let mut rng = self.rng.lock().await;
+                for n in snapshot_nodes.iter() {
+                    if let Ok(node) = n.try_lock() {
+                        let mut e = node.energy.lock().await;
+                        e.gain(rng.gen_range(0.5..2.0), EnergyFlow::BrainStimulus);
                     }
-
-                    rng.gen_range(0.7..1.0)
                 }
-                "rest" => { 
-                    println!("😴 Brain: сеть отдыхает...");
-                    for n in snapshot_nodes.iter() {
-                        if let Ok(node) = n.try_lock() {
-                            let mut e = node.energy.lock().await;
-                            e.level += rng.gen_range(0.5..2.0);
-                        }
+            }
+            BrainAction::Rest => { 
+                println!("😴 Brain: сеть отдыхает...");
+                let mut rng = self.rng.lock().await;
+                for n in snapshot_nodes.iter() {
+                    if let Ok(node) = n.try_lock() {
+                        let mut e = node.energy.lock().await;
+                        e.gain(rng.gen_range(0.5..2.0), EnergyFlow::BrainStimulus);
                     }
-                    rng.gen_range(0.5..0.8)
                 }
-                _ => 0.5,
-            };
- 
-             
-            self.memory.lock().await.add_event(BrainEvent::new(
-                "feedback",
-                "Результат действия",
-                result_metric,
-            )).await;
-            println!("✅ [DEBUG] Событие отправлено в память!");
-
-            // === 6️⃣ Адаптация (обучение) ===
-            self.learn_from_feedback(result_metric).await;
-
-            // === 7️⃣ Мониторинг ===
-            let recent_avg = self.memory.lock().await.average_result(10).await;
-            println!(
-                "🧠 Brain: avg_energy = {:.2}, result = {:.2}, aggr = {:.2}, recent_avg = {:.2}",
-                avg_energy, result_metric, self.aggressiveness, recent_avg
-            );
-
-            // === 8️⃣ Саморегуляция ===
-            if recent_avg < 0.4 {
-                self.aggressiveness *= 1.15;
-                self.memory.lock().await.add_event(
-                    BrainEvent::new("feedback", "Рост реактивности", self.aggressiveness)
-                ).await; 
-                println!("⚡ Увеличение агрессивности → {:.2}", self.aggressiveness);
-            } else if recent_avg > 0.8 {
-                self.aggressiveness *= 0.9;
-                self.memory.lock().await.add_event(
-                    BrainEvent::new("feedback", "Снижение реактивности", self.aggressiveness)
-                ).await;  
-                println!("🌿 Снижение агрессивности → {:.2}", self.aggressiveness);
-            }else { 
-                self.aggressiveness *= 1.02;
-                self.memory.lock().await.add_event(
-                    BrainEvent::new("feedback", "поддерживаем динамику", self.aggressiveness)
-                ).await; 
-            }
-            
-            if rand::random::<f64>() < 0.2 {
-                let mut aggr = self.aggressiveness;
-                aggr += (rand::random::<f64>() - 0.5) * 0.1;
-                self.aggressiveness = aggr.clamp(0.1, 2.0);
-                println!("🔥 [Mutation] агрессивность случайно изменилась → {:.2}", self.aggressiveness);
             }
+        }
 
-            // 🧩 Каждые 10 тиков — самоанализ мозга
-            if self.tick_counter % 10 == 0 {
-                let event = BrainEvent::new("reflect", "Самоанализ цикла", self.aggressiveness);
-                self.memory.lock().await.add_event(event).await;
-                println!("💭 [Brain::reflect] Самоанализ выполнен (агрессивность {:.2})", self.aggressiveness);
-            }
+        // === 5️⃣ Результат: как изменилась сеть после действия ===
+        let after_nodes = nodes.lock().await.clone();
+        let result_metric = match self.observe(&after_nodes).await {
+            Some(after) => brain_policy::reward(&observation, &after),
+            // все ноды погибли
+            None => 0.0,
+        };
+ 
+         
+        self.memory.lock().await.add_event(BrainEvent::new(
+            "feedback",
+            "Результат действия",
+            result_metric,
+        )).await;
+        println!("✅ [DEBUG] Событие отправлено в память!");
+
+        // === 6️⃣ Адаптация (обучение) ===
+        self.policy.lock().await.feedback(&observation, &action, result_metric);
+        self.learn_from_feedback(result_metric).await;
+
+        // === 7️⃣ Мониторинг ===
+        let recent_avg = self.memory.lock().await.average_result(10).await;
+        println!(
+            "🧠 Brain: avg_energy = {:.2}, result = {:.2}, aggr = {:.2}, recent_avg = {:.2}",
+            avg_energy, result_metric, self.aggressiveness, recent_avg
+        );
 
-            // 🌀 Самовосстановление импульса 
-            if self.tick_counter % 5 == 0 {
-                // каждые 5 циклов слегка поднимаем агрессивность
-                self.aggressiveness += 0.1 * (1.0 - self.aggressiveness);
-                self.aggressiveness = self.aggressiveness.clamp(0.2, 2.0);
-                println!("💥 [Impulse] восстановление импульса: агрессивность {:.2}", self.aggressiveness);
-            }
+        // === 8️⃣ Саморегуляция ===
+        if recent_avg < 0.4 {
+            self.aggressiveness *= 1.15;
+            self.memory.lock().await.add_event(
+                BrainEvent::new("feedback", "Рост реактивности", self.aggressiveness)
+            ).await; 
+            println!("⚡ Увеличение агрессивности → {:.2}", self.aggressiveness);
+        } else if recent_avg > 0.8 {
+            self.aggressiveness *= 0.9;
+            self.memory.lock().await.add_event(
+                BrainEvent::new("feedback", "Снижение реактивности", self.aggressiveness)
+            ).await;  
+            println!("🌿 Снижение агрессивности → {:.2}", self.aggressiveness);
+        }else { 
+            self.aggressiveness *= 1.02;
+            self.memory.lock().await.add_event(
+                BrainEvent::new("feedback", "поддерживаем динамику", self.aggressiveness)
+            ).await; 
+        }
+        
+        let (mutate_roll, mutate_delta): (f64, f64) = {
+            let mut rng = self.rng.lock().await;
+            (rng.gen(), rng.gen())
+        };
+        if mutate_roll < 0.2 {
+            let mut aggr = self.aggressiveness;
+            aggr += (mutate_delta - 0.5) * 0.1;
+            self.aggressiveness = aggr.clamp(0.1, 2.0);
+            println!("🔥 [Mutation] агрессивность случайно изменилась → {:.2}", self.aggressiveness);
+        }
 
+        // 🧩 Каждые 10 тиков — самоанализ мозга
+        if self.tick_counter.is_multiple_of(10) {
+            let event = BrainEvent::new("reflect", "Самоанализ цикла", self.aggressiveness);
+            self.memory.lock().await.add_event(event).await;
+            println!("💭 [Brain::reflect] Самоанализ выполнен (агрессивность {:.2})", self.aggressiveness);
+        }
 
-            // можно вставить небольшую паузу, чтобы не перегружать цикл
-            tokio::time::sleep(Duration::from_secs(1)).await;
+        // 🌀 Самовосстановление импульса 
+        if self.tick_counter.is_multiple_of(5) {
+            // каждые 5 циклов слегка поднимаем агрессивность
+            self.aggressiveness += 0.1 * (1.0 - self.aggressiveness);
+            self.aggressiveness = self.aggressiveness.clamp(0.2, 2.0);
+            println!("💥 [Impulse] восстановление импульса: агрессивность {:.2}", self.aggressiveness);
         }
+
+        self.log.emit(Event::BrainAdapted { result: result_metric, aggressiveness: self.aggressiveness });
     }
 
-    /// 🔄 Перераспределение энергии между узлами (help mode)
-    pub async fn redistribute_energy(
-        &mut self,
-        snapshot_nodes: &Vec<Arc<Mutex<Node>>>,
-        fund: &Arc<Mutex<NetworkFund>>,
-        avg_energy: f64,
-    ) {
+    /// 👁️ Энергия нод, которые удалось прочитать; `None`, если ни одной.
+    /// `recent_result` заполняет вызывающий.
+    async fn observe(&self, nodes: &[Arc<Mutex<Node>>]) -> Option<Observation> {
+        let mut infos = Vec::new();
+        for n in nodes.iter() {
+            if let Ok(node) = n.try_lock() {
+                let e = node.energy.lock().await;
+                infos.push(NodeEnergyInfo { name: node.name.clone(), energy: e.level, experience: node.experience });
+            }
+        }
+        if infos.is_empty() {
+            return None;
+        }
+
+        let energies = infos.iter().map(|info| info.energy);
+        Some(Observation {
+            tick: self.clock.tick(),
+            population: nodes.len(),
+            min_energy: energies.clone().fold(f64::INFINITY, f64::min),
+            max_energy: energies.clone().fold(f64::NEG_INFINITY, f64::max),
+            avg_energy: energies.sum::<f64>() / infos.len() as f64,
+            nodes: infos,
+            aggressiveness: self.aggressiveness,
+            recent_result: 0.0,
+        })
+    }
+
+    /// 🔄 Перераспределение энергии между узлами (help mode):
+    /// до `pairs` пар «сильнейшая → слабейшая», слабой уходит `share` разброса пары
+    pub async fn redistribute_energy(&mut self, snapshot_nodes: &[Arc<Mutex<Node>>], pairs: usize, share: f64) {
         if snapshot_nodes.is_empty() {
             return;
         }
@@ -270,67 +290,77 @@ impl Brain {
 
         energy_list.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
 
-        let (lowest_name, lowest_energy) = energy_list.first().unwrap();
-        let (highest_name, highest_energy) = energy_list.last().unwrap();
+        // пары сходятся от краёв к середине списка
+        for i in 0..pairs.min(energy_list.len() / 2) {
+            let (lowest_name, lowest_energy) = &energy_list[i];
+            let (highest_name, highest_energy) = &energy_list[energy_list.len() - 1 - i];
 
-        if highest_energy - lowest_energy < 5.0 {
-            // Разброс мал — ничего не делаем
-            return;
-        }
+            if highest_energy - lowest_energy < 5.0 {
+                // Разброс мал — у следующих пар он ещё меньше
+                break;
+            }
 
-        let delta = (highest_energy - lowest_energy) * 0.25;
+            let delta = (highest_energy - lowest_energy) * share;
 
-        let from_opt = snapshot_nodes.iter().find(|n| {
-            if let Ok(node) = n.try_lock() {
-                node.name == *highest_name
-            } else {
-                false
-            }
-        });
+            let from_opt = snapshot_nodes.iter().find(|n| {
+                if let Ok(node) = n.try_lock() {
+                    node.name == *highest_name
+                } else {
+                    false
+                }
+            });
 
-        let to_opt = snapshot_nodes.iter().find(|n| {
-            if let Ok(node) = n.try_lock() {
-                node.name == *lowest_name
-            } else {
-                false
-            }
-        });
+            let to_opt = snapshot_nodes.iter().find(|n| {
+                if let Ok(node) = n.try_lock() {
+                    node.name == *lowest_name
+                } else {
+                    false
+                }
+            });
 
-        if let (Some(from), Some(to)) = (from_opt, to_opt) {
-            let mut from_node = from.lock().await;
-            let mut to_node = to.lock().await;
+            if let (Some(from), Some(to)) = (from_opt, to_opt) {
+                let from_node = from.lock().await;
+                let to_node = to.lock().await;
 
-            let mut from_energy = from_node.energy.lock().await;
-            let mut to_energy = to_node.energy.lock().await; 
+                let mut from_energy = from_node.energy.lock().await;
+                let mut to_energy = to_node.energy.lock().await; 
 
-            if from_energy.level >= delta {
-                from_energy.level -= delta;
-                to_energy.level += delta;
+                if from_energy.level >= delta {
+                    from_energy.consume(delta, EnergyFlow::Transfer);
+                    to_energy.gain(delta, EnergyFlow::Transfer);
+                    let mut moved = delta;
 
-                let mut delta = (from_energy.level - to_energy.level) * 0.2;
+                    let mut delta = (from_energy.level - to_energy.level) * 0.2;
 
-                // 💖 если цель — потомок, усиливаем помощь
-                if to_node.name.contains("_child_") {
-                    delta *= 1.5; // помогать потомкам чуть больше
-                }
+                    // 💖 если цель — потомок, усиливаем помощь
+                    if to_node.name.contains("_child_") {
+                        delta *= 1.5; // помогать потомкам чуть больше
+                    }
+
+                    if delta > 1.0 {
+                        from_energy.consume(delta, EnergyFlow::Transfer);
+                        to_energy.restore(delta, EnergyFlow::Transfer);
+                        moved += delta;
+                    }
+                    println!(
+                        "🤝 Brain: перераспределил {:.2} энергии {} → {}",
+                        delta, from_node.name, to_node.name
+                    );
+                    self.log.emit(Event::EnergyRedistributed {
+                        from: from_node.name.clone(),
+                        to: to_node.name.clone(),
+                        amount: moved,
+                    });
+
+                    self.memory.lock().await.add_event(
+                        BrainEvent::new(
+                            "redistribution",
+                            &format!("{} → {} (Δ={:.2})", from_node.name, to_node.name, delta),
+                            delta,
+                        )
+                    ).await;
 
-                if delta > 1.0 {
-                    from_energy.consume(delta);
-                    to_energy.restore(delta); 
                 }
-                println!(
-                    "🤝 Brain: перераспределил {:.2} энергии {} → {}",
-                    delta, from_node.name, to_node.name
-                );
-
-                self.memory.lock().await.add_event(
-                    BrainEvent::new(
-                        "redistribution",
-                        &format!("{} → {} (Δ={:.2})", from_node.name, to_node.name, delta),
-                        delta,
-                    )
-                ).await;
- 
             }
         }
     }
@@ -358,7 +388,7 @@ impl Brain {
         // --- 2️⃣ Контроль перенаселения ---
         {
             let total_now = nodes_ref.lock().await.len();
-            if total_now > 400 {
+            if total_now > self.population.max_before_skip {
                 println!("⚠️ [EVOLUTION] Перенаселение: {} нод — эволюция пропущена", total_now);
                 return;
             }
@@ -366,51 +396,34 @@ impl Brain {
 
         println!("🧠 Эволюция узлов ({} нод)", total_before);
 
-        // --- 3️⃣ Ограничиваем параллельные tick-и ---
-        use tokio::sync::Semaphore;
-        let semaphore = Arc::new(Semaphore::new(12)); // максимум 12 одновременно
-        let mut handles = Vec::new();
-
-        // --- 4️⃣ Запуск tick для каждой ноды ---
-        for n_arc in snapshot_nodes.into_iter() {
-            let sem = semaphore.clone();
-            let net = net.clone();
-            let nodes_ref_clone = nodes_ref.clone();
-
-            let handle = tokio::spawn(async move {
-                let _permit = sem.acquire_owned().await.unwrap();
-                // таймаут — если тик зависнет, пропускаем
-                match tokio::time::timeout(Duration::from_secs(6),
-                    Node::tick(n_arc.clone(), net.clone(), nodes_ref_clone.clone(), 0)
-                ).await {
-                    Ok(res) => res,       // Option<Arc<Mutex<Node>>>
-                    Err(_) => {
-                        println!("⚠️ [Tick Timeout] Node tick took too long, skipped");
-                        None
-                    }
-                }
-            });
-            handles.push(handle);
-        }
-
-        // --- 5️⃣ Собираем новых детей ---
+        // --- 3️⃣ Tick каждой ноды по очереди (порядок = порядок в списке) ---
+        let tick = self.clock.tick();
         let mut new_children: Vec<Arc<Mutex<Node>>> = Vec::new();
-        for h in handles {
-            if let Ok(Some(child)) = h.await {
+        for n_arc in snapshot_nodes.into_iter() {
+            if let Some(child) = Node::tick(n_arc, net.clone(), nodes_ref.clone(), tick).await {
                 new_children.push(child);
             }
         }
 
-        // --- 6️⃣ Удаляем "мёртвые" ноды ---
+        // --- 4️⃣ Удаляем "мёртвые" ноды ---
         {
             let mut nodes_locked = nodes_ref.lock().await;
             let mut survivors: Vec<Arc<Mutex<Node>>> = Vec::new();
 
             for n in nodes_locked.iter() {
                 let node = n.lock().await;
-                let e = node.energy.lock().await;
-                if e.level > 5.0 {
+                let mut e = node.energy.lock().await;
+                if e.level > self.population.death_energy {
                     survivors.push(n.clone());
+                } else {
+                    self.log.emit(Event::NodeDied {
+                        node: node.name.clone(),
+                        energy: e.level,
+                        cause: DeathCause::Starved,
+                    });
+                    node.data.record_death(&node.name, tick);
+                    e.retire();
+                    node.wallet.close().await;
                 }
             }
 
@@ -422,7 +435,7 @@ impl Brain {
             }
         }
 
-        // --- 7️⃣ Добавляем новых потомков ---
+        // --- 5️⃣ Добавляем новых потомков ---
         if !new_children.is_empty() {
             let added = new_children.len();
             let mut nodes_locked = nodes_ref.lock().await;
@@ -432,10 +445,10 @@ impl Brain {
             println!("🧬 Эволюция прошла без новых нод");
         }
 
-        // --- 8️⃣ Контроль перенаселения + удаление слабых ---
+        // --- 6️⃣ Контроль перенаселения + удаление слабых ---
         {
             let mut nodes = nodes_ref.lock().await;
-            if nodes.len() > 120 {
+            if nodes.len() > self.population.cull_above {
                 println!("⚠️ Перенаселение ({} нод): удаляем слабейших...", nodes.len());
 
                 // безопасный снимок энергий
@@ -449,26 +462,39 @@ impl Brain {
                 // сортировка по энергии
                 energy_snapshot.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
 
-                // оставляем 80 самых сильных
-                let survivors: Vec<_> = energy_snapshot.into_iter().rev().take(80).map(|(n, _)| n).collect();
+                // оставляем cull_keep самых сильных
+                let keep = self.population.cull_keep;
+                let culled = energy_snapshot.len().saturating_sub(keep);
+                for (n, level) in energy_snapshot.iter().take(culled) {
+                    let node = n.lock().await;
+                    self.log.emit(Event::NodeDied {
+                        node: node.name.clone(),
+                        energy: *level,
+                        cause: DeathCause::Culled,
+                    });
+                    node.data.record_death(&node.name, tick);
+                    node.energy.lock().await.retire();
+                    node.wallet.close().await;
+                }
+                let survivors: Vec<_> = energy_snapshot.into_iter().rev().take(keep).map(|(n, _)| n).collect();
                 let removed = nodes.len().saturating_sub(survivors.len());
                 *nodes = survivors;
 
-                println!("🧹 Удалено {} слабых нод (truncate до 80)", removed);
+                println!("🧹 Удалено {} слабых нод (truncate до {})", removed, keep);
             }
         }
 
-        // --- 9️⃣ Восстанавливаем энергию выживших ---
+        // --- 7️⃣ Восстанавливаем энергию выживших ---
         {
-            let mut rng = StdRng::from_entropy();
-            let mut nodes_locked = nodes_ref.lock().await;
+            let mut rng = self.rng.lock().await;
+            let nodes_locked = nodes_ref.lock().await;
 
             for n in nodes_locked.iter() {
-                let mut node = n.lock().await;
+                let node = n.lock().await;
                 let mut e = node.energy.lock().await;
-                e.level += 5.0 + rng.gen_range(0.0..10.0);
-                if e.level > 120.0 {
-                    e.level = 120.0;
+                e.gain(5.0 + rng.gen_range(0.0..10.0), EnergyFlow::SurvivorTopUp);
+                if e.level > self.population.survivor_energy_cap {
+                    e.set_level(self.population.survivor_energy_cap, EnergyFlow::Cap);
                 }
             }
         }
@@ -499,33 +525,7 @@ impl Brain {
             self.aggressiveness *= 0.98;
         }
         // clamp
-        if self.aggressiveness < 0.2 { self.aggressiveness = 0.2; }
-        if self.aggressiveness > 3.0 { self.aggressiveness = 3.0; }
+        self.aggressiveness = self.aggressiveness.clamp(0.2, 3.0);
     }
     
-    /// Возвращает структуру состояния мозга для API (snapshot).
-    pub async fn snapshot(nodes: Arc<Mutex<Vec<Arc<Mutex<Node>>>>>) -> BrainState {
-        let snapshot_nodes = {
-            let guard = nodes.lock().await;
-            guard.clone()
-        };
- 
-
-        let mut infos = Vec::with_capacity(snapshot_nodes.len());
-        let mut sum = 0.0;
-        for n in snapshot_nodes.iter() {
-            let node = n.lock().await;
-            let energy_guard = node.energy.lock().await;
-            let energy = energy_guard.level;
-            let exp = node.experience;
-            infos.push(NodeEnergyInfo {
-                name: node.name.clone(),
-                energy,
-                experience: exp,
-            });
-            sum += energy; 
-        }
-        let avg = if infos.is_empty() { 0.0 } else { sum / (infos.len() as f64) };
-        BrainState { nodes: infos, summary_avg_energy: avg }
-    }
 }
@@ -0,0 +1,356 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::brain::NodeEnergyInfo;
+use crate::config::BrainConfig;
+use crate::rng::SimRng;
+
+/// Какую политику использует мозг (`brain.policy`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PolicyKind {
+    /// исходное правило: эволюция с вероятностью `avg_energy/100`, иначе помощь или отдых
+    Default,
+    /// без случайности: пороги средней энергии и разброса
+    Threshold,
+    /// равновероятное действие
+    Random,
+    /// выравнивает энергию всех пар нод, пока разброс велик
+    GreedyBalancer,
+    /// ε-жадный выбор по оценкам, выученным из обратной связи
+    Learned,
+}
+
+impl PolicyKind {
+    /// Имя политики в конфиге
+    pub fn name(&self) -> &'static str {
+        match self {
+            PolicyKind::Default => "default",
+            PolicyKind::Threshold => "threshold",
+            PolicyKind::Random => "random",
+            PolicyKind::GreedyBalancer => "greedy-balancer",
+            PolicyKind::Learned => "learned",
+        }
+    }
+}
+
+/// 👁️ Что мозг видит в начале цикла
+#[derive(Clone, Debug, Serialize)]
+pub struct Observation {
+    pub tick: u64,
+    /// сколько нод в сети, включая занятые
+    pub population: usize,
+    /// ноды, которые удалось прочитать (занятые в этот момент пропускаются)
+    pub nodes: Vec<NodeEnergyInfo>,
+    pub avg_energy: f64,
+    pub min_energy: f64,
+    pub max_energy: f64,
+    pub aggressiveness: f64,
+    /// средний результат последних действий из памяти мозга
+    pub recent_result: f64,
+}
+
+impl Observation {
+    /// Разброс энергии между самой сильной и самой слабой нодой
+    pub fn spread(&self) -> f64 {
+        self.max_energy - self.min_energy
+    }
+}
+
+/// 🎯 Результат действия по наблюдениям до и после него, от 0 до 1:
+/// 0.5 — ничего не изменилось. Рост средней и минимальной энергии, сужение
+/// разброса и прирост нод поднимают результат; изменения энергии меряются
+/// относительно прежней средней (не меньше 10), чтобы шкала не зависела от уровня сети
+pub fn reward(before: &Observation, after: &Observation) -> f64 {
+    let scale = before.avg_energy.max(10.0);
+    let avg = (after.avg_energy - before.avg_energy) / scale;
+    let min = (after.min_energy - before.min_energy) / scale;
+    let spread = (before.spread() - after.spread()) / scale;
+    let population = (after.population as f64 - before.population as f64) / before.population.max(1) as f64;
+    let score = avg + 0.5 * min + 0.5 * spread + population;
+    (0.5 + 0.5 * score.tanh()).clamp(0.0, 1.0)
+}
+
+/// 🧩 Решение политики
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum BrainAction {
+    /// тик эволюции нод и подпитка сети
+    Evolve,
+    /// перераспределение энергии: `pairs` пар «сильнейшая → слабейшая»,
+    /// каждой слабой ноде уходит `share` разброса пары
+    Help { pairs: usize, share: f64 },
+    /// сеть отдыхает и немного подпитывается
+    Rest,
+}
+
+impl BrainAction {
+    /// Имя действия в событии `BrainDecision`
+    pub fn name(&self) -> &'static str {
+        match self {
+            BrainAction::Evolve => "evolve",
+            BrainAction::Help { .. } => "help",
+            BrainAction::Rest => "rest",
+        }
+    }
+}
+
+/// Помощь, как её всегда делал мозг: одна пара, четверть разброса
+const DEFAULT_HELP: BrainAction = BrainAction::Help { pairs: 1, share: 0.25 };
+
+/// 🧠 Правило выбора действия мозга. Случайность — только из переданного
+/// потока RNG мозга, чтобы прогон с тем же seed повторялся.
+pub trait BrainPolicy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn decide(&mut self, observation: &Observation, rng: &mut SimRng) -> BrainAction;
+
+    /// Результат исполненного действия (`reward`, та же метрика, что в `learn_from_feedback`)
+    fn feedback(&mut self, _observation: &Observation, _action: &BrainAction, _reward: f64) {}
+
+    /// Выученное состояние для контрольной точки (`Null` — нечего сохранять)
+    fn state(&self) -> serde_json::Value {
+        serde_json::Value::Null
+    }
+
+    fn restore(&mut self, _state: serde_json::Value) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Политика из `[brain]`
+pub fn from_config(config: &BrainConfig) -> Box<dyn BrainPolicy> {
+    match config.policy {
+        PolicyKind::Default => Box::new(DefaultRule),
+        PolicyKind::Threshold => Box::new(Threshold { evolve_above: config.evolve_above, help_spread: config.help_spread }),
+        PolicyKind::Random => Box::new(RandomPolicy),
+        PolicyKind::GreedyBalancer => {
+            Box::new(GreedyBalancer { evolve_above: config.evolve_above, help_spread: config.help_spread })
+        }
+        PolicyKind::Learned => Box::new(Learned::new(config.epsilon, config.learning_rate)),
+    }
+}
+
+/// Исходное правило мозга
+pub struct DefaultRule;
+
+impl BrainPolicy for DefaultRule {
+    fn name(&self) -> &'static str {
+        PolicyKind::Default.name()
+    }
+
+    fn decide(&mut self, observation: &Observation, rng: &mut SimRng) -> BrainAction {
+        let evolve_chance = (observation.avg_energy / 100.0).clamp(0.1, 0.9);
+        let roll: f64 = rng.gen();
+        if roll < evolve_chance {
+            BrainAction::Evolve
+        } else if observation.avg_energy > 40.0 {
+            DEFAULT_HELP
+        } else {
+            BrainAction::Rest
+        }
+    }
+}
+
+/// Эволюция при средней энергии от `evolve_above`, иначе помощь при разбросе
+/// от `help_spread`, иначе отдых
+pub struct Threshold {
+    pub evolve_above: f64,
+    pub help_spread: f64,
+}
+
+impl BrainPolicy for Threshold {
+    fn name(&self) -> &'static str {
+        PolicyKind::Threshold.name()
+    }
+
+    fn decide(&mut self, observation: &Observation, _rng: &mut SimRng) -> BrainAction {
+        if observation.avg_energy >= self.evolve_above {
+            BrainAction::Evolve
+        } else if observation.spread() >= self.help_spread {
+            DEFAULT_HELP
+        } else {
+            BrainAction::Rest
+        }
+    }
+}
+
+/// Любое из трёх действий с равной вероятностью — точка отсчёта для сравнения
+pub struct RandomPolicy;
+
+impl BrainPolicy for RandomPolicy {
+    fn name(&self) -> &'static str {
+        PolicyKind::Random.name()
+    }
+
+    fn decide(&mut self, _observation: &Observation, rng: &mut SimRng) -> BrainAction {
+        match rng.gen_range(0..3) {
+            0 => BrainAction::Evolve,
+            1 => DEFAULT_HELP,
+            _ => BrainAction::Rest,
+        }
+    }
+}
+
+/// Пока разброс не меньше `help_spread`, выравнивает все пары нод
+/// (сильные отдают слабым половину разницы); потом — как `Threshold`
+pub struct GreedyBalancer {
+    pub evolve_above: f64,
+    pub help_spread: f64,
+}
+
+impl BrainPolicy for GreedyBalancer {
+    fn name(&self) -> &'static str {
+        PolicyKind::GreedyBalancer.name()
+    }
+
+    fn decide(&mut self, observation: &Observation, _rng: &mut SimRng) -> BrainAction {
+        if observation.spread() >= self.help_spread {
+            BrainAction::Help { pairs: (observation.nodes.len() / 2).max(1), share: 0.5 }
+        } else if observation.avg_energy >= self.evolve_above {
+            BrainAction::Evolve
+        } else {
+            BrainAction::Rest
+        }
+    }
+}
+
+/// Уровни средней энергии, для которых оценки выучиваются отдельно
+const ENERGY_BANDS: [f64; 2] = [40.0, 70.0];
+const ACTIONS: [BrainAction; 3] = [BrainAction::Evolve, DEFAULT_HELP, BrainAction::Rest];
+
+/// Выученные оценки: `values[уровень][действие]` — средний результат действия
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LearnedState {
+    pub values: Vec<[f64; 3]>,
+    pub counts: Vec<[u64; 3]>,
+}
+
+/// ε-жадный бандит по уровням средней энергии: с вероятностью `epsilon`
+/// пробует случайное действие, иначе берёт лучшее по оценке; оценка
+/// сдвигается к полученному результату на `learning_rate`
+pub struct Learned {
+    epsilon: f64,
+    learning_rate: f64,
+    state: LearnedState,
+}
+
+impl Learned {
+    pub fn new(epsilon: f64, learning_rate: f64) -> Self {
+        let bands = ENERGY_BANDS.len() + 1;
+        Self {
+            epsilon,
+            learning_rate,
+            // оптимистичный старт: каждое действие будет испробовано
+            state: LearnedState { values: vec![[1.0; 3]; bands], counts: vec![[0; 3]; bands] },
+        }
+    }
+
+    fn band(observation: &Observation) -> usize {
+        ENERGY_BANDS.iter().filter(|edge| observation.avg_energy >= **edge).count()
+    }
+}
+
+impl BrainPolicy for Learned {
+    fn name(&self) -> &'static str {
+        PolicyKind::Learned.name()
+    }
+
+    fn decide(&mut self, observation: &Observation, rng: &mut SimRng) -> BrainAction {
+        let values = &self.state.values[Self::band(observation)];
+        let index = if rng.gen::<f64>() < self.epsilon {
+            rng.gen_range(0..ACTIONS.len())
+        } else {
+            // при равных оценках — первое действие, чтобы выбор не зависел от порядка сравнения
+            (0..ACTIONS.len()).fold(0, |best, i| if values[i] > values[best] { i } else { best })
+        };
+        ACTIONS[index].clone()
+    }
+
+    fn feedback(&mut self, observation: &Observation, action: &BrainAction, reward: f64) {
+        let Some(index) = ACTIONS.iter().position(|a| a.name() == action.name()) else {
+            return;
+        };
+        let band = Self::band(observation);
+        let value = &mut self.state.values[band][index];
+        *value += self.learning_rate * (reward - *value);
+        self.state.counts[band][index] += 1;
+    }
+
+    fn state(&self) -> serde_json::Value {
+        serde_json::to_value(&self.state).unwrap_or_default()
+    }
+
+    fn restore(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let state: LearnedState = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        if state.values.len() != self.state.values.len() || state.counts.len() != self.state.counts.len() {
+            return Err("другое число уровней энергии".into());
+        }
+        self.state = state;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Seed;
+
+    fn observation(avg_energy: f64, min_energy: f64, max_energy: f64, population: usize) -> Observation {
+        Observation {
+            tick: 0,
+            population,
+            nodes: Vec::new(),
+            avg_energy,
+            min_energy,
+            max_energy,
+            aggressiveness: 1.0,
+            recent_result: 0.5,
+        }
+    }
+
+    #[test]
+    fn reward_follows_the_network() {
+        let before = observation(50.0, 20.0, 80.0, 10);
+        assert_eq!(reward(&before, &before), 0.5);
+        // сеть окрепла и выровнялась
+        assert!(reward(&before, &observation(60.0, 40.0, 70.0, 10)) > 0.7);
+        // энергия упала, часть нод умерла
+        assert!(reward(&before, &observation(40.0, 10.0, 80.0, 8)) < 0.3);
+        // помощь: средняя та же, слабые подтянулись
+        assert!(reward(&before, &observation(50.0, 30.0, 70.0, 10)) > 0.5);
+        let best = observation(1e9, 1e9, 1e9, 1000);
+        assert!(reward(&before, &best) <= 1.0);
+    }
+
+    #[test]
+    fn learned_settles_on_the_best_action() {
+        let mut policy = Learned::new(0.1, 0.2);
+        let mut rng = Seed::new(3).stream("brain");
+        let seen = observation(50.0, 20.0, 80.0, 10);
+        // подставная награда: помощь лучше всего, эволюция хуже всего
+        let stub = |action: &BrainAction| match action {
+            BrainAction::Help { .. } => 0.9,
+            BrainAction::Rest => 0.5,
+            BrainAction::Evolve => 0.2,
+        };
+
+        let mut late_help = 0;
+        for round in 0..500 {
+            let action = policy.decide(&seen, &mut rng);
+            if round >= 400 && action.name() == "help" {
+                late_help += 1;
+            }
+            policy.feedback(&seen, &action, stub(&action));
+        }
+
+        let band = Learned::band(&seen);
+        let values = policy.state.values[band];
+        assert!((values[1] - 0.9).abs() < 0.01, "{:?}", values);
+        assert!(values[1] > values[2] && values[2] > values[0], "{:?}", values);
+        // после обучения случайны только исследовательские шаги (ε = 0.1)
+        assert!(late_help >= 80, "help {} из 100", late_help);
+        policy.epsilon = 0.0;
+        assert_eq!(policy.decide(&seen, &mut rng), DEFAULT_HELP);
+    }
+}
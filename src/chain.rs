@@ -1,96 +1,690 @@
-
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
+use std::fmt;
+use std::path::Path;
 use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+
+use crate::block_store::BlockStore;
+use crate::config::StorageConfig;
+use crate::identity::{self, Identity, KeyRegistry};
 
+/// `prev_hash` генезис-блока
+pub const GENESIS_PREV_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
 
-#[derive(Clone, serde::Serialize, serde::Deserialize)]
+/// 🧱 Блок цепи. Хэш (SHA-256) фиксирует номер, хэш предыдущего блока,
+/// время, валидатора с его ключом, работу и дайджест данных, поэтому правка
+/// любого блока ломает все ссылки после него. Валидатор подписывает хэш
+/// своим ключом Ed25519; генезис не подписан.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Block {
     pub index: u64,
-    pub data_root: String,
-    pub key_root: String,
+    pub prev_hash: String,
+    /// время симуляции (unix-секунды, см. `SimClock::now`)
+    pub timestamp: i64,
+    pub data_root: String,  // ссылка на DataChain
+    pub key_root: String,   // ссылка на KeyChain
     pub validator: String,
-    pub hash: String, // 🔥 добавляем
+    /// открытый ключ валидатора (hex)
+    pub validator_key: String,
+    /// вклад, подтверждённый блоком: суммарный вес раунда PoC для ключевых блоков,
+    /// 1 для блоков DataChain. Сумма по цепи — её работа при выборе форка.
+    pub work: f64,
+    pub hash: String,
+    /// подпись валидатора под `hash` (hex)
+    pub signature: String,
+}
+
+impl Block {
+    /// Новый блок, подписанный `signer` — он же валидатор
+    pub fn new(
+        index: u64,
+        prev_hash: String,
+        timestamp: i64,
+        data_root: String,
+        key_root: String,
+        signer: &Identity,
+        work: f64,
+    ) -> Self {
+        let mut block = Block {
+            index,
+            prev_hash,
+            timestamp,
+            data_root,
+            key_root,
+            validator: signer.name.clone(),
+            validator_key: signer.public_key(),
+            work,
+            hash: String::new(),
+            signature: String::new(),
+        };
+        block.hash = block.compute_hash();
+        block.signature = signer.sign(block.hash.as_bytes());
+        block
+    }
+
+    /// Одинаковый у всех цепей: время 0, валидатор `system` без ключа, работа 0
+    pub fn genesis() -> Self {
+        let mut block = Block {
+            index: 0,
+            prev_hash: GENESIS_PREV_HASH.into(),
+            timestamp: 0,
+            data_root: "genesis".into(),
+            key_root: "genesis".into(),
+            validator: "system".into(),
+            validator_key: String::new(),
+            work: 0.0,
+            hash: String::new(),
+            signature: String::new(),
+        };
+        block.hash = block.compute_hash();
+        block
+    }
+
+    /// Подпись валидатора сходится с хэшем и ключом блока
+    pub fn check_signature(&self) -> Result<(), ChainError> {
+        identity::verify(&self.validator_key, self.hash.as_bytes(), &self.signature)
+            .map_err(|_| ChainError::BadSignature { index: self.index })
+    }
+
+    /// Ключ блока — действительно ключ ноды, названной валидатором
+    pub fn check_validator(&self, keys: &KeyRegistry) -> Result<(), ChainError> {
+        keys.check(&self.validator, &self.validator_key).map_err(|_| ChainError::ForeignValidator {
+            index: self.index,
+            validator: self.validator.clone(),
+        })
+    }
+
+    /// SHA-256 от `data_root` и `key_root`
+    pub fn data_digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hash_field(&mut hasher, &self.data_root);
+        hash_field(&mut hasher, &self.key_root);
+        hasher.finalize().into()
+    }
+
+    pub fn compute_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.index.to_le_bytes());
+        hash_field(&mut hasher, &self.prev_hash);
+        hasher.update(self.timestamp.to_le_bytes());
+        hash_field(&mut hasher, &self.validator);
+        hash_field(&mut hasher, &self.validator_key);
+        hasher.update(self.work.to_bits().to_le_bytes());
+        hasher.update(self.data_digest());
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Строка с длиной впереди — соседние поля не склеиваются в одинаковый вход
+fn hash_field(hasher: &mut Sha256, value: &str) {
+    hasher.update((value.len() as u64).to_le_bytes());
+    hasher.update(value.as_bytes());
+}
+
+/// Блок стоит на позиции `position` после блока с хэшем `prev_hash`:
+/// номер, ссылка и хэш сходятся, работа допустима. Подпись проверяется отдельно.
+fn check_link(position: usize, prev_hash: &str, block: &Block) -> Result<(), ChainError> {
+    if block.index != position as u64 {
+        return Err(ChainError::BadIndex { position, found: block.index });
+    }
+    if block.prev_hash != prev_hash {
+        return Err(ChainError::BrokenLink {
+            index: block.index,
+            expected: prev_hash.to_string(),
+            found: block.prev_hash.clone(),
+        });
+    }
+    let expected = block.compute_hash();
+    if block.hash != expected {
+        return Err(ChainError::HashMismatch { index: block.index, expected, found: block.hash.clone() });
+    }
+    // работа входит в выбор форка, поэтому отрицательная или NaN недопустима
+    if !(block.work.is_finite() && block.work >= 0.0) {
+        return Err(ChainError::BadWork { index: block.index, work: block.work });
+    }
+    Ok(())
+}
+
+/// Почему цепь не прошла проверку
+#[derive(Debug)]
+pub enum ChainError {
+    Io(std::io::Error),
+    Format(String),
+    Empty,
+    /// блок стоит не на своём месте
+    BadIndex { position: usize, found: u64 },
+    /// `prev_hash` не совпадает с хэшем предыдущего блока
+    BrokenLink { index: u64, expected: String, found: String },
+    /// содержимое блока не соответствует его хэшу
+    HashMismatch { index: u64, expected: String, found: String },
+    /// работа блока отрицательная или не число
+    BadWork { index: u64, work: f64 },
+    /// у цепей разный генезис — общего предка нет
+    ForeignGenesis,
+    /// подпись не сходится с хэшем и ключом блока
+    BadSignature { index: u64 },
+    /// ключ блока не принадлежит ноде, указанной валидатором
+    ForeignValidator { index: u64, validator: String },
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::Io(e) => write!(f, "ошибка чтения или записи цепи: {}", e),
+            ChainError::Format(e) => write!(f, "повреждённое хранилище цепи: {}", e),
+            ChainError::Empty => write!(f, "цепь пуста — нет даже генезис-блока"),
+            ChainError::BadIndex { position, found } => {
+                write!(f, "на позиции {} стоит блок #{}", position, found)
+            }
+            ChainError::BrokenLink { index, expected, found } => write!(
+                f,
+                "блок #{} ссылается на {}, а хэш предыдущего блока {}",
+                index, found, expected
+            ),
+            ChainError::HashMismatch { index, expected, found } => write!(
+                f,
+                "хэш блока #{} записан как {}, а по содержимому {}",
+                index, found, expected
+            ),
+            ChainError::BadWork { index, work } => write!(f, "блок #{} заявляет работу {}", index, work),
+            ChainError::ForeignGenesis => write!(f, "цепи начинаются с разных генезис-блоков"),
+            ChainError::BadSignature { index } => write!(f, "подпись блока #{} не сходится", index),
+            ChainError::ForeignValidator { index, validator } => {
+                write!(f, "блок #{} подписан не ключом {}", index, validator)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+/// 🔀 Итог смены ветки: блоки после общего предка, которые ушли и пришли
+#[derive(Clone, Debug)]
+pub struct Reorg {
+    /// номер последнего общего блока
+    pub common_ancestor: u64,
+    /// блоки прежней ветки, выпавшие из цепи (по возрастанию номера)
+    pub orphaned: Vec<Block>,
+    /// блоки новой ветки, добавленные после предка
+    pub adopted: Vec<Block>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+/// ⛓️ Цепь блоков. Блоки держатся в памяти; если цепь открыта из хранилища
+/// (`storage.enabled`, см. `DataRoot`), каждое изменение сначала записывается в него,
+/// а отдельные блоки для API и синхронизации читаются из него по индексу.
+#[derive(Serialize, Deserialize)]
 pub struct Chain {
     pub name: String,
     pub blocks: Vec<Block>,
+    #[serde(skip)]
+    store: Option<BlockStore>,
+}
+
+/// Копия — снимок в памяти: в хранилище пишет только сама цепь
+impl Clone for Chain {
+    fn clone(&self) -> Self {
+        Self::from_blocks(&self.name, self.blocks.clone())
+    }
 }
 
 impl Chain {
+    /// Новая цепь в памяти с одним генезис-блоком (хранилище — см. `Chain::open`)
     pub fn new(name: &str) -> Self {
-        let path = format!("{}_chain.json", name);
-        if let Ok(chain) = Self::load_from_file(&path) {
-            println!("♻️ Загружена существующая цепь: {}", name);
-            return chain;
+        println!("🆕 Создана новая цепь: {}", name);
+        Self::from_blocks(name, vec![Block::genesis()])
+    }
+
+    /// Цепь в памяти из готовых блоков (без проверки — см. `verify`)
+    pub fn from_blocks(name: &str, blocks: Vec<Block>) -> Self {
+        Self { name: name.to_string(), blocks, store: None }
+    }
+
+    /// 💽 Открыть цепь из хранилища в каталоге `dir` (пустое получает генезис).
+    /// Номера, ссылки и хэши проверяются заново; подписи проверены при записи,
+    /// а целость записей гарантируют контрольные суммы хранилища.
+    pub fn open(dir: &Path, name: &str, storage: &StorageConfig) -> Result<Self, ChainError> {
+        let mut store = BlockStore::open(dir, storage.fsync, storage.segment_bytes)?;
+        let recovery = store.recovery();
+        if recovery.reindexed > 0 || recovery.truncated_bytes > 0 {
+            println!(
+                "🩹 Хранилище {}: дочитано {} блоков, отрезано {} байт оборванной записи",
+                name, recovery.reindexed, recovery.truncated_bytes
+            );
         }
 
-        println!("🆕 Создана новая цепь: {}", name);
-        Self {
-            name: name.to_string(),
-            blocks: vec![Block {
-                index: 0,
-                data_root: "genesis".into(),
-                key_root: "genesis".into(),
-                validator: "system".into(),
-                hash: "genesis_hash".into(), // ✅
-            }],
+        let mut blocks: Vec<Block> = Vec::with_capacity(store.len());
+        for block in store.read_all()? {
+            let prev_hash = blocks.last().map_or(GENESIS_PREV_HASH, |b| b.hash.as_str());
+            check_link(blocks.len(), prev_hash, &block)?;
+            if !blocks.is_empty() {
+                block.check_signature()?;
+            }
+            blocks.push(block);
+        }
+        if store.is_empty() {
+            let genesis = Block::genesis();
+            store.append(&genesis)?;
+            blocks.push(genesis);
+            println!("🆕 Создана новая цепь: {}", name);
+        } else if blocks[0].hash != Block::genesis().hash {
+            return Err(ChainError::ForeignGenesis);
+        } else {
+            println!("♻️ Загружена существующая цепь: {} ({} блоков)", name, blocks.len());
         }
+
+        Ok(Self { name: name.to_string(), blocks, store: Some(store) })
+    }
+
+    /// Привязать цепь из памяти (например, из контрольной точки) к хранилищу
+    /// в каталоге `dir`: совпадающее начало остаётся на диске, остальное переписывается
+    pub fn attach_store(&mut self, dir: &Path, storage: &StorageConfig) -> Result<(), ChainError> {
+        let mut store = BlockStore::open(dir, storage.fsync, storage.segment_bytes)?;
+        let keep = self
+            .blocks
+            .iter()
+            .take_while(|b| store.hash_at(b.index).as_deref() == Some(b.hash.as_str()))
+            .count();
+        store.truncate(keep)?;
+        for block in self.blocks[keep..].iter() {
+            store.append(block)?;
+        }
+        self.store = Some(store);
+        Ok(())
+    }
+
+    /// Сколько блоков в цепи
+    pub fn height(&self) -> u64 {
+        self.blocks.len() as u64
+    }
+
+    /// Хэш блока `height`: из индекса хранилища, без чтения сегментов
+    pub fn hash_at(&self, height: u64) -> Option<String> {
+        match &self.store {
+            Some(store) => store.hash_at(height),
+            None => self.blocks.get(height as usize).map(|b| b.hash.clone()),
+        }
+    }
+
+    /// Блок с номером `height`: из хранилища по индексу, если цепь в нём
+    pub fn block(&self, height: u64) -> Result<Option<Block>, ChainError> {
+        match &self.store {
+            Some(store) => store.get(height),
+            None => Ok(self.blocks.get(height as usize).cloned()),
+        }
+    }
+
+    /// Блок по хэшу: в хранилище — через индекс по хэшам
+    pub fn block_by_hash(&self, hash: &str) -> Result<Option<Block>, ChainError> {
+        match &self.store {
+            Some(store) => store.get_by_hash(hash),
+            None => Ok(self.blocks.iter().find(|b| b.hash == hash).cloned()),
+        }
+    }
+
+    /// Не больше `limit` блоков начиная с номера `from`
+    pub fn blocks_from(&self, from: u64, limit: usize) -> Result<Vec<Block>, ChainError> {
+        let to = self.height().min(from.saturating_add(limit as u64));
+        let mut blocks = Vec::new();
+        for height in from..to {
+            blocks.extend(self.block(height)?);
+        }
+        Ok(blocks)
     }
 
     pub fn last_hash(&self) -> String {
         self.blocks.last().unwrap().hash.clone()
     }
 
-    pub fn add_block(&mut self, data_root: String, key_root: String, validator: String) {
+    /// Добавить блок поверх последнего от имени `signer`. `timestamp` — время симуляции.
+    pub fn add_block(
+        &mut self,
+        data_root: String,
+        key_root: String,
+        signer: &Identity,
+        timestamp: i64,
+    ) -> Result<(), ChainError> {
         let index = self.blocks.len() as u64;
-        let hash_input = format!("{}{}{}{}", index, data_root, key_root, validator);
-        let hash = format!("{:x}", md5::compute(hash_input));
+        let block = Block::new(index, self.last_hash(), timestamp, data_root, key_root, signer, 1.0);
+        self.push(block)
+    }
 
-        let block = Block {
-            index,
-            data_root,
-            key_root,
-            validator,
-            hash, // теперь это переменная
-        };
+    /// Принять готовый блок (например, решённый консенсусом): номер, ссылка
+    /// на последний блок, хэш и подпись должны сходиться
+    pub fn append(&mut self, block: Block) -> Result<(), ChainError> {
+        self.check_next(&block)?;
+        self.push(block)
+    }
+
+    /// Встал бы блок следующим в цепь (как в `append`, но цепь не меняется)
+    pub fn check_next(&self, block: &Block) -> Result<(), ChainError> {
+        check_link(self.blocks.len(), &self.last_hash(), block)?;
+        block.check_signature()
+    }
 
+    /// Записать блок в хранилище, затем в память — при ошибке записи цепь не меняется
+    fn push(&mut self, block: Block) -> Result<(), ChainError> {
+        if let Some(store) = self.store.as_mut() {
+            store.append(&block)?;
+        }
         self.blocks.push(block);
+        Ok(())
+    }
+
+    /// Суммарная работа цепи
+    pub fn total_work(&self) -> f64 {
+        self.blocks.iter().map(|b| b.work).sum()
+    }
+
+    /// ⚖️ Правило выбора форка: больше работы → длиннее → меньший хэш вершины.
+    /// Порядок строгий, поэтому все ноды, видящие одни и те же цепи, выбирают одну.
+    pub fn is_better_than(&self, other: &Chain) -> bool {
+        let (mine, theirs) = (self.total_work(), other.total_work());
+        if mine != theirs {
+            return mine > theirs;
+        }
+        if self.blocks.len() != other.blocks.len() {
+            return self.blocks.len() > other.blocks.len();
+        }
+        self.last_hash() < other.last_hash()
+    }
+
+    /// Номер последнего блока, общего с `other`; `None`, если различается даже генезис.
+    /// Хэш фиксирует всю историю блока, поэтому после первого расхождения общих блоков нет.
+    pub fn common_ancestor(&self, other: &Chain) -> Option<u64> {
+        self.blocks
+            .iter()
+            .zip(other.blocks.iter())
+            .take_while(|(a, b)| a.hash == b.hash)
+            .last()
+            .map(|(a, _)| a.index)
     }
 
-    pub fn save_to_file(&self) -> std::io::Result<()> {
-        let json = serde_json::to_string_pretty(&self)?;
-        let filename = format!("{}_chain.json", self.name);
-        let mut file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&filename)?;
-        file.write_all(json.as_bytes())?;
+    /// 🔀 Синхронизация с чужой цепью: найти общего предка и, если по правилу
+    /// выбора форка чужая ветка лучше, перейти на неё. Блоки до предка уже есть
+    /// у нас; новые проходят через `append` (ссылки, хэши, подписи) и сверяются
+    /// с ключами `keys`, так что ветка принимается только целиком.
+    /// `None` — своя цепь не хуже и осталась как была.
+    pub fn sync(&mut self, incoming: &Chain, keys: &KeyRegistry) -> Result<Option<Reorg>, ChainError> {
+        let ancestor = self.common_ancestor(incoming).ok_or(ChainError::ForeignGenesis)?;
+
+        let keep = ancestor as usize + 1;
+        let mut candidate = Chain::from_blocks(&self.name, self.blocks[..keep].to_vec());
+        let adopted = incoming.blocks[keep..].to_vec();
+        for block in adopted.iter() {
+            block.check_validator(keys)?;
+            candidate.append(block.clone())?;
+        }
+        if !candidate.is_better_than(self) {
+            return Ok(None);
+        }
+
+        if let Some(store) = self.store.as_mut() {
+            store.truncate(keep)?;
+            for block in adopted.iter() {
+                store.append(block)?;
+            }
+        }
+        let orphaned = self.blocks.split_off(keep);
+        self.blocks = candidate.blocks;
+        Ok(Some(Reorg { common_ancestor: ancestor, orphaned, adopted }))
+    }
+
+    /// Отрезать блоки начиная с номера `from` и вернуть их (генезис не трогается)
+    pub fn truncate_from(&mut self, from: u64) -> Result<Vec<Block>, ChainError> {
+        let from = (from as usize).max(1).min(self.blocks.len());
+        if let Some(store) = self.store.as_mut() {
+            store.truncate(from)?;
+        }
+        Ok(self.blocks.split_off(from))
+    }
+
+    /// 🔍 Пройти цепь от генезиса и вернуть первую ошибку: чужой генезис,
+    /// неверный номер, разорванную ссылку, несовпадение хэша или подписи
+    pub fn verify(&self) -> Result<(), ChainError> {
+        let Some(first) = self.blocks.first() else {
+            return Err(ChainError::Empty);
+        };
+        if first.hash != Block::genesis().hash {
+            return Err(ChainError::ForeignGenesis);
+        }
+
+        let mut prev_hash = GENESIS_PREV_HASH;
+        for (position, block) in self.blocks.iter().enumerate() {
+            check_link(position, prev_hash, block)?;
+            if position > 0 {
+                block.check_signature()?;
+            }
+            prev_hash = &block.hash;
+        }
         Ok(())
     }
 
-    fn save_block(&self, block: &Block) {
-        let filename = format!("data/blocks/{}_{}.json", self.name, block.index);
-        let json = serde_json::to_string_pretty(&block).unwrap();
-        let mut f = File::create(filename).unwrap();
-        f.write_all(json.as_bytes()).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Seed;
+    use std::path::PathBuf;
+
+    fn signer(name: &str) -> Identity {
+        Identity::derive(name, &Seed::new(1).stream(&format!("node/{}", name)))
     }
 
-    pub fn load_from_file(path: &str) -> std::io::Result<Self> {
-        let mut file = File::open(path)?;
-        let mut content = String::new();
-        file.read_to_string(&mut content)?;
-        let chain: Chain = serde_json::from_str(&content)?;
-        Ok(chain)
+    fn keys() -> KeyRegistry {
+        let keys = KeyRegistry::new();
+        for name in ["node0", "node1"] {
+            keys.register(&signer(name));
+        }
+        keys
     }
 
-    pub fn print_chain(&self) {
-        println!("\n==== {} ====", self.name);
-        for b in &self.blocks {
-            println!("#{} [{}] {}", b.index, b.validator, b.hash);
+    /// Копия `base` и поверх неё блоки с указанной работой; `tag` различает ветки
+    fn fork(base: &Chain, works: &[f64], tag: &str) -> Chain {
+        let signer = signer("node0");
+        let mut chain = base.clone();
+        for work in works {
+            let index = chain.height();
+            let block =
+                Block::new(index, chain.last_hash(), index as i64, format!("{}-{}", tag, index), "k".into(), &signer, *work);
+            chain.append(block).unwrap();
         }
+        chain
     }
-}
\ No newline at end of file
+
+    fn hashes(chain: &Chain) -> Vec<String> {
+        chain.blocks.iter().map(|b| b.hash.clone()).collect()
+    }
+
+    #[test]
+    fn equal_work_and_length_goes_to_the_lower_tip() {
+        let base = fork(&Chain::from_blocks("key", vec![Block::genesis()]), &[1.0, 1.0], "base");
+        let a = fork(&base, &[2.0], "a");
+        let b = fork(&base, &[2.0], "b");
+        assert_eq!(a.total_work(), b.total_work());
+        let (winner, loser) = if a.last_hash() < b.last_hash() { (a, b) } else { (b, a) };
+        assert!(winner.is_better_than(&loser) && !loser.is_better_than(&winner));
+
+        // обе стороны сходятся на одной вершине
+        let mut kept = winner.clone();
+        assert!(kept.sync(&loser, &keys()).unwrap().is_none());
+        let mut switched = loser.clone();
+        let reorg = switched.sync(&winner, &keys()).unwrap().unwrap();
+        assert_eq!(reorg.common_ancestor, 2);
+        assert_eq!(hashes(&kept), hashes(&switched));
+    }
+
+    #[test]
+    fn work_outweighs_length() {
+        let base = fork(&Chain::from_blocks("key", vec![Block::genesis()]), &[1.0], "base");
+        let long = fork(&base, &[1.0, 1.0, 1.0], "long");
+        let heavy = fork(&base, &[5.0], "heavy");
+
+        let mut chain = long.clone();
+        let reorg = chain.sync(&heavy, &keys()).unwrap().unwrap();
+        assert_eq!(reorg.orphaned.iter().map(|b| b.index).collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(reorg.adopted.len(), 1);
+        assert_eq!(hashes(&chain), hashes(&heavy));
+
+        let mut chain = heavy.clone();
+        assert!(chain.sync(&long, &keys()).unwrap().is_none());
+        assert_eq!(hashes(&chain), hashes(&heavy));
+
+        // при равной работе побеждает длинная
+        let short = fork(&base, &[3.0], "short");
+        assert!(long.is_better_than(&short));
+    }
+
+    #[test]
+    fn foreign_genesis_is_rejected() {
+        let mut genesis = Block::genesis();
+        genesis.data_root = "другой мир".into();
+        genesis.hash = genesis.compute_hash();
+        let foreign = fork(&Chain::from_blocks("key", vec![genesis]), &[10.0], "foreign");
+        // сама по себе цепь связна, но начинается не с нашего генезиса
+        assert!(matches!(foreign.verify(), Err(ChainError::ForeignGenesis)));
+
+        let mut chain = Chain::from_blocks("key", vec![Block::genesis()]);
+        assert!(matches!(chain.sync(&foreign, &keys()), Err(ChainError::ForeignGenesis)));
+        assert_eq!(chain.height(), 1);
+    }
+
+    #[test]
+    fn branch_with_a_foreign_key_is_not_adopted() {
+        let base = Chain::from_blocks("key", vec![Block::genesis()]);
+        let mut chain = fork(&base, &[1.0], "mine");
+        let before = hashes(&chain);
+
+        // блок подписан ключом, которого нет в реестре под именем валидатора
+        let impostor = Identity::derive("node1", &Seed::new(99).stream("node/node1"));
+        let mut incoming = base.clone();
+        let index = incoming.height();
+        let block = Block::new(index, incoming.last_hash(), 1, "x".into(), "k".into(), &impostor, 9.0);
+        incoming.append(block).unwrap();
+
+        assert!(matches!(chain.sync(&incoming, &keys()), Err(ChainError::ForeignValidator { .. })));
+        assert_eq!(hashes(&chain), before);
+    }
+
+    #[test]
+    fn orphaned_blocks_are_rolled_back_on_disk() {
+        let dir: PathBuf = std::env::temp_dir().join(format!("organism-chain-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let storage = StorageConfig { enabled: true, dir: dir.clone(), fsync: false, segment_bytes: 1 << 20 };
+
+        let mut chain = Chain::open(&dir, "key", &storage).unwrap();
+        let signer = signer("node0");
+        for t in 1..=3 {
+            chain.add_block(format!("old-{}", t), "k".into(), &signer, t).unwrap();
+        }
+        let orphans: Vec<String> = chain.blocks[2..].iter().map(|b| b.hash.clone()).collect();
+
+        let base = Chain::from_blocks("key", chain.blocks[..2].to_vec());
+        let better = fork(&base, &[2.0, 2.0], "new");
+        let reorg = chain.sync(&better, &keys()).unwrap().unwrap();
+        assert_eq!(reorg.common_ancestor, 1);
+        assert_eq!(reorg.orphaned.iter().map(|b| b.hash.clone()).collect::<Vec<_>>(), orphans);
+
+        // индекс хранилища уже не знает выпавших блоков
+        for hash in orphans.iter() {
+            assert!(chain.block_by_hash(hash).unwrap().is_none());
+        }
+        assert_eq!(chain.hash_at(3), Some(better.last_hash()));
+        drop(chain);
+
+        let reopened = Chain::open(&dir, "key", &storage).unwrap();
+        assert_eq!(hashes(&reopened), hashes(&better));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Цепь `chain`, у которой блок #`index` испорчен функцией `tamper`
+    fn tampered(chain: &Chain, index: usize, tamper: impl FnOnce(&mut Block)) -> Chain {
+        let mut blocks = chain.blocks.clone();
+        tamper(&mut blocks[index]);
+        Chain::from_blocks("key", blocks)
+    }
+
+    #[test]
+    fn tampered_chains_fail_verify() {
+        let chain = fork(&Chain::from_blocks("key", vec![Block::genesis()]), &[1.0, 2.0, 3.0], "t");
+        assert!(chain.verify().is_ok());
+
+        // изменённое содержимое без пересчёта хэша
+        let changed = tampered(&chain, 2, |b| b.data_root = "подмена".into());
+        assert!(matches!(changed.verify(), Err(ChainError::HashMismatch { index: 2, .. })));
+
+        // хэш пересчитан, но подпись валидатора осталась от старого
+        let rehashed = tampered(&chain, 2, |b| {
+            b.data_root = "подмена".into();
+            b.hash = b.compute_hash();
+        });
+        assert!(matches!(rehashed.verify(), Err(ChainError::BadSignature { index: 2 })));
+
+        // блок ссылается не на предыдущий
+        let relinked = tampered(&chain, 3, |b| {
+            b.prev_hash = chain.blocks[1].hash.clone();
+            b.hash = b.compute_hash();
+        });
+        assert!(matches!(relinked.verify(), Err(ChainError::BrokenLink { index: 3, .. })));
+
+        // хэш и ссылки целы, но подпись валидатора — не от этого хэша
+        let other = chain.blocks[1].signature.clone();
+        let forged = tampered(&chain, 3, |b| b.signature = other);
+        assert!(matches!(forged.verify(), Err(ChainError::BadSignature { index: 3 })));
+
+        // блок выпал из середины
+        let mut blocks = chain.blocks.clone();
+        blocks.remove(1);
+        assert!(matches!(Chain::from_blocks("key", blocks).verify(), Err(ChainError::BadIndex { position: 1, found: 2 })));
+
+        let negative = tampered(&chain, 1, |b| {
+            b.work = -1.0;
+            b.hash = b.compute_hash();
+        });
+        assert!(matches!(negative.verify(), Err(ChainError::BadWork { index: 1, .. })));
+        assert!(matches!(Chain::from_blocks("key", vec![]).verify(), Err(ChainError::Empty)));
+    }
+
+    #[test]
+    fn tampered_stores_fail_to_open() {
+        let root: PathBuf = std::env::temp_dir().join(format!("organism-chain-tamper-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let storage = StorageConfig { enabled: true, dir: root.clone(), fsync: false, segment_bytes: 1 << 20 };
+        let chain = fork(&Chain::from_blocks("key", vec![Block::genesis()]), &[1.0, 2.0, 3.0], "t");
+
+        // хранилище из готовых блоков, в обход проверок `Chain`
+        let store = |name: &str, blocks: &[Block]| {
+            let dir = root.join(name);
+            let mut store = BlockStore::open(&dir, false, storage.segment_bytes).unwrap();
+            for block in blocks {
+                store.append(block).unwrap();
+            }
+            dir
+        };
+
+        let dir = store("intact", &chain.blocks);
+        assert_eq!(hashes(&Chain::open(&dir, "key", &storage).unwrap()), hashes(&chain));
+
+        let changed = tampered(&chain, 2, |b| b.data_root = "подмена".into());
+        let dir = store("changed", &changed.blocks);
+        assert!(matches!(Chain::open(&dir, "key", &storage), Err(ChainError::HashMismatch { index: 2, .. })));
+
+        let relinked = tampered(&chain, 3, |b| {
+            b.prev_hash = chain.blocks[1].hash.clone();
+            b.hash = b.compute_hash();
+        });
+        let dir = store("relinked", &relinked.blocks);
+        assert!(matches!(Chain::open(&dir, "key", &storage), Err(ChainError::BrokenLink { index: 3, .. })));
+
+        let other = chain.blocks[1].signature.clone();
+        let forged = tampered(&chain, 3, |b| b.signature = other);
+        let dir = store("forged", &forged.blocks);
+        assert!(matches!(Chain::open(&dir, "key", &storage), Err(ChainError::BadSignature { index: 3 })));
+
+        let mut genesis = Block::genesis();
+        genesis.data_root = "другой мир".into();
+        genesis.hash = genesis.compute_hash();
+        let dir = store("foreign", &[genesis]);
+        assert!(matches!(Chain::open(&dir, "key", &storage), Err(ChainError::ForeignGenesis)));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,595 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
+
+use crate::brain::{Brain, BrainSnapshot};
+use crate::chain::Chain;
+use crate::clock::SimClock;
+use crate::config::{NodeConfig, PopulationConfig};
+use crate::data_dir::DataRoot;
+use crate::netsim::{NetSim, NetsimState};
+use crate::consensus::{Ballots, Proposal};
+use crate::brain_policy::BrainPolicy;
+use crate::network::Network;
+use crate::economy::NetworkFund;
+use crate::energy::Energy;
+use crate::energy_ledger::{EnergyLedger, EnergyTotals, EnergyTransfer};
+use crate::events::{ChainKind, Event, EventLog};
+use crate::identity::{Identity, KeyRegistry};
+use crate::interaction::{Message, NetworkBus, OpenRequest};
+use crate::memory::BrainEvent;
+use crate::neuron::Neuron;
+use crate::node::{Node, IDENTITY_FILE};
+use crate::payload::{BlockBodies, BlockBody};
+use crate::rng::SimRng;
+use crate::synapse::SynapseChain;
+use crate::token_ledger::{Entry, TokenLedger, TokenTotals};
+use crate::token_amount::TokenAmount;
+use crate::wallet::Wallet;
+use crate::world::World;
+
+/// Версия формата. Увеличивается при любом несовместимом изменении структуры файла.
+pub const CHECKPOINT_VERSION: u32 = 8;
+
+/// 💾 Полный снимок организма на границе глобального тика.
+/// Восстановление из него продолжает прогон так, как будто остановки не было.
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    pub seed: u64,
+    pub tick: u64,
+    /// unix-время нулевого тика — метки памяти мозга остаются согласованными
+    pub epoch: i64,
+    pub nodes: Vec<NodeState>,
+    pub fund: TokenAmount,
+    pub brain: BrainState,
+    /// сообщения, ещё не доставленные фазой доставки
+    pub pending_messages: Vec<Message>,
+    /// следующий номер сообщения шины и запросы, ждущие ответов
+    #[serde(default)]
+    pub next_message_id: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub open_requests: Vec<OpenRequest>,
+    pub streams: StreamState,
+    /// итоги книги токенов
+    pub tokens: TokenTotals,
+    /// открытые ключи всех нод, включая умерших: имя → ключ
+    pub keys: BTreeMap<String, String>,
+    /// содержимое ключевых блоков по хэшу
+    pub bodies: BTreeMap<String, BlockBody>,
+    /// проводки и переводы энергии, ещё не попавшие в блок
+    pub pending_tokens: Vec<Entry>,
+    pub pending_transfers: Vec<EnergyTransfer>,
+    /// итоги учёта энергии: `/energy/budget` продолжается, а не начинается с нуля
+    pub energy: EnergyTotals,
+    /// поток случайности API
+    pub api_rng: SimRng,
+    /// сообщения в пути и поток RNG симулятора сети (есть, только если он включён)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub netsim: Option<NetsimState>,
+    /// подписки нод на темы шины, если нода подписана не на все
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub topics: BTreeMap<String, Vec<String>>,
+    /// объявленные ключевые блоки, ещё не набравшие голосов
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proposals: Vec<Proposal>,
+    /// номера последних записей журналов событий и сообщений: `--restore`
+    /// отрезает всё, что записано после снятия контрольной точки
+    pub event_seq: u64,
+    pub message_seq: u64,
+}
+
+/// Состояние одной ноды
+#[derive(Serialize, Deserialize)]
+pub struct NodeState {
+    pub name: String,
+    pub energy: f64,
+    pub balance: TokenAmount,
+    pub efficiency: f64,
+    pub altruism: f64,
+    pub resilience: f64,
+    pub experience: f64,
+    /// имя и секретный ключ ноды
+    pub identity: Identity,
+    pub data_chain: Chain,
+    pub key_chain: Chain,
+    pub synapse_chain: SynapseChain,
+    pub connections: Vec<String>,
+    pub neurons: Vec<Neuron>,
+    pub rng: SimRng,
+    pub children_spawned: u64,
+    pub params: NodeConfig,
+}
+
+/// Состояние мозга вместе с короткой и долгой памятью
+#[derive(Serialize, Deserialize)]
+pub struct BrainState {
+    pub aggressiveness: f64,
+    pub reward_history: Vec<f64>,
+    pub tick_counter: u64,
+    pub rng: SimRng,
+    pub short_memory: Vec<BrainEvent>,
+    pub long_memory: Vec<BrainEvent>,
+    /// выученное состояние политики мозга (есть только у `learned`)
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub policy: serde_json::Value,
+}
+
+/// Потоки RNG, которыми владеет планировщик
+#[derive(Serialize, Deserialize)]
+pub struct StreamState {
+    pub evolution: SimRng,
+    pub economy: SimRng,
+}
+
+/// Ошибка чтения или записи контрольной точки
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io { path: PathBuf, source: std::io::Error },
+    Format { path: PathBuf, message: String },
+    Version { path: PathBuf, found: u64 },
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io { path, source } => {
+                write!(f, "контрольная точка {}: {}", path.display(), source)
+            }
+            CheckpointError::Format { path, message } => {
+                write!(f, "повреждённая контрольная точка {}: {}", path.display(), message)
+            }
+            CheckpointError::Version { path, found } => write!(
+                f,
+                "контрольная точка {} имеет версию {}, а поддерживается {}",
+                path.display(),
+                found,
+                CHECKPOINT_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl Checkpoint {
+    /// Снять состояние мира. Вызывается планировщиком между тиками,
+    /// поэтому ни одна фаза не держит блокировки.
+    pub async fn capture(seed: u64, world: &World, streams: StreamState) -> Self {
+        let nodes = world.nodes.lock().await.clone();
+        let mut states = Vec::with_capacity(nodes.len());
+        for n in nodes.iter() {
+            let node = n.lock().await;
+            states.push(NodeState {
+                name: node.name.clone(),
+                energy: node.energy.lock().await.level,
+                balance: node.wallet.get_balance().await,
+                efficiency: node.efficiency,
+                altruism: node.altruism,
+                resilience: node.resilience,
+                experience: node.experience,
+                identity: node.identity.clone(),
+                data_chain: node.data_chain.lock().await.clone(),
+                key_chain: node.key_chain.lock().await.clone(),
+                synapse_chain: node.synapse_chain.lock().await.clone(),
+                connections: node.connections.lock().await.clone(),
+                neurons: node.neurons.lock().await.clone(),
+                rng: node.rng.lock().await.clone(),
+                children_spawned: node.children_spawned,
+                params: node.params,
+            });
+        }
+
+        let brain = {
+            let brain = world.brain.read().await;
+            let memory = brain.memory.lock().await;
+            let short_memory = memory.short.lock().await.clone();
+            let long_memory = memory.long.lock().await.clone();
+            let rng = brain.rng.lock().await.clone();
+            let policy = brain.policy.lock().await.state();
+            BrainState {
+                aggressiveness: brain.aggressiveness,
+                reward_history: brain.reward_history.clone(),
+                tick_counter: brain.tick_counter,
+                rng,
+                short_memory,
+                long_memory,
+                policy,
+            }
+        };
+
+        let fund = world.fund.lock().await.get_balance().await;
+
+        let pending_messages = world.net.pending().await;
+
+        let (next_message_id, open_requests) = world.net.open_requests();
+
+        Self {
+            version: CHECKPOINT_VERSION,
+            seed,
+            tick: world.clock.tick(),
+            epoch: world.clock.epoch(),
+            nodes: states,
+            fund,
+            brain,
+            pending_messages,
+            next_message_id,
+            open_requests,
+            streams,
+            tokens: world.tokens.totals(),
+            keys: world.keys.keys(),
+            bodies: world.bodies.bodies(),
+            pending_tokens: world.tokens.pending(),
+            pending_transfers: world.energy.pending_transfers(),
+            energy: world.energy.totals(),
+            api_rng: world.api_rng.lock().await.clone(),
+            netsim: world.netsim.state(),
+            topics: world.net.custom_topics(),
+            proposals: world.ballots.proposals(),
+            event_seq: world.log.seq(),
+            message_seq: world.net.messages().seq(),
+        }
+    }
+
+    /// Часы, стоящие на тике контрольной точки
+    pub fn clock(&self, tick_ms: u64) -> SimClock {
+        let clock = SimClock::new(self.epoch, tick_ms);
+        clock.set_tick(self.tick);
+        clock
+    }
+
+    /// Собрать из снимка новый мир. Возвращает также потоки RNG планировщика.
+    /// `clock` — из [`Checkpoint::clock`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn into_world(
+        self,
+        clock: SimClock,
+        log: EventLog,
+        net: Arc<NetworkBus>,
+        population: PopulationConfig,
+        mut policy: Box<dyn BrainPolicy>,
+        data: DataRoot,
+        network: Network,
+        netsim: NetSim,
+    ) -> (World, StreamState) {
+        let energy = EnergyLedger::from_totals(self.energy, self.pending_transfers);
+        let tokens = TokenLedger::from_totals(clock.clone(), self.tokens, self.pending_tokens);
+        let keys = KeyRegistry::from_keys(self.keys).with_random_keys(network.is_enabled());
+
+        let nodes: Vec<_> = self
+            .nodes
+            .into_iter()
+            .map(|state| {
+                state.restore(log.clone(), energy.clone(), tokens.clone(), clock.clone(), keys.clone(), data.clone())
+            })
+            .collect();
+
+        let fund = NetworkFund::new(log.clone(), tokens.clone());
+        *fund.total.lock().await = self.fund;
+
+        // журнал дописывается: отмечаем точку продолжения и заново объявляем ноды
+        log.emit(Event::Restored {
+            fund: self.fund,
+            aggressiveness: self.brain.aggressiveness,
+        });
+        let mut names = Vec::with_capacity(nodes.len());
+        for node in nodes.iter() {
+            let node = node.lock().await;
+            node.log_spawn(None).await;
+            names.push(node.name.clone());
+        }
+        net.sync_members(&names);
+        for (name, topics) in self.topics.iter() {
+            if let Err(e) = net.set_topics(name, topics) {
+                println!("⚠️ Подписки {} не восстановлены: {}", name, e);
+            }
+        }
+
+        if !self.brain.policy.is_null() {
+            // состояние другой политики не подходит — она начинает с нуля
+            if let Err(e) = policy.restore(self.brain.policy) {
+                println!("⚠️ Состояние политики {} не восстановлено: {}", policy.name(), e);
+            }
+        }
+        let mut brain = Brain::new(self.brain.rng, clock.clone(), population, log.clone(), policy);
+        brain.aggressiveness = self.brain.aggressiveness;
+        brain.reward_history = self.brain.reward_history;
+        brain.tick_counter = self.brain.tick_counter;
+        {
+            let memory = brain.memory.lock().await;
+            *memory.short.lock().await = self.brain.short_memory;
+            *memory.long.lock().await = self.brain.long_memory;
+        }
+        let brain = Arc::new(RwLock::new(brain));
+        let snapshot = BrainSnapshot::from_brain_lock(&brain).await;
+
+        // сообщения уже посчитаны отправленными в прошлом процессе
+        net.requeue(self.pending_messages);
+        net.restore_requests(self.next_message_id, self.open_requests);
+        match self.netsim {
+            Some(state) if netsim.is_enabled() => netsim.restore(state),
+            // симулятор выключили: сообщения из пути доставляются как обычные
+            Some(state) => net.requeue(state.in_flight.into_iter().map(|flight| flight.message).collect()),
+            None => {}
+        }
+
+        let world = World {
+            nodes: Arc::new(Mutex::new(nodes)),
+            fund: Arc::new(Mutex::new(fund)),
+            brain,
+            snapshot: Arc::new(RwLock::new(snapshot)),
+            net,
+            clock,
+            log,
+            energy,
+            tokens,
+            keys,
+            bodies: BlockBodies::from_bodies(self.bodies),
+            data,
+            network,
+            netsim,
+            ballots: Ballots::from_proposals(self.proposals),
+            api_rng: Arc::new(Mutex::new(self.api_rng)),
+        };
+        (world, self.streams)
+    }
+
+    /// Записать атомарно: сначала во временный файл, затем переименовать —
+    /// оборванная запись не испортит предыдущую точку
+    pub fn save(&self, path: &Path) -> Result<(), CheckpointError> {
+        let io_err = |source| CheckpointError::Io { path: path.to_path_buf(), source };
+
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(io_err)?;
+        }
+
+        let tmp = path.with_extension("json.tmp");
+        let file = File::create(&tmp).map_err(io_err)?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, self).map_err(|e| CheckpointError::Format {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        let file = writer.into_inner().map_err(|e| io_err(e.into_error()))?;
+        file.sync_all().map_err(io_err)?;
+        fs::rename(&tmp, path).map_err(io_err)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, CheckpointError> {
+        let text = fs::read_to_string(path).map_err(|source| CheckpointError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let format_err = |e: serde_json::Error| CheckpointError::Format {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        };
+
+        // версию проверяем до разбора остального — у чужого формата поля могут не совпасть
+        let value: serde_json::Value = serde_json::from_str(&text).map_err(format_err)?;
+        let found = value["version"].as_u64().unwrap_or(0);
+        if found != CHECKPOINT_VERSION as u64 {
+            return Err(CheckpointError::Version { path: path.to_path_buf(), found });
+        }
+
+        let checkpoint: Checkpoint = serde_json::from_value(value).map_err(format_err)?;
+
+        // цепи и ключи проверяем сразу — изменённый вручную блок не должен попасть в мир
+        for node in checkpoint.nodes.iter() {
+            let public_key = node.identity.public_key();
+            if node.identity.name != node.name || checkpoint.keys.get(&node.name) != Some(&public_key) {
+                return Err(CheckpointError::Format {
+                    path: path.to_path_buf(),
+                    message: format!("ключ ноды {} не совпадает с реестром ключей", node.name),
+                });
+            }
+            for chain in [&node.data_chain, &node.key_chain] {
+                chain.verify().map_err(|e| CheckpointError::Format {
+                    path: path.to_path_buf(),
+                    message: format!("цепь {}: {}", chain.name, e),
+                })?;
+            }
+        }
+        Ok(checkpoint)
+    }
+
+    /// Имя файла для автоматической точки: номер тика с ведущими нулями,
+    /// чтобы сортировка по имени совпадала с порядком тиков
+    pub fn file_name(tick: u64) -> String {
+        format!("checkpoint_{:010}.json", tick)
+    }
+}
+
+impl NodeState {
+    fn restore(
+        self,
+        log: EventLog,
+        ledger: EnergyLedger,
+        tokens: TokenLedger,
+        clock: SimClock,
+        keys: KeyRegistry,
+        data: DataRoot,
+    ) -> Arc<Mutex<Node>> {
+        // хранилище переписывается по снимку: после точки восстановления цепи могли уйти дальше
+        let (mut data_chain, mut key_chain) = (self.data_chain, self.key_chain);
+        data.attach_chain(&self.name, ChainKind::Data, &mut data_chain);
+        data.attach_chain(&self.name, ChainKind::Key, &mut key_chain);
+        // ключ из контрольной точки заменяет тот, что лежал в каталоге ноды
+        if let Err(e) = data.write_json(&self.name, IDENTITY_FILE, &self.identity) {
+            println!("⚠️ {}: ключ не сохранён: {}", self.name, e);
+        }
+        let energy = Energy { level: self.energy, node_name: self.name.clone(), log: log.clone(), ledger };
+        let wallet = Wallet::new(&self.name, log.clone(), tokens);
+        // Wallet::new() только что создал Arc — блокировка гарантированно свободна
+        *wallet.balance.try_lock().expect("новый кошелёк") = self.balance;
+
+        Arc::new(Mutex::new(Node {
+            name: self.name,
+            energy: Arc::new(Mutex::new(energy)),
+            efficiency: self.efficiency,
+            altruism: self.altruism,
+            resilience: self.resilience,
+            experience: self.experience,
+            data_chain: Arc::new(Mutex::new(data_chain)),
+            key_chain: Arc::new(Mutex::new(key_chain)),
+            synapse_chain: Arc::new(Mutex::new(self.synapse_chain)),
+            connections: Arc::new(Mutex::new(self.connections)),
+            neurons: Arc::new(Mutex::new(self.neurons)),
+            wallet,
+            rng: Arc::new(Mutex::new(self.rng)),
+            children_spawned: self.children_spawned,
+            params: self.params,
+            log,
+            clock,
+            identity: self.identity,
+            keys,
+            data,
+        }))
+    }
+}
+
+/// Удалить старые автоматические точки, оставив `keep` последних (0 = не удалять)
+pub fn prune(dir: &Path, keep: usize) -> std::io::Result<()> {
+    if keep == 0 {
+        return Ok(());
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with("checkpoint_") && n.ends_with(".json"))
+                .unwrap_or(false)
+        })
+        .collect();
+    files.sort();
+    let excess = files.len().saturating_sub(keep);
+    for old in files.into_iter().take(excess) {
+        fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+/// Ответ планировщика на запрос точки: тик и путь к файлу
+pub type CheckpointReply = oneshot::Sender<Result<(u64, PathBuf), String>>;
+
+/// 📮 Запрос контрольной точки у планировщика (для `POST /checkpoint`).
+/// Снимок делается планировщиком между тиками, а не посреди фазы.
+#[derive(Clone)]
+pub struct CheckpointHandle {
+    tx: mpsc::Sender<CheckpointReply>,
+}
+
+impl CheckpointHandle {
+    pub fn channel() -> (Self, mpsc::Receiver<CheckpointReply>) {
+        let (tx, rx) = mpsc::channel(8);
+        (Self { tx }, rx)
+    }
+
+    pub async fn request(&self) -> Result<(u64, PathBuf), String> {
+        let (reply, answer) = oneshot::channel();
+        self.tx
+            .send(reply)
+            .await
+            .map_err(|_| "планировщик остановлен".to_string())?;
+        answer.await.map_err(|_| "планировщик не ответил".to_string())?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::interaction::MessageType;
+    use crate::message_log::MessageLog;
+    use crate::rng::Seed;
+    use crate::scheduler::Scheduler;
+    use rand::Rng;
+
+    fn scheduler(config: &Config, world: World) -> Scheduler {
+        Scheduler::new(
+            config.scheduler.clone(),
+            config.economy,
+            config.consensus,
+            config.checkpoint.clone(),
+            Seed::new(1),
+            world,
+        )
+    }
+
+    #[tokio::test]
+    async fn save_load_restore_gives_the_same_checkpoint() {
+        let dir = std::env::temp_dir().join(format!("organism-checkpoint-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut config = Config::default();
+        config.simulation.initial_nodes = 3;
+        config.population.cull_above = 6;
+        config.population.cull_keep = 4;
+        config.checkpoint.dir = dir.join("a");
+
+        let world = crate::test_world(&config).await;
+        let mut original = scheduler(&config, world.clone());
+        for _ in 0..25 {
+            original.step().await;
+        }
+        // недоставленное сообщение и сдвинутый поток API тоже должны пережить снимок
+        world.net.send(Message::new("node0", Some("node1"), MessageType::ValidateBlock, 1.0, Some("ping"))).await;
+        world.api_rng.lock().await.gen::<u64>();
+
+        let (sent, queued) = (world.net.stats().sent, world.net.stats().queued);
+        let path = original.save_checkpoint().await.unwrap();
+        original.save_checkpoint().await.unwrap();
+        assert_eq!(world.net.stats().sent, sent, "снимок очереди посчитан как отправка");
+        assert_eq!(world.net.stats().queued, queued);
+
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        assert_eq!(checkpoint.tick, 25);
+        assert_eq!(checkpoint.pending_messages.len(), queued);
+        assert!(checkpoint.pending_messages.iter().any(|m| m.content.as_deref() == Some("ping")));
+        assert!(checkpoint.energy.ticks_checked > 0);
+
+        let clock = checkpoint.clock(config.scheduler.tick_ms);
+        let bus = Arc::new(NetworkBus::new(
+            config.simulation.bus_capacity,
+            config.simulation.inbox_capacity,
+            config.simulation.request_timeout_ticks,
+            MessageLog::disabled(),
+        ));
+        let (restored, streams) = checkpoint
+            .into_world(
+                clock,
+                EventLog::disabled(),
+                bus,
+                config.population,
+                crate::brain_policy::from_config(&config.brain),
+                DataRoot::disabled(),
+                Network::new(&config.network),
+                NetSim::disabled(),
+            )
+            .await;
+        assert_eq!(restored.net.stats().sent, 0);
+        assert_eq!(restored.net.stats().queued, queued);
+        let budget = |world: &World| serde_json::to_string(&world.energy.budget(25, 0.0)).unwrap();
+        assert_eq!(budget(&restored), budget(&world));
+
+        config.checkpoint.dir = dir.join("b");
+        let mut again = scheduler(&config, restored.clone());
+        again.restore_streams(streams);
+        let copy = again.save_checkpoint().await.unwrap();
+        assert!(fs::read(&path).unwrap() == fs::read(&copy).unwrap(), "контрольная точка изменилась после круга");
+
+        // оба мира продолжают одинаково, включая поток API
+        original.step().await;
+        again.step().await;
+        assert_eq!(world.api_rng.lock().await.gen::<u64>(), restored.api_rng.lock().await.gen::<u64>());
+        let next = [original.save_checkpoint().await.unwrap(), again.save_checkpoint().await.unwrap()];
+        assert!(fs::read(&next[0]).unwrap() == fs::read(&next[1]).unwrap(), "прогоны разошлись после восстановления");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// ⏱️ Логические часы симуляции.
+/// Время считается от номера глобального тика, а не от системных часов,
+/// поэтому прогон с одним seed даёт одинаковые метки времени.
+#[derive(Clone, Debug)]
+pub struct SimClock {
+    tick: Arc<AtomicU64>,
+    epoch: i64,
+    tick_ms: u64,
+}
+
+impl SimClock {
+    /// `epoch` — unix-время нулевого тика, `tick_ms` — длительность тика
+    pub fn new(epoch: i64, tick_ms: u64) -> Self {
+        Self {
+            tick: Arc::new(AtomicU64::new(0)),
+            epoch,
+            tick_ms,
+        }
+    }
+
+    /// Текущий глобальный тик
+    pub fn tick(&self) -> u64 {
+        self.tick.load(Ordering::SeqCst)
+    }
+
+    pub fn set_tick(&self, tick: u64) {
+        self.tick.store(tick, Ordering::SeqCst);
+    }
+
+    /// Unix-время нулевого тика
+    pub fn epoch(&self) -> i64 {
+        self.epoch
+    }
+
+    /// Время симуляции (unix-секунды) для текущего тика
+    pub fn now(&self) -> i64 {
+        self.epoch + (self.tick() * self.tick_ms / 1000) as i64
+    }
+}
@@ -0,0 +1,987 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use crate::batch::BatchOptions;
+use crate::brain_policy::PolicyKind;
+use crate::replay::ReplayOptions;
+use crate::scheduler::{Phase, RunMode, SchedulerConfig};
+use crate::token_amount::{TokenAmount, MINOR_PER_TOKEN};
+
+/// Файл конфигурации по умолчанию (читается, только если существует)
+pub const DEFAULT_CONFIG_PATH: &str = "organism.toml";
+
+/// Префикс переменных окружения: `ORGANISM__NODE__DECAY_PER_TICK=2`
+const ENV_PREFIX: &str = "ORGANISM__";
+
+/// ⚙️ Все настраиваемые константы симуляции
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Главный seed; если не задан — выбирается случайно
+    pub seed: Option<u64>,
+    pub simulation: SimulationConfig,
+    pub scheduler: SchedulerConfig,
+    pub node: NodeConfig,
+    pub population: PopulationConfig,
+    pub brain: BrainConfig,
+    pub economy: EconomyConfig,
+    pub mining: MiningConfig,
+    pub consensus: ConsensusConfig,
+    pub api: ApiConfig,
+    pub checkpoint: CheckpointConfig,
+    pub events: EventsConfig,
+    pub storage: StorageConfig,
+    pub network: NetworkConfig,
+    pub netsim: NetsimConfig,
+    pub messages: MessagesConfig,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulationConfig {
+    /// Сколько нод создаётся при старте
+    pub initial_nodes: usize,
+    /// Ёмкость исходящей очереди NetworkBus
+    pub bus_capacity: usize,
+    /// Ёмкость входящей очереди каждой ноды на одну фазу доставки
+    pub inbox_capacity: usize,
+    /// Сколько тиков запрос (просьба о помощи) ждёт ответов
+    pub request_timeout_ticks: u64,
+}
+
+/// Параметры жизненного цикла ноды (наследуются потомками)
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeConfig {
+    pub decay_per_tick: f64,
+    pub replication_threshold: f64,
+    pub reproduction_cost: f64,
+    pub mutation_rate: f64,
+}
+
+/// Контроль численности в `Brain::evolve_network`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PopulationConfig {
+    /// при большем числе нод эволюция пропускается
+    pub max_before_skip: usize,
+    /// при большем числе нод слабейшие удаляются...
+    pub cull_above: usize,
+    /// ...пока не останется столько сильнейших
+    pub cull_keep: usize,
+    /// ноды с энергией не выше порога считаются мёртвыми
+    pub death_energy: f64,
+    /// потолок энергии после подпитки выживших
+    pub survivor_energy_cap: f64,
+}
+
+/// Политика мозга (см. `brain_policy.rs`)
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BrainConfig {
+    /// `default`, `threshold`, `random`, `greedy-balancer` или `learned`
+    pub policy: PolicyKind,
+    /// `threshold`, `greedy-balancer`: эволюция при средней энергии не ниже
+    pub evolve_above: f64,
+    /// `threshold`, `greedy-balancer`: помощь при разбросе энергии не меньше
+    pub help_spread: f64,
+    /// `learned`: доля случайных проб
+    pub epsilon: f64,
+    /// `learned`: насколько оценка сдвигается к новому результату
+    pub learning_rate: f64,
+}
+
+/// Параметры `EconomyCycle`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EconomyConfig {
+    /// подпитка из фонда включается, когда средняя энергия ниже порога
+    pub fund_trigger_avg_energy: f64,
+    /// сколько токенов фонд тратит на одну подпитку
+    pub fund_injection_cost: TokenAmount,
+    /// сколько энергии получает каждая нода при подпитке
+    pub fund_injection_energy: f64,
+    /// плата за цикл экономики с каждого кошелька
+    pub upkeep_fee: TokenAmount,
+    /// минимальная энергия, ниже которой нода не опускается
+    pub energy_floor: f64,
+}
+
+/// Выплаты `POST /mine/:id` и `POST /update/:id`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MiningConfig {
+    pub reward: TokenAmount,
+    pub validator_cut: TokenAmount,
+    pub fund_cut: TokenAmount,
+    pub update_fee: TokenAmount,
+    pub update_fund_cut: TokenAmount,
+}
+
+/// Раунды Proof-of-Contribution (см. `consensus.rs`).
+/// Вес ноды = energy_weight·энергия + experience_weight·опыт + quality_weight·качество обучения.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConsensusConfig {
+    pub energy_weight: f64,
+    pub experience_weight: f64,
+    pub quality_weight: f64,
+    /// сколько проходов по задаче раунда делает нейрон (плюс по одному за единицу опыта)
+    pub training_epochs: u32,
+    /// награда победителю раунда
+    pub reward: TokenAmount,
+    /// доля голосов нод (включая победителя), нужная, чтобы объявленный блок вошёл в цепь
+    pub quorum: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    pub bind: String,
+}
+
+/// Автоматические контрольные точки (см. `checkpoint.rs`)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CheckpointConfig {
+    /// сохранять мир каждые N тиков (0 = только по запросу `POST /checkpoint`)
+    pub every: u64,
+    /// каталог для файлов контрольных точек
+    pub dir: PathBuf,
+    /// сколько последних файлов хранить (0 = все)
+    pub keep: usize,
+}
+
+/// Журнал событий для воспроизведения (см. `events.rs`, `replay.rs`)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventsConfig {
+    pub enabled: bool,
+    /// файл JSON Lines; новый прогон начинает его заново, `--restore` продолжает
+    /// с записи контрольной точки
+    pub path: PathBuf,
+}
+
+/// Журнал сообщений шины (см. `message_log.rs`, `GET /messages`)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MessagesConfig {
+    pub enabled: bool,
+    /// файл JSON Lines; новый прогон начинает его заново, `--restore` продолжает
+    /// с записи контрольной точки
+    pub path: PathBuf,
+    /// сколько последних сообщений хранить
+    pub capacity: usize,
+}
+
+/// Данные нод на диске (см. `data_dir.rs`, `block_store.rs`)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// хранить цепи и состояние нод на диске (по умолчанию выключено): без него всё
+    /// живёт только в памяти, и прогон с тем же seed не зависит от прошлых прогонов
+    pub enabled: bool,
+    /// корень данных: манифест и по подкаталогу на ноду
+    pub dir: PathBuf,
+    /// дожидаться записи на диск после каждого блока
+    pub fsync: bool,
+    /// размер сегмента в байтах, после которого начинается следующий файл
+    pub segment_bytes: u64,
+}
+
+/// Организм из нескольких процессов (см. `network.rs`)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// обмениваться сообщениями шины, блоками и нейронами с другими процессами по TCP
+    pub enabled: bool,
+    /// адрес, на котором процесс принимает пакеты пиров
+    pub listen: String,
+    /// стартовые адреса других процессов; остальных пиров процесс узнаёт от них
+    pub peers: Vec<String>,
+    /// префикс имён нод процесса; пустой — `p<порт>_`, чтобы имена в разных процессах не совпали
+    pub name_prefix: String,
+    /// как часто (в секундах) отправлять пирам нейроны и запрашивать их key_chain
+    pub sync_secs: u64,
+    /// интервал (в секундах) между `Ping` в открытом соединении и между попытками переподключения
+    pub keepalive_secs: u64,
+    /// больше стольких пиров одновременно процесс не держит
+    pub max_peers: usize,
+    /// штраф, при котором пир банится (см. `peers.rs`)
+    pub ban_threshold: u32,
+    /// на сколько секунд банится пир
+    pub ban_secs: u64,
+}
+
+/// Симулятор сети между нодами (см. `netsim.rs`).
+/// Задержки — в тиках; вероятности — от 0 до 1.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetsimConfig {
+    /// доставлять сообщения шины через симулятор; выключено — сразу, как раньше
+    pub enabled: bool,
+    /// задержка доставки по умолчанию
+    pub latency_ticks: u64,
+    /// к задержке добавляется случайно от 0 до `jitter_ticks`
+    pub jitter_ticks: u64,
+    /// доля потерянных сообщений
+    pub loss: f64,
+    /// доля сообщений, доставленных дважды
+    pub duplicate: f64,
+    /// доля сообщений, придержанных так, что их обгоняют отправленные позже
+    pub reorder: f64,
+    /// параметры отдельных связей; подходящие правила применяются по порядку
+    pub links: Vec<LinkRule>,
+    /// расписание разделений сети
+    pub partitions: Vec<PartitionRule>,
+}
+
+/// 🔗 Параметры связи `from → to` на отрезке тиков `[at, until)`.
+/// `from` и `to` — шаблоны нод (см. `netsim::expand`), `*` — любая нода;
+/// незаданные параметры берутся из `[netsim]`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LinkRule {
+    #[serde(default = "any_node")]
+    pub from: String,
+    #[serde(default = "any_node")]
+    pub to: String,
+    #[serde(default)]
+    pub at: u64,
+    pub until: Option<u64>,
+    pub latency_ticks: Option<u64>,
+    pub jitter_ticks: Option<u64>,
+    pub loss: Option<f64>,
+    pub duplicate: Option<f64>,
+    pub reorder: Option<f64>,
+}
+
+fn any_node() -> String {
+    "*".into()
+}
+
+/// ✂️ Разделение: с тика `at` до `heal` (без `heal` — навсегда) ноды из разных
+/// групп не слышат друг друга. Ноды вне групп связь не теряют.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartitionRule {
+    pub at: u64,
+    pub heal: Option<u64>,
+    pub groups: Vec<Vec<String>>,
+}
+
+impl NetworkConfig {
+    /// Префикс имён нод этого процесса (без сети — пустой)
+    pub fn node_prefix(&self) -> String {
+        if !self.enabled {
+            return String::new();
+        }
+        if !self.name_prefix.is_empty() {
+            return self.name_prefix.clone();
+        }
+        let port = self.listen.parse::<SocketAddr>().map(|a| a.port()).unwrap_or_default();
+        format!("p{}_", port)
+    }
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self { initial_nodes: 10, bus_capacity: 1024, inbox_capacity: 256, request_timeout_ticks: 5 }
+    }
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        Self {
+            decay_per_tick: 1.0,
+            replication_threshold: 1.0, // вместо 80
+            reproduction_cost: 0.0,
+            mutation_rate: 0.05,
+        }
+    }
+}
+
+impl Default for PopulationConfig {
+    fn default() -> Self {
+        Self {
+            max_before_skip: 400,
+            cull_above: 120,
+            cull_keep: 80,
+            death_energy: 5.0,
+            survivor_energy_cap: 120.0,
+        }
+    }
+}
+
+impl Default for BrainConfig {
+    fn default() -> Self {
+        Self { policy: PolicyKind::Default, evolve_above: 60.0, help_spread: 20.0, epsilon: 0.1, learning_rate: 0.2 }
+    }
+}
+
+impl Default for EconomyConfig {
+    fn default() -> Self {
+        Self {
+            fund_trigger_avg_energy: 25.0,
+            fund_injection_cost: TokenAmount::whole(5),
+            fund_injection_energy: 10.0,
+            upkeep_fee: TokenAmount::from_minor(MINOR_PER_TOKEN / 2),
+            energy_floor: 5.0,
+        }
+    }
+}
+
+impl Default for MiningConfig {
+    fn default() -> Self {
+        Self {
+            reward: TokenAmount::whole(15),
+            validator_cut: TokenAmount::whole(3),
+            fund_cut: TokenAmount::whole(2),
+            update_fee: TokenAmount::whole(1),
+            update_fund_cut: TokenAmount::from_minor(MINOR_PER_TOKEN / 2),
+        }
+    }
+}
+
+impl Default for ConsensusConfig {
+    fn default() -> Self {
+        Self {
+            energy_weight: 1.0,
+            experience_weight: 10.0,
+            quality_weight: 50.0,
+            training_epochs: 3,
+            reward: TokenAmount::whole(2),
+            quorum: 0.67,
+        }
+    }
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self { bind: "127.0.0.1:3000".into() }
+    }
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self { enabled: false, path: PathBuf::from("events.jsonl") }
+    }
+}
+
+impl Default for MessagesConfig {
+    fn default() -> Self {
+        Self { enabled: false, path: PathBuf::from("messages.jsonl"), capacity: 10_000 }
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: "127.0.0.1:4000".into(),
+            peers: Vec::new(),
+            name_prefix: String::new(),
+            sync_secs: 5,
+            keepalive_secs: 10,
+            max_peers: 8,
+            ban_threshold: 100,
+            ban_secs: 600,
+        }
+    }
+}
+
+impl Default for NetsimConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            latency_ticks: 0,
+            jitter_ticks: 0,
+            loss: 0.0,
+            duplicate: 0.0,
+            reorder: 0.0,
+            links: Vec::new(),
+            partitions: Vec::new(),
+        }
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self { enabled: false, dir: PathBuf::from("data"), fsync: true, segment_bytes: 64 * 1024 * 1024 }
+    }
+}
+
+impl Default for CheckpointConfig {
+    fn default() -> Self {
+        Self { every: 0, dir: PathBuf::from("checkpoints"), keep: 5 }
+    }
+}
+
+/// Ошибка загрузки конфигурации — с понятным текстом для человека
+#[derive(Debug)]
+pub enum ConfigError {
+    Io { path: PathBuf, source: std::io::Error },
+    Parse { path: PathBuf, message: String },
+    Override { key: String, message: String },
+    Args(String),
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => {
+                write!(f, "не удалось прочитать конфиг {}: {}", path.display(), source)
+            }
+            ConfigError::Parse { path, message } => {
+                write!(f, "ошибка в конфиге {}: {}", path.display(), message)
+            }
+            ConfigError::Override { key, message } => {
+                write!(f, "некорректное переопределение '{}': {}", key, message)
+            }
+            ConfigError::Args(message) => write!(f, "{}", message),
+            ConfigError::Invalid(problems) => {
+                writeln!(f, "конфигурация не прошла проверку:")?;
+                for p in problems {
+                    writeln!(f, "  • {}", p)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Загрузить конфиг: файл (`--config` или `organism.toml`), затем переменные
+    /// окружения `ORGANISM__СЕКЦИЯ__ПАРАМЕТР`, затем аргументы командной строки.
+    pub fn load(cli: &CliArgs) -> Result<Self, ConfigError> {
+        let env: Vec<(String, String)> = std::env::vars().collect();
+        Self::load_from(cli, &env)
+    }
+
+    pub fn load_from(cli: &CliArgs, env: &[(String, String)]) -> Result<Self, ConfigError> {
+        let mut config = match &cli.config_path {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        };
+
+        let mut overrides = Vec::new();
+        for (name, value) in env {
+            if name == "ORGANISM_SEED" {
+                // короткий алиас, который был до появления конфига
+                overrides.push(("seed".to_string(), value.clone()));
+            } else if let Some(path) = name.strip_prefix(ENV_PREFIX) {
+                let key = path.to_lowercase().replace("__", ".");
+                overrides.push((key, value.clone()));
+            }
+        }
+        // порядок переменных окружения не определён — сортируем для воспроизводимости
+        overrides.sort();
+        overrides.extend(cli.overrides.iter().cloned());
+
+        for (key, value) in &overrides {
+            config = config.with_override(key, value)?;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Прочитать TOML или JSON (по расширению файла)
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let is_json = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("json"))
+            .unwrap_or(false);
+
+        let parsed = if is_json {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&text).map_err(|e| e.to_string())
+        };
+
+        parsed.map_err(|message| ConfigError::Parse { path: path.to_path_buf(), message })
+    }
+
+    /// Переопределить одно поле по пути `секция.параметр`.
+    /// Значение разбирается как литерал TOML, иначе берётся как строка.
+    pub fn with_override(&self, key: &str, raw: &str) -> Result<Self, ConfigError> {
+        let err = |message: String| ConfigError::Override { key: key.to_string(), message };
+
+        let mut root = toml::Value::try_from(self).map_err(|e| err(e.to_string()))?;
+        let value = parse_literal(raw);
+
+        let mut parts = key.split('.').peekable();
+        let mut cursor = &mut root;
+        while let Some(part) = parts.next() {
+            let table = cursor
+                .as_table_mut()
+                .ok_or_else(|| err(format!("'{}' не является секцией", part)))?;
+            if parts.peek().is_none() {
+                // необязательные поля (seed) в TOML отсутствуют, пока не заданы
+                if !table.contains_key(part) && !OPTIONAL_KEYS.contains(&key) {
+                    return Err(err("нет такого параметра".into()));
+                }
+                table.insert(part.to_string(), value);
+                break;
+            }
+            cursor = table
+                .get_mut(part)
+                .ok_or_else(|| err(format!("нет такой секции '{}'", part)))?;
+        }
+
+        root.try_into().map_err(|e: toml::de::Error| err(e.message().to_string()))
+    }
+
+    /// Проверить значения; все проблемы собираются в одну ошибку
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        let mut check = |ok: bool, message: String| {
+            if !ok {
+                problems.push(message);
+            }
+        };
+
+        check(self.simulation.initial_nodes > 0, "simulation.initial_nodes должен быть больше 0".into());
+        check(self.simulation.bus_capacity > 0, "simulation.bus_capacity должен быть больше 0".into());
+        check(self.simulation.inbox_capacity > 0, "simulation.inbox_capacity должен быть больше 0".into());
+        check(self.scheduler.tick_ms > 0, "scheduler.tick_ms должен быть больше 0".into());
+
+        for (name, value) in [
+            ("node.decay_per_tick", self.node.decay_per_tick),
+            ("node.replication_threshold", self.node.replication_threshold),
+            ("node.reproduction_cost", self.node.reproduction_cost),
+            ("population.death_energy", self.population.death_energy),
+            ("population.survivor_energy_cap", self.population.survivor_energy_cap),
+            ("brain.evolve_above", self.brain.evolve_above),
+            ("brain.help_spread", self.brain.help_spread),
+            ("economy.fund_trigger_avg_energy", self.economy.fund_trigger_avg_energy),
+            ("economy.fund_injection_energy", self.economy.fund_injection_energy),
+            ("economy.energy_floor", self.economy.energy_floor),
+            ("consensus.energy_weight", self.consensus.energy_weight),
+            ("consensus.experience_weight", self.consensus.experience_weight),
+            ("consensus.quality_weight", self.consensus.quality_weight),
+        ] {
+            check(
+                value.is_finite() && value >= 0.0,
+                format!("{} должен быть конечным неотрицательным числом (сейчас {})", name, value),
+            );
+        }
+
+        check(
+            self.consensus.quorum > 0.0 && self.consensus.quorum <= 1.0,
+            format!("consensus.quorum должен быть в диапазоне (0, 1] (сейчас {})", self.consensus.quorum),
+        );
+        check(
+            (0.0..=1.0).contains(&self.brain.epsilon),
+            format!("brain.epsilon должен быть в диапазоне 0..1 (сейчас {})", self.brain.epsilon),
+        );
+        check(
+            self.brain.learning_rate > 0.0 && self.brain.learning_rate <= 1.0,
+            format!("brain.learning_rate должен быть в диапазоне (0, 1] (сейчас {})", self.brain.learning_rate),
+        );
+        check(
+            (0.0..=1.0).contains(&self.node.mutation_rate),
+            format!("node.mutation_rate должен быть в диапазоне 0..1 (сейчас {})", self.node.mutation_rate),
+        );
+        check(
+            self.population.cull_keep <= self.population.cull_above,
+            format!(
+                "population.cull_keep ({}) не может быть больше population.cull_above ({})",
+                self.population.cull_keep, self.population.cull_above
+            ),
+        );
+        check(
+            self.population.cull_above <= self.population.max_before_skip,
+            format!(
+                "population.cull_above ({}) не может быть больше population.max_before_skip ({})",
+                self.population.cull_above, self.population.max_before_skip
+            ),
+        );
+        check(
+            self.mining.update_fund_cut <= self.mining.update_fee,
+            format!(
+                "mining.update_fund_cut ({}) не может быть больше mining.update_fee ({})",
+                self.mining.update_fund_cut, self.mining.update_fee
+            ),
+        );
+        check(
+            self.api.bind.parse::<SocketAddr>().is_ok(),
+            format!("api.bind '{}' не является адресом вида 127.0.0.1:3000", self.api.bind),
+        );
+        check(
+            !self.checkpoint.dir.as_os_str().is_empty(),
+            "checkpoint.dir не может быть пустым".into(),
+        );
+        check(
+            !self.events.enabled || !self.events.path.as_os_str().is_empty(),
+            "events.path не может быть пустым, если журнал включён".into(),
+        );
+        check(
+            !self.messages.enabled || !self.messages.path.as_os_str().is_empty(),
+            "messages.path не может быть пустым, если журнал включён".into(),
+        );
+        check(self.messages.capacity > 0, "messages.capacity должен быть больше 0".into());
+        check(
+            !self.storage.enabled || !self.storage.dir.as_os_str().is_empty(),
+            "storage.dir не может быть пустым, если хранилище включено".into(),
+        );
+        check(self.storage.segment_bytes > 0, "storage.segment_bytes должен быть больше 0".into());
+        if self.network.enabled {
+            check(
+                self.network.listen.parse::<SocketAddr>().is_ok(),
+                format!("network.listen '{}' не является адресом вида 127.0.0.1:4000", self.network.listen),
+            );
+            for peer in self.network.peers.iter() {
+                check(
+                    peer.parse::<SocketAddr>().is_ok(),
+                    format!("network.peers: '{}' не является адресом вида 127.0.0.1:4001", peer),
+                );
+            }
+            check(self.network.sync_secs > 0, "network.sync_secs должен быть больше 0".into());
+            check(self.network.keepalive_secs > 0, "network.keepalive_secs должен быть больше 0".into());
+            check(self.network.max_peers > 0, "network.max_peers должен быть больше 0".into());
+            check(self.network.ban_threshold > 0, "network.ban_threshold должен быть больше 0".into());
+        }
+        if self.netsim.enabled {
+            let mut probabilities = vec![
+                ("netsim.loss".to_string(), self.netsim.loss),
+                ("netsim.duplicate".to_string(), self.netsim.duplicate),
+                ("netsim.reorder".to_string(), self.netsim.reorder),
+            ];
+            for (i, link) in self.netsim.links.iter().enumerate() {
+                for (name, value) in [("loss", link.loss), ("duplicate", link.duplicate), ("reorder", link.reorder)] {
+                    if let Some(value) = value {
+                        probabilities.push((format!("netsim.links[{}].{}", i, name), value));
+                    }
+                }
+                for pattern in [&link.from, &link.to] {
+                    if let Err(e) = crate::netsim::expand(pattern) {
+                        check(false, format!("netsim.links[{}]: {}", i, e));
+                    }
+                }
+                check(
+                    link.until.is_none_or(|until| until > link.at),
+                    format!("netsim.links[{}]: until должен быть больше at ({})", i, link.at),
+                );
+            }
+            for (name, value) in probabilities {
+                check(
+                    (0.0..=1.0).contains(&value),
+                    format!("{} должен быть в диапазоне 0..1 (сейчас {})", name, value),
+                );
+            }
+            for (i, partition) in self.netsim.partitions.iter().enumerate() {
+                check(
+                    partition.groups.len() >= 2,
+                    format!("netsim.partitions[{}]: нужно хотя бы две группы нод", i),
+                );
+                check(
+                    partition.heal.is_none_or(|heal| heal > partition.at),
+                    format!("netsim.partitions[{}]: heal должен быть больше at ({})", i, partition.at),
+                );
+                for pattern in partition.groups.iter().flatten() {
+                    if let Err(e) = crate::netsim::expand(pattern) {
+                        check(false, format!("netsim.partitions[{}]: {}", i, e));
+                    }
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+/// Поля-`Option`, которых может не быть в сериализованном конфиге
+const OPTIONAL_KEYS: &[&str] = &["seed"];
+
+fn parse_literal(raw: &str) -> toml::Value {
+    // `toml` разбирает только документы, поэтому оборачиваем значение в `v = ...`
+    toml::from_str::<toml::Table>(&format!("v = {}", raw))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+/// ⌨️ Вся командная строка, разобранная за один проход. Неизвестный флаг или
+/// флаг режима, который не включён, — ошибка, а не молча пропущенная опечатка.
+#[derive(Clone, Debug, Default)]
+pub struct CliArgs {
+    /// `--config путь`
+    pub config_path: Option<PathBuf>,
+    /// переопределения конфига в порядке флагов
+    pub overrides: Vec<(String, String)>,
+    /// `--headless` с `--ticks`, `--report`, `--sample-every`
+    pub batch: Option<BatchOptions>,
+    /// `--restore путь`
+    pub restore: Option<PathBuf>,
+    /// `--replay путь` с `--until`
+    pub replay: Option<ReplayOptions>,
+    /// `--gc`
+    pub gc: bool,
+}
+
+impl CliArgs {
+    /// Аргументы процесса без имени программы
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        Self::parse(&args)
+    }
+
+    /// `--config путь`, `--set секция.параметр=значение` и короткие формы:
+    /// `--seed N`, `--fast`, `--tick-ms N`, `--every <фаза>=N`,
+    /// `--listen адрес` и `--peer адрес` (можно несколько раз) — они включают сеть.
+    /// Режимы: `--headless [--ticks N] [--report путь] [--sample-every N]`,
+    /// `--restore путь`, `--replay путь [--until N]`, `--gc`.
+    pub fn parse(args: &[String]) -> Result<Self, ConfigError> {
+        let mut cli = Self::default();
+        let mut peers = Vec::new();
+        let mut headless = false;
+        let mut batch = BatchOptions::default();
+        let mut batch_flag = None;
+        let mut until = None;
+        let mut it = args.iter();
+        while let Some(arg) = it.next() {
+            let mut value_for = |flag: &str| {
+                it.next()
+                    .cloned()
+                    .ok_or_else(|| ConfigError::Args(format!("{} требует значение", flag)))
+            };
+            let number = |flag: &str, value: String| {
+                value.parse::<u64>().map_err(|_| ConfigError::Args(format!("некорректный {} '{}'", flag, value)))
+            };
+            match arg.as_str() {
+                "--config" => cli.config_path = Some(PathBuf::from(value_for("--config")?)),
+                "--set" => {
+                    let value = value_for("--set")?;
+                    let (key, v) = value.split_once('=').ok_or_else(|| {
+                        ConfigError::Args(format!("--set '{}': ожидается секция.параметр=значение", value))
+                    })?;
+                    cli.overrides.push((key.trim().to_string(), v.trim().to_string()));
+                }
+                "--seed" => cli.overrides.push(("seed".into(), value_for("--seed")?)),
+                "--fast" => cli.overrides.push((
+                    "scheduler.mode".into(),
+                    format!("\"{}\"", RunMode::AsFastAsPossible.name()),
+                )),
+                "--tick-ms" => cli.overrides.push(("scheduler.tick_ms".into(), value_for("--tick-ms")?)),
+                "--listen" => {
+                    cli.overrides.push(("network.listen".into(), value_for("--listen")?));
+                    cli.overrides.push(("network.enabled".into(), "true".into()));
+                }
+                "--peer" => peers.push(value_for("--peer")?),
+                "--every" => {
+                    let value = value_for("--every")?;
+                    let (name, every) = value.split_once('=').ok_or_else(|| {
+                        ConfigError::Args(format!("--every '{}': ожидается <фаза>=N", value))
+                    })?;
+                    let phase = Phase::from_name(name).ok_or_else(|| {
+                        let known: Vec<_> = Phase::ALL.iter().map(|p| p.name()).collect();
+                        ConfigError::Args(format!("неизвестная фаза '{}' (есть: {})", name, known.join(", ")))
+                    })?;
+                    cli.overrides.push((format!("scheduler.{}_every", phase.name()), every.to_string()));
+                }
+                "--headless" => headless = true,
+                "--ticks" => {
+                    batch.ticks = number("--ticks", value_for("--ticks")?)?;
+                    batch_flag = Some("--ticks");
+                }
+                "--report" => {
+                    batch.report_path = PathBuf::from(value_for("--report")?);
+                    batch_flag = Some("--report");
+                }
+                "--sample-every" => {
+                    batch.sample_every = number("--sample-every", value_for("--sample-every")?)?;
+                    batch_flag = Some("--sample-every");
+                }
+                "--restore" => cli.restore = Some(PathBuf::from(value_for("--restore")?)),
+                "--replay" => {
+                    let path = PathBuf::from(value_for("--replay")?);
+                    cli.replay = Some(ReplayOptions { path, until: None });
+                }
+                "--until" => until = Some(number("--until", value_for("--until")?)?),
+                "--gc" => cli.gc = true,
+                other => match other.strip_prefix("--seed=") {
+                    Some(v) => cli.overrides.push(("seed".into(), v.to_string())),
+                    None => return Err(ConfigError::Args(format!("неизвестный аргумент '{}'", other))),
+                },
+            }
+        }
+
+        if !peers.is_empty() {
+            // массив TOML: ["127.0.0.1:4001", "127.0.0.1:4002"]
+            let list: Vec<String> = peers.iter().map(|p| format!("{:?}", p)).collect();
+            cli.overrides.push(("network.peers".into(), format!("[{}]", list.join(", "))));
+            cli.overrides.push(("network.enabled".into(), "true".into()));
+        }
+        if headless {
+            if batch.sample_every == 0 {
+                return Err(ConfigError::Args("--sample-every должен быть больше нуля".into()));
+            }
+            cli.batch = Some(batch);
+        } else if let Some(flag) = batch_flag {
+            return Err(ConfigError::Args(format!("{} действует только вместе с --headless", flag)));
+        }
+        match (&mut cli.replay, until) {
+            (Some(replay), until) => replay.until = until,
+            (None, Some(_)) => return Err(ConfigError::Args("--until действует только вместе с --replay".into())),
+            (None, None) => {}
+        }
+        Ok(cli)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, ConfigError> {
+        CliArgs::parse(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn unknown_flags_are_rejected() {
+        for args in [&["--seeed", "4"][..], &["--headless", "--tick", "10"], &["run.json"], &["--set"]] {
+            assert!(matches!(parse(args), Err(ConfigError::Args(_))), "{:?}", args);
+        }
+        assert!(parse(&["--ticks", "10"]).is_err(), "--ticks без --headless");
+        assert!(parse(&["--until", "5"]).is_err(), "--until без --replay");
+    }
+
+    #[test]
+    fn every_mode_is_read_in_one_pass() {
+        let cli = parse(&[
+            "--headless", "--seed=7", "--ticks", "200", "--report", "r.csv", "--fast",
+            "--restore", "c.json", "--set", "storage.enabled=false", "--peer", "127.0.0.1:4001",
+        ])
+        .unwrap();
+        let batch = cli.batch.unwrap();
+        assert_eq!((batch.ticks, batch.sample_every), (200, 1));
+        assert_eq!(batch.report_path, PathBuf::from("r.csv"));
+        assert_eq!(cli.restore, Some(PathBuf::from("c.json")));
+        assert!(!cli.gc && cli.replay.is_none());
+        let keys: Vec<_> = cli.overrides.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["seed", "scheduler.mode", "storage.enabled", "network.peers", "network.enabled"]);
+
+        let cli = parse(&["--replay", "events.jsonl", "--until", "850"]).unwrap();
+        assert_eq!(cli.replay.unwrap().until, Some(850));
+        assert!(parse(&["--gc"]).unwrap().gc);
+    }
+
+    fn problems_of(config: &Config) -> Vec<String> {
+        match config.validate() {
+            Err(ConfigError::Invalid(problems)) => problems,
+            other => panic!("ожидалась ошибка проверки, получено {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_values_are_reported_together_and_readably() {
+        assert!(Config::default().validate().is_ok());
+
+        let mut config = Config::default();
+        config.simulation.initial_nodes = 0;
+        config.node.decay_per_tick = f64::NAN;
+        config.consensus.quorum = 1.5;
+        config.population.cull_keep = config.population.cull_above + 1;
+        config.api.bind = "nowhere".into();
+        let problems = problems_of(&config);
+        assert_eq!(problems.len(), 5, "{:#?}", problems);
+        for expected in [
+            "simulation.initial_nodes должен быть больше 0",
+            "node.decay_per_tick должен быть конечным неотрицательным числом (сейчас NaN)",
+            "consensus.quorum должен быть в диапазоне (0, 1] (сейчас 1.5)",
+            "api.bind 'nowhere' не является адресом вида 127.0.0.1:3000",
+        ] {
+            assert!(problems.iter().any(|p| p == expected), "нет '{}' в {:#?}", expected, problems);
+        }
+        assert!(problems.iter().any(|p| p.starts_with("population.cull_keep (")));
+
+        // каждая проблема — отдельной строкой под общим заголовком
+        let text = ConfigError::Invalid(problems).to_string();
+        assert!(text.starts_with("конфигурация не прошла проверку:\n"));
+        assert_eq!(text.lines().filter(|l| l.starts_with("  • ")).count(), 5);
+
+        // сеть и симулятор проверяются, только когда включены
+        let mut config = Config::default();
+        config.netsim.loss = 2.0;
+        config.netsim.partitions = vec![PartitionRule { at: 5, heal: Some(5), groups: vec![vec!["node0".into()]] }];
+        assert!(config.validate().is_ok());
+        config.netsim.enabled = true;
+        let problems = problems_of(&config);
+        assert_eq!(problems.len(), 3, "{:#?}", problems);
+        assert!(problems.contains(&"netsim.loss должен быть в диапазоне 0..1 (сейчас 2)".to_string()));
+        assert!(problems.contains(&"netsim.partitions[0]: нужно хотя бы две группы нод".to_string()));
+    }
+
+    #[test]
+    fn env_then_command_line_override_the_file() {
+        let dir = std::env::temp_dir().join(format!("organism-config-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("organism.toml");
+        std::fs::write(
+            &path,
+            "[simulation]\ninitial_nodes = 7\n\n[node]\ndecay_per_tick = 2.0\n\n[scheduler]\ntick_ms = 800\nbrain_every = 4\n",
+        )
+        .unwrap();
+        let env = |vars: &[(&str, &str)]| -> Vec<(String, String)> {
+            vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        let cli = |args: &[&str]| {
+            let mut all = vec!["--config", path.to_str().unwrap()];
+            all.extend_from_slice(args);
+            parse(&all).unwrap()
+        };
+
+        let config = Config::load_from(
+            &cli(&["--set", "node.decay_per_tick=4.0", "--seed", "9", "--every", "brain=6", "--set", "scheduler.brain_every=8"]),
+            &env(&[
+                ("ORGANISM__SCHEDULER__TICK_MS", "250"),
+                ("ORGANISM__NODE__DECAY_PER_TICK", "3.0"),
+                ("ORGANISM_SEED", "5"),
+                ("ORGANISM__ECONOMY__UPKEEP_FEE", "1.25"),
+                ("HOME", "/root"),
+            ]),
+        )
+        .unwrap();
+        // файл, где ничего не переопределено
+        assert_eq!(config.simulation.initial_nodes, 7);
+        // окружение поверх файла
+        assert_eq!(config.scheduler.tick_ms, 250);
+        assert_eq!(config.economy.upkeep_fee, "1.25".parse().unwrap());
+        // командная строка поверх окружения, флаги — по порядку
+        assert_eq!(config.node.decay_per_tick, 4.0);
+        assert_eq!(config.seed, Some(9));
+        assert_eq!(config.scheduler.brain_every, 8);
+
+        let config = Config::load_from(&cli(&[]), &env(&[("ORGANISM_SEED", "5")])).unwrap();
+        assert_eq!((config.seed, config.node.decay_per_tick), (Some(5), 2.0));
+
+        // опечатка в ключе или значении называет ключ
+        let err = Config::load_from(&cli(&[]), &env(&[("ORGANISM__NODE__DECAY", "1")])).unwrap_err();
+        assert!(matches!(&err, ConfigError::Override { key, .. } if key == "node.decay"), "{}", err);
+        let err = Config::load_from(&cli(&["--set", "scheduler.tick_ms=fast"]), &[]).unwrap_err();
+        assert!(err.to_string().starts_with("некорректное переопределение 'scheduler.tick_ms'"), "{}", err);
+        // переопределённое значение тоже проходит проверку
+        let err = Config::load_from(&cli(&[]), &env(&[("ORGANISM__SIMULATION__INITIAL_NODES", "0")])).unwrap_err();
+        assert!(matches!(&err, ConfigError::Invalid(p) if p.len() == 1), "{}", err);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
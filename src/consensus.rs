@@ -0,0 +1,647 @@
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::chain::{Block, Chain};
+use crate::config::ConsensusConfig;
+use crate::events::{ChainKind, Event};
+use crate::interaction::{Directory, Message, MessageType};
+use crate::neuron::Neuron;
+use crate::node::Node;
+use crate::payload::{BlockBody, PayloadItem};
+use crate::rng::SimRng;
+use crate::world::World;
+
+/// Сколько примеров в учебной задаче раунда
+const TASK_SAMPLES: usize = 8;
+const LEARNING_RATE: f64 = 0.5;
+
+/// 📜 Доказательство вклада ноды в раунд консенсуса.
+/// Всё, из чего считается вес, лежит в самом доказательстве —
+/// любая нода может пересчитать выбор победителя.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContributionProof {
+    pub node: String,
+    pub height: u64,
+    pub prev_hash: String,
+    pub energy: f64,
+    pub experience: f64,
+    /// качество обучения нейрона на задаче раунда, 0..1
+    pub quality: f64,
+    pub weight: f64,
+    /// SHA-256 весов обученного нейрона
+    pub neuron_hash: String,
+    #[serde(skip)]
+    pub neuron: Neuron,
+}
+
+/// Итог раунда
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoundOutcome {
+    pub height: u64,
+    pub winner: String,
+    pub weight: f64,
+    pub total_weight: f64,
+    pub participants: usize,
+    pub hash: String,
+}
+
+/// Детерминированный поток случайности раунда: зависит только от вершины цепи
+/// и метки, поэтому одинаков у всех нод и не трогает их собственные RNG
+pub fn round_rng(prev_hash: &str, height: u64, label: &str) -> SimRng {
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash.as_bytes());
+    hasher.update(height.to_le_bytes());
+    hasher.update(label.as_bytes());
+    SimRng::from_seed(hasher.finalize().into())
+}
+
+/// 🎓 Общая задача раунда: три входа, ответ 1, если их сумма больше 1.5
+pub fn round_task(prev_hash: &str, height: u64) -> Vec<(Vec<f64>, f64)> {
+    let mut rng = round_rng(prev_hash, height, "task");
+    (0..TASK_SAMPLES)
+        .map(|_| {
+            let inputs = vec![rng.gen::<f64>(), rng.gen::<f64>(), rng.gen::<f64>()];
+            let expected = if inputs.iter().sum::<f64>() > 1.5 { 1.0 } else { 0.0 };
+            (inputs, expected)
+        })
+        .collect()
+}
+
+/// Обучить нейрон ноды на задаче раунда и вернуть его вместе с качеством (1 − средняя ошибка·2).
+/// Опыт добавляет проходы, эффективность ускоряет обучение.
+pub fn train(
+    node_name: &str,
+    prev_hash: &str,
+    height: u64,
+    experience: f64,
+    efficiency: f64,
+    config: &ConsensusConfig,
+) -> (Neuron, f64) {
+    let task = round_task(prev_hash, height);
+    let mut init = round_rng(prev_hash, height, node_name);
+    let mut neuron = Neuron::new(height, 3, &mut init);
+
+    let epochs = config.training_epochs as usize + experience.clamp(0.0, 20.0) as usize;
+    let lr = LEARNING_RATE * efficiency.clamp(0.1, 1.5);
+    for _ in 0..epochs {
+        for (inputs, expected) in task.iter() {
+            neuron.learn(inputs, *expected, lr);
+        }
+    }
+
+    // оцениваем уже без обучения
+    let loss: f64 = task
+        .iter()
+        .map(|(inputs, expected)| {
+            let out = neuron.activate(inputs);
+            0.5 * (expected - out).powi(2)
+        })
+        .sum::<f64>()
+        / task.len() as f64;
+    (neuron, (1.0 - 2.0 * loss).clamp(0.0, 1.0))
+}
+
+pub fn neuron_hash(neuron: &Neuron) -> String {
+    let mut hasher = Sha256::new();
+    for w in neuron.weights.iter() {
+        hasher.update(w.to_le_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// ⚖️ Выбрать победителя: лотерея, взвешенная по вкладу.
+/// Случайное число берётся из вершины цепи, доказательства упорядочены по имени ноды —
+/// результат одинаков у всех, кто видит те же доказательства.
+pub fn choose_winner(proofs: &[ContributionProof], prev_hash: &str, height: u64) -> Option<usize> {
+    let mut order: Vec<usize> = (0..proofs.len()).filter(|&i| proofs[i].weight > 0.0).collect();
+    order.sort_by(|&a, &b| proofs[a].node.cmp(&proofs[b].node));
+    let total: f64 = order.iter().map(|&i| proofs[i].weight).sum();
+    if order.is_empty() || total <= 0.0 {
+        return None;
+    }
+
+    let target = round_rng(prev_hash, height, "winner").gen::<f64>() * total;
+    let mut acc = 0.0;
+    for &i in order.iter() {
+        acc += proofs[i].weight;
+        if target < acc {
+            return Some(i);
+        }
+    }
+    order.last().copied()
+}
+
+/// 📦 Содержимое ключевого блока: нейрон победителя, переводы энергии и проводки
+/// токенов, накопленные с прошлого блока, плюс доказательства раунда по имени ноды
+pub fn round_body(proofs: &[ContributionProof], winner: &ContributionProof, world: &World) -> BlockBody {
+    let mut items = vec![PayloadItem::Neuron {
+        node: winner.node.clone(),
+        id: winner.neuron.id,
+        neuron_hash: winner.neuron_hash.clone(),
+    }];
+    items.extend(world.energy.take_transfers().into_iter().map(PayloadItem::EnergyTransfer));
+    items.extend(world.tokens.take_pending().into_iter().map(PayloadItem::TokenTx));
+
+    let mut proofs = proofs.to_vec();
+    proofs.sort_by(|a, b| a.node.cmp(&b.node));
+    BlockBody { items, proofs }
+}
+
+/// 🔗 Выбрать лучшую проверенную `key_chain` по правилу выбора форка
+/// (`Chain::is_better_than`) и синхронизировать с ней остальные ноды:
+/// отстающие дотягиваются, разошедшиеся переходят на неё с откатом выпавших блоков.
+/// Возвращает эталонную цепь, на которую будет строиться следующий блок.
+async fn sync_key_chains(nodes: &[Arc<Mutex<Node>>]) -> Option<Chain> {
+    let mut reference: Option<Chain> = None;
+    for n in nodes.iter() {
+        let node = n.lock().await;
+        let chain = node.key_chain.lock().await;
+        let better = reference.as_ref().is_none_or(|r| chain.is_better_than(r));
+        if better && chain.verify().is_ok() {
+            reference = Some(chain.clone());
+        }
+    }
+    let reference = reference?;
+
+    for n in nodes.iter() {
+        let node = n.lock().await;
+        if let Err(e) = node.sync_chain(ChainKind::Key, &reference).await {
+            println!("⚠️ [PoC] {} не синхронизировал key_chain: {}", node.name, e);
+        }
+    }
+    Some(reference)
+}
+
+/// 🗳️ Один раунд Proof-of-Contribution:
+/// синхронизация → доказательства всех нод → один победитель → объявление
+/// блока на шине. Ноды раунда проверяют блок и голосуют ответами на объявление;
+/// набрав `consensus.quorum` голосов «за», блок входит в `key_chain` каждой ноды
+/// (`finalize`). Содержимое блока ложится в `world.bodies`.
+pub async fn run_round(nodes: &[Arc<Mutex<Node>>], config: &ConsensusConfig, world: &World) -> Option<RoundOutcome> {
+    let reference = sync_key_chains(nodes).await?;
+    let height = reference.blocks.len() as u64;
+    let prev_hash = reference.last_hash();
+
+    let mut proofs = Vec::with_capacity(nodes.len());
+    for n in nodes.iter() {
+        let node = n.lock().await;
+        proofs.push(node.contribute(height, &prev_hash, config).await);
+    }
+
+    let Some(winner_idx) = choose_winner(&proofs, &prev_hash, height) else {
+        println!("😴 [PoC] раунд {}: ни у кого нет вклада", height);
+        return None;
+    };
+    let total_weight: f64 = proofs.iter().map(|p| p.weight).sum();
+    let winner = &proofs[winner_idx];
+    // доказательства идут в порядке нод — блок подписывает сам победитель
+    let signer = nodes[winner_idx].lock().await.identity.clone();
+    let body = round_body(&proofs, winner, world);
+
+    // работа блока — весь вклад раунда: ветка, за которой стояло больше нод, тяжелее
+    let block = Block::new(
+        height,
+        prev_hash,
+        world.clock.now(),
+        body.data_root(),
+        body.key_root(),
+        &signer,
+        total_weight,
+    );
+    world.bodies.insert(&block.hash, body);
+
+    let outcome = RoundOutcome {
+        height,
+        winner: winner.node.clone(),
+        weight: winner.weight,
+        total_weight,
+        participants: proofs.len(),
+        hash: block.hash.clone(),
+    };
+
+    // 📣 блок объявляется на шине; голосуют все ноды раунда, победитель — сразу «за»
+    let voters: BTreeSet<String> = proofs.iter().map(|p| p.node.clone()).collect();
+    let needed = ((voters.len() as f64 * config.quorum).ceil() as usize).clamp(1, voters.len());
+    let announcement = Message::new(
+        &winner.node,
+        None,
+        MessageType::BlockAnnouncement,
+        height as f64,
+        Some(&serde_json::to_string(&block).unwrap_or_default()),
+    );
+    let (id, _) = world.net.open_request(announcement, &signer, world.clock.tick()).await;
+    world.log.emit(Event::BlockProposed {
+        height,
+        proposer: winner.node.clone(),
+        hash: block.hash.clone(),
+        voters: voters.len(),
+        needed,
+    });
+    println!(
+        "📣 [PoC] {} объявил блок #{} ({} из {} голосов нужно)",
+        winner.node, height, needed, voters.len()
+    );
+
+    let proposal = Proposal {
+        announcement: id,
+        block,
+        neuron: winner.neuron.clone(),
+        outcome: outcome.clone(),
+        approvals: BTreeSet::from([winner.node.clone()]),
+        rejections: BTreeSet::new(),
+        voters,
+        needed,
+        config: *config,
+    };
+    // единственной ноде голосовать некому
+    if proposal.approved() {
+        let directory: Directory = proofs.iter().map(|p| p.node.clone()).zip(nodes.iter().cloned()).collect();
+        let proposer = nodes[winner_idx].lock().await;
+        finalize(proposal, &proposer, &directory, world).await;
+    } else {
+        world.ballots.open(proposal);
+    }
+    Some(outcome)
+}
+
+/// 🗳️ Объявленный, но ещё не принятый ключевой блок
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Proposal {
+    /// номер объявления на шине: голоса приходят ответами на него
+    pub announcement: u64,
+    pub block: Block,
+    /// нейрон победителя — записывается в его DataChain при принятии
+    pub neuron: Neuron,
+    pub outcome: RoundOutcome,
+    /// ноды раунда; голоса остальных не считаются
+    pub voters: BTreeSet<String>,
+    /// сколько голосов «за» нужно (`consensus.quorum` от числа голосующих)
+    pub needed: usize,
+    pub approvals: BTreeSet<String>,
+    pub rejections: BTreeSet<String>,
+    pub config: ConsensusConfig,
+}
+
+impl Proposal {
+    pub fn approved(&self) -> bool {
+        self.approvals.len() >= self.needed
+    }
+
+    /// Даже если все оставшиеся проголосуют «за», кворума не будет
+    pub fn hopeless(&self) -> bool {
+        self.voters.len() - self.rejections.len() < self.needed
+    }
+}
+
+/// Чем закончился голос
+pub enum Verdict {
+    /// голос учтён, решения пока нет
+    Pending,
+    /// кворум набран — блок надо принять
+    Finalize(Proposal),
+    /// кворум уже не набрать
+    Reject(Proposal),
+    /// голос не к открытому блоку или не от ноды раунда
+    Ignored,
+}
+
+/// 🗳️ Урна: объявленные блоки по номеру объявления.
+/// Клонирование дешёвое.
+#[derive(Clone, Default)]
+pub struct Ballots {
+    inner: Arc<std::sync::Mutex<BTreeMap<u64, Proposal>>>,
+}
+
+impl Ballots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Урна из контрольной точки
+    pub fn from_proposals(proposals: Vec<Proposal>) -> Self {
+        let ballots = Self::new();
+        for proposal in proposals {
+            ballots.open(proposal);
+        }
+        ballots
+    }
+
+    pub fn open(&self, proposal: Proposal) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.insert(proposal.announcement, proposal);
+        }
+    }
+
+    /// Учесть голос `voter` за блок объявления `announcement`.
+    /// Решённый блок покидает урну.
+    pub fn vote(&self, announcement: u64, voter: &str, approve: bool) -> Verdict {
+        let Ok(mut inner) = self.inner.lock() else {
+            return Verdict::Ignored;
+        };
+        let Some(proposal) = inner.get_mut(&announcement).filter(|p| p.voters.contains(voter)) else {
+            return Verdict::Ignored;
+        };
+        // повторный голос (дубль в сети) ничего не меняет
+        if proposal.approvals.contains(voter) || proposal.rejections.contains(voter) {
+            return Verdict::Pending;
+        }
+        if approve {
+            proposal.approvals.insert(voter.to_string());
+        } else {
+            proposal.rejections.insert(voter.to_string());
+        }
+        if proposal.approved() {
+            return inner.remove(&announcement).map(Verdict::Finalize).unwrap_or(Verdict::Ignored);
+        }
+        if proposal.hopeless() {
+            return inner.remove(&announcement).map(Verdict::Reject).unwrap_or(Verdict::Ignored);
+        }
+        Verdict::Pending
+    }
+
+    /// Голосование по объявлению не уложилось в срок запроса
+    pub fn expire(&self, announcement: u64) -> Option<Proposal> {
+        self.inner.lock().ok()?.remove(&announcement)
+    }
+
+    /// Открытые голосования (для контрольной точки)
+    pub fn proposals(&self) -> Vec<Proposal> {
+        self.inner.lock().map(|inner| inner.values().cloned().collect()).unwrap_or_default()
+    }
+}
+
+/// ✅ Кворум набран: блок входит в `key_chain` каждой ноды через `finalize_keyblock`,
+/// победитель получает награду. `held` — нода, чья блокировка уже взята
+/// (победитель); остальные ноды `directory` блокируются по очереди.
+pub async fn finalize(proposal: Proposal, held: &Node, directory: &Directory, world: &World) {
+    // блок, который уже не встаёт в цепь победителя, опоздал — его место занял другой
+    if let Err(e) = held.validate_keyblock(&proposal.block).await {
+        reject(proposal, world, &format!("не встаёт в цепь победителя: {}", e));
+        return;
+    }
+    world.net.close(proposal.announcement);
+    accept(held, &proposal).await;
+    for (name, n) in directory.iter() {
+        if *name != held.name {
+            accept(&*n.lock().await, &proposal).await;
+        }
+    }
+
+    let outcome = &proposal.outcome;
+    world.log.emit(Event::BlockFinalized {
+        height: outcome.height,
+        hash: outcome.hash.clone(),
+        approvals: proposal.approvals.len(),
+        rejections: proposal.rejections.len(),
+    });
+    world.log.emit(Event::ConsensusRound {
+        height: outcome.height,
+        winner: outcome.winner.clone(),
+        weight: outcome.weight,
+        total_weight: outcome.total_weight,
+        participants: outcome.participants,
+        hash: outcome.hash.clone(),
+    });
+    println!(
+        "👑 [PoC] блок #{} → {} (вес {:.2} из {:.2}, участников {}, голосов за {} из {})",
+        outcome.height,
+        outcome.winner,
+        outcome.weight,
+        outcome.total_weight,
+        outcome.participants,
+        proposal.approvals.len(),
+        proposal.voters.len()
+    );
+}
+
+async fn accept(node: &Node, proposal: &Proposal) {
+    let block = &proposal.block;
+    if node.name == proposal.outcome.winner {
+        node.reward_consensus(block, proposal.neuron.clone(), &proposal.config).await;
+    }
+    if let Err(e) = node.finalize_keyblock(block).await {
+        println!("⚠️ [PoC] {} не принял блок #{}: {}", node.name, block.index, e);
+    }
+}
+
+/// ❌ Блок не принят: переводы энергии и проводки токенов из его содержимого
+/// возвращаются в очередь и попадут в следующий блок
+pub fn reject(proposal: Proposal, world: &World, reason: &str) {
+    world.net.close(proposal.announcement);
+    if let Some(body) = world.bodies.remove(&proposal.block.hash) {
+        let mut transfers = Vec::new();
+        let mut entries = Vec::new();
+        for item in body.items {
+            match item {
+                PayloadItem::EnergyTransfer(transfer) => transfers.push(transfer),
+                PayloadItem::TokenTx(entry) => entries.push(entry),
+                _ => {}
+            }
+        }
+        world.energy.requeue_transfers(transfers);
+        world.tokens.requeue(entries);
+    }
+    world.log.emit(Event::BlockRejected {
+        height: proposal.outcome.height,
+        hash: proposal.outcome.hash.clone(),
+        approvals: proposal.approvals.len(),
+        rejections: proposal.rejections.len(),
+        reason: reason.to_string(),
+    });
+    println!("❌ [PoC] блок #{} от {} не принят: {}", proposal.outcome.height, proposal.outcome.winner, reason);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::Block;
+    use crate::config::Config;
+    use crate::energy_ledger::EnergyTransfer;
+    use crate::payload::BlockBody;
+    use crate::rng::Seed;
+    use crate::scheduler::Scheduler;
+    use crate::token_amount::TokenAmount;
+    use crate::token_ledger::{Account, Entry, TokenReason};
+
+    fn proof(node: &str, weight: f64) -> ContributionProof {
+        ContributionProof {
+            node: node.into(),
+            height: 3,
+            prev_hash: "tip".into(),
+            energy: 50.0,
+            experience: 1.0,
+            quality: 0.5,
+            weight,
+            neuron_hash: String::new(),
+            neuron: Neuron::default(),
+        }
+    }
+
+    #[test]
+    fn winner_depends_only_on_the_proofs_and_the_tip() {
+        let proofs = vec![proof("node2", 1.0), proof("node0", 3.0), proof("node1", 0.5), proof("node3", 2.0)];
+        let winner = |proofs: &[ContributionProof], tip: &str| choose_winner(proofs, tip, 3).map(|i| proofs[i].node.clone());
+
+        let first = winner(&proofs, "tip").unwrap();
+        assert_eq!(winner(&proofs, "tip").unwrap(), first);
+        // порядок, в котором пришли доказательства, не важен
+        let mut reversed = proofs.clone();
+        reversed.reverse();
+        assert_eq!(winner(&reversed, "tip").unwrap(), first);
+
+        // разные вершины дают разных победителей, но каждый раз тех же
+        let picks: BTreeSet<String> = (0..32).map(|i| winner(&proofs, &format!("tip-{}", i)).unwrap()).collect();
+        assert!(picks.len() > 1);
+        for i in 0..32 {
+            let tip = format!("tip-{}", i);
+            assert_eq!(winner(&proofs, &tip), winner(&reversed, &tip));
+        }
+
+        // без вклада победителя нет, нулевой вклад не выигрывает
+        assert_eq!(choose_winner(&[proof("node0", 0.0)], "tip", 3), None);
+        assert_eq!(choose_winner(&[], "tip", 3), None);
+        let one = vec![proof("node0", 0.0), proof("node1", 1.0)];
+        assert_eq!(winner(&one, "tip").as_deref(), Some("node1"));
+    }
+
+    #[tokio::test]
+    async fn rounds_keep_key_chains_in_agreement() {
+        let mut config = Config::default();
+        config.simulation.initial_nodes = 4;
+        // только раунды консенсуса и доставка голосов
+        let s = &mut config.scheduler;
+        (s.help_every, s.nodes_every, s.evolution_every, s.economy_every, s.brain_every, s.snapshot_every) = (0, 0, 0, 0, 0, 0);
+        (s.consensus_every, s.delivery_every) = (3, 1);
+
+        let world = crate::test_world(&config).await;
+        let mut scheduler = Scheduler::new(
+            config.scheduler.clone(),
+            config.economy,
+            config.consensus,
+            config.checkpoint.clone(),
+            Seed::new(1),
+            world.clone(),
+        );
+        for _ in 0..16 {
+            scheduler.step().await;
+        }
+
+        let mut chains = Vec::new();
+        for node in world.nodes.lock().await.iter() {
+            chains.push(node.lock().await.key_chain.lock().await.clone());
+        }
+        let height = chains[0].height();
+        assert!(height >= 5, "за 5 раундов принято только {} блоков", height - 1);
+        for chain in chains.iter() {
+            assert!(chain.verify().is_ok());
+            assert_eq!(chain.height(), height);
+            for h in 0..height {
+                assert_eq!(chain.hash_at(h), chains[0].hash_at(h), "{} расходится на высоте {}", chain.name, h);
+            }
+        }
+        assert!(world.ballots.proposals().is_empty());
+    }
+
+    fn proposal(voters: &[&str], needed: usize) -> Proposal {
+        let block = Block::genesis();
+        Proposal {
+            announcement: 7,
+            outcome: RoundOutcome {
+                height: 1,
+                winner: voters[0].into(),
+                weight: 1.0,
+                total_weight: 1.0,
+                participants: voters.len(),
+                hash: block.hash.clone(),
+            },
+            block,
+            neuron: Neuron::default(),
+            voters: voters.iter().map(|v| v.to_string()).collect(),
+            needed,
+            approvals: BTreeSet::from([voters[0].to_string()]),
+            rejections: BTreeSet::new(),
+            config: ConsensusConfig::default(),
+        }
+    }
+
+    #[test]
+    fn quorum_finalizes_and_duplicates_do_not_count() {
+        let ballots = Ballots::new();
+        ballots.open(proposal(&["a", "b", "c", "d"], 3));
+
+        assert!(matches!(ballots.vote(7, "b", true), Verdict::Pending));
+        // повтор и смена голоса той же нодой ничего не меняют
+        assert!(matches!(ballots.vote(7, "b", true), Verdict::Pending));
+        assert!(matches!(ballots.vote(7, "b", false), Verdict::Pending));
+        assert_eq!(ballots.proposals()[0].approvals.len(), 2);
+        assert!(ballots.proposals()[0].rejections.is_empty());
+        // чужие ноды и чужие объявления не голосуют
+        assert!(matches!(ballots.vote(7, "stranger", true), Verdict::Ignored));
+        assert!(matches!(ballots.vote(8, "c", true), Verdict::Ignored));
+
+        match ballots.vote(7, "c", true) {
+            Verdict::Finalize(p) => assert_eq!(p.approvals.len(), 3),
+            _ => panic!("кворум набран, а блок не принят"),
+        }
+        // решённый блок покинул урну
+        assert!(ballots.proposals().is_empty());
+        assert!(matches!(ballots.vote(7, "d", true), Verdict::Ignored));
+    }
+
+    #[test]
+    fn hopeless_vote_is_rejected_early() {
+        let ballots = Ballots::new();
+        ballots.open(proposal(&["a", "b", "c", "d"], 3));
+        assert!(matches!(ballots.vote(7, "b", false), Verdict::Pending));
+        // 4 голосующих − 2 против < 3 нужных: ждать остальных незачем
+        match ballots.vote(7, "c", false) {
+            Verdict::Reject(p) => assert_eq!(p.rejections.len(), 2),
+            _ => panic!("кворум недостижим, а блок не отклонён"),
+        }
+        assert!(ballots.proposals().is_empty());
+
+        ballots.open(proposal(&["a", "b"], 2));
+        assert!(ballots.expire(7).is_some());
+        assert!(ballots.expire(7).is_none());
+    }
+
+    #[tokio::test]
+    async fn rejected_block_requeues_transfers_and_entries() {
+        let world = crate::test_world(&Config::default()).await;
+        let transfer = |tick, delta| EnergyTransfer { tick, node: "node0".into(), delta };
+        let entry = |seq| Entry {
+            seq,
+            tick: seq,
+            from: Account::Mint,
+            to: Account::Wallet("node0".into()),
+            amount: TokenAmount::whole(1),
+            reason: TokenReason::BlockReward,
+        };
+
+        let proposal = proposal(&["a", "b"], 2);
+        let body = BlockBody {
+            items: vec![
+                PayloadItem::EnergyTransfer(transfer(1, -2.0)),
+                PayloadItem::TokenTx(entry(1)),
+                PayloadItem::EnergyTransfer(transfer(1, 2.0)),
+            ],
+            proofs: Vec::new(),
+        };
+        world.bodies.insert(&proposal.block.hash, body);
+        // пока шло голосование, накопились новые
+        world.energy.requeue_transfers(vec![transfer(2, 1.0)]);
+        world.tokens.requeue(vec![entry(2)]);
+
+        reject(proposal.clone(), &world, "тест");
+        assert!(world.bodies.get(&proposal.block.hash).is_none());
+        // отклонённые идут раньше новых
+        let ticks: Vec<(u64, f64)> = world.energy.pending_transfers().iter().map(|t| (t.tick, t.delta)).collect();
+        assert_eq!(ticks, [(1, -2.0), (1, 2.0), (2, 1.0)]);
+        let seqs: Vec<u64> = world.tokens.pending().iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, [1, 2]);
+    }
+}
@@ -0,0 +1,541 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::chain::Chain;
+use crate::config::StorageConfig;
+use crate::events::ChainKind;
+
+pub const MANIFEST_FILE: &str = "manifest.jsonl";
+const NODES_DIR: &str = "nodes";
+/// каталоги умерших нод, чьё имя снова понадобилось
+const ARCHIVE_DIR: &str = "archive";
+
+/// Версия раскладки каталога; записывается первой строкой манифеста
+const LAYOUT_VERSION: u32 = 1;
+
+/// Строка манифеста
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ManifestRecord {
+    Layout { version: u32 },
+    Born { node: String, parent: Option<String>, tick: u64 },
+    Died { node: String, tick: u64 },
+    /// организм завершился штатно, нода была жива
+    Stopped { node: String, tick: u64 },
+}
+
+/// Нода по манифесту
+#[derive(Clone, Debug, Serialize)]
+pub struct NodeEntry {
+    pub parent: Option<String>,
+    pub born_tick: u64,
+    /// `None` — нода жива
+    pub died_tick: Option<u64>,
+    /// тик штатного завершения организма, при котором нода была жива
+    pub stopped_tick: Option<u64>,
+}
+
+impl NodeEntry {
+    /// Живая нода: родилась и с тех пор не умирала и не останавливалась
+    pub fn is_alive(&self) -> bool {
+        self.died_tick.is_none() && self.stopped_tick.is_none()
+    }
+}
+
+/// 🗂️ Корень данных (`storage.dir`): манифест и по каталогу на ноду.
+/// ```text
+/// data/
+///   manifest.jsonl            рождения, смерти и остановки нод (дописывается)
+///   nodes/<нода>/
+///     data_chain/ key_chain/  хранилища блоков (см. `block_store.rs`)
+///     synapses.json  neurons.json  wallet.json  memory.json  identity.json
+///   archive/<нода>-<тик>/     каталоги умерших нод, чьё имя занято новой нодой
+/// ```
+/// Клонирование дешёвое. Выключенный корень ничего не пишет: цепи и состояние
+/// нод живут только в памяти.
+#[derive(Clone, Default)]
+pub struct DataRoot {
+    inner: Option<Arc<Inner>>,
+}
+
+struct Inner {
+    config: StorageConfig,
+    manifest: Mutex<Manifest>,
+}
+
+/// Файл манифеста и его свёртка (см. `read_manifest`)
+struct Manifest {
+    file: File,
+    nodes: BTreeMap<String, NodeEntry>,
+}
+
+impl fmt::Debug for DataRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.inner {
+            Some(inner) => write!(f, "DataRoot({})", inner.config.dir.display()),
+            None => write!(f, "DataRoot(off)"),
+        }
+    }
+}
+
+impl DataRoot {
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Открыть корень из `[storage]`; при `enabled = false` — выключенный корень
+    pub fn open(config: &StorageConfig) -> io::Result<Self> {
+        if !config.enabled {
+            return Ok(Self::disabled());
+        }
+        fs::create_dir_all(config.dir.join(NODES_DIR))?;
+
+        let path = config.dir.join(MANIFEST_FILE);
+        if let Some(version) = layout_version(&path)? {
+            if version != LAYOUT_VERSION {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: раскладка версии {}, ожидается {}", path.display(), version, LAYOUT_VERSION),
+                ));
+            }
+        }
+        let fresh = fs::metadata(&path).map(|m| m.len() == 0).unwrap_or(true);
+        let nodes = read_manifest(&config.dir)?;
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        let root = Self {
+            inner: Some(Arc::new(Inner { config: config.clone(), manifest: Mutex::new(Manifest { file, nodes }) })),
+        };
+        if fresh {
+            root.append(&ManifestRecord::Layout { version: LAYOUT_VERSION });
+        }
+        Ok(root)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Каталог ноды (`None`, если корень выключен)
+    pub fn node_dir(&self, node: &str) -> Option<PathBuf> {
+        self.inner.as_ref().map(|inner| node_dir(&inner.config.dir, node))
+    }
+
+    /// 🗃️ Подготовить каталог под новую ноду `node`. Каталог умершей ноды с тем же
+    /// именем не открывается: он уносится в `archive/` (его заберёт `--gc`), и нода
+    /// начинает с чистого листа. Каталоги живых и остановленных нод открываются как есть.
+    pub fn claim(&self, node: &str) {
+        let Some(inner) = &self.inner else {
+            return;
+        };
+        let died = match inner.manifest.lock() {
+            Ok(manifest) => manifest.nodes.get(node).and_then(|entry| entry.died_tick),
+            Err(_) => return,
+        };
+        let Some(tick) = died else {
+            return;
+        };
+        let dir = node_dir(&inner.config.dir, node);
+        if !dir.exists() {
+            return;
+        }
+        let archived = inner.config.dir.join(ARCHIVE_DIR).join(format!("{}-{}", node, tick));
+        let moved = fs::create_dir_all(inner.config.dir.join(ARCHIVE_DIR))
+            .and_then(|_| if archived.exists() { fs::remove_dir_all(&archived) } else { Ok(()) })
+            .and_then(|_| fs::rename(&dir, &archived));
+        match moved {
+            Ok(()) => println!("🗃️ {}: каталог умершей ноды перенесён в {}", node, archived.display()),
+            Err(e) => {
+                // открывать чужие цепи нельзя — лучше потерять старый каталог
+                println!("⚠️ {}: каталог умершей ноды не перенесён ({}), удаляется", node, e);
+                let _ = fs::remove_dir_all(&dir);
+            }
+        }
+    }
+
+    /// Цепь `kind` ноды `node`: из её хранилища, если корень включён, иначе в памяти
+    pub fn open_chain(&self, node: &str, kind: ChainKind) -> Chain {
+        let name = chain_name(node, kind);
+        if let Some(inner) = &self.inner {
+            let dir = node_dir(&inner.config.dir, node).join(chain_dir(kind));
+            match Chain::open(&dir, &name, &inner.config) {
+                Ok(chain) => return chain,
+                Err(e) => println!("❌ Хранилище цепи {} не открылось ({}) — цепь только в памяти", name, e),
+            }
+        }
+        Chain::new(&name)
+    }
+
+    /// Привязать цепь из контрольной точки к хранилищу ноды (см. `Chain::attach_store`)
+    pub fn attach_chain(&self, node: &str, kind: ChainKind, chain: &mut Chain) {
+        let Some(inner) = &self.inner else {
+            return;
+        };
+        let dir = node_dir(&inner.config.dir, node).join(chain_dir(kind));
+        if let Err(e) = chain.attach_store(&dir, &inner.config) {
+            println!("❌ Хранилище цепи {} не открылось ({}) — цепь только в памяти", chain.name, e);
+        }
+    }
+
+    /// 📋 Отметить в манифесте рождение (или восстановление) ноды
+    pub fn record_birth(&self, node: &str, parent: Option<&str>, tick: u64) {
+        self.append(&ManifestRecord::Born {
+            node: node.to_string(),
+            parent: parent.map(|p| p.to_string()),
+            tick,
+        });
+    }
+
+    /// 📋 Отметить в манифесте смерть ноды — её каталог станет добычей `--gc`
+    pub fn record_death(&self, node: &str, tick: u64) {
+        self.append(&ManifestRecord::Died { node: node.to_string(), tick });
+    }
+
+    /// 📋 Отметить в манифесте, что организм завершился штатно, а нода была жива:
+    /// каталог можно открыть при следующем запуске или забрать `--gc`
+    pub fn record_stop(&self, node: &str, tick: u64) {
+        self.append(&ManifestRecord::Stopped { node: node.to_string(), tick });
+    }
+
+    fn append(&self, record: &ManifestRecord) {
+        let Some(inner) = &self.inner else {
+            return;
+        };
+        let Ok(mut manifest) = inner.manifest.lock() else {
+            return;
+        };
+        fold(&mut manifest.nodes, record.clone());
+        let mut line = serde_json::to_vec(record).unwrap_or_default();
+        line.push(b'\n');
+        let file = &mut manifest.file;
+        let written = file.write_all(&line).and_then(|_| if inner.config.fsync { file.sync_data() } else { Ok(()) });
+        if let Err(e) = written {
+            eprintln!("⚠️ Не удалось дописать манифест {}: {}", inner.config.dir.display(), e);
+        }
+    }
+
+    /// 💾 Записать файл ноды целиком: во временный файл, затем переименованием
+    pub fn write_json<T: Serialize>(&self, node: &str, file: &str, value: &T) -> io::Result<()> {
+        let Some(inner) = &self.inner else {
+            return Ok(());
+        };
+        let dir = node_dir(&inner.config.dir, node);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(file);
+        let tmp = path.with_extension("json.tmp");
+        {
+            let mut out = File::create(&tmp)?;
+            serde_json::to_writer_pretty(&mut out, value)?;
+            if inner.config.fsync {
+                out.sync_all()?;
+            }
+        }
+        fs::rename(&tmp, &path)
+    }
+
+    /// Прочитать файл ноды; `None`, если корень выключен или файла нет
+    pub fn read_json<T: DeserializeOwned>(&self, node: &str, file: &str) -> Option<T> {
+        let path = self.node_dir(node)?.join(file);
+        let text = fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&text) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                println!("⚠️ {} не прочитан: {}", path.display(), e);
+                None
+            }
+        }
+    }
+}
+
+fn node_dir(root: &Path, node: &str) -> PathBuf {
+    root.join(NODES_DIR).join(node)
+}
+
+fn chain_name(node: &str, kind: ChainKind) -> String {
+    match kind {
+        ChainKind::Data => format!("{}_data", node),
+        ChainKind::Key => format!("{}_key", node),
+    }
+}
+
+fn chain_dir(kind: ChainKind) -> &'static str {
+    match kind {
+        ChainKind::Data => "data_chain",
+        ChainKind::Key => "key_chain",
+    }
+}
+
+/// Версия из первой строки манифеста (`None`, если манифеста ещё нет)
+fn layout_version(path: &Path) -> io::Result<Option<u32>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut first = String::new();
+    BufReader::new(file).read_line(&mut first)?;
+    match serde_json::from_str(&first) {
+        Ok(ManifestRecord::Layout { version }) => Ok(Some(version)),
+        _ if first.trim().is_empty() => Ok(None),
+        _ => Ok(Some(0)),
+    }
+}
+
+/// Учесть запись манифеста: последняя запись о ноде решает, жива ли она
+fn fold(nodes: &mut BTreeMap<String, NodeEntry>, record: ManifestRecord) {
+    match record {
+        ManifestRecord::Layout { .. } => {}
+        ManifestRecord::Born { node, parent, tick } => {
+            nodes.insert(node, NodeEntry { parent, born_tick: tick, died_tick: None, stopped_tick: None });
+        }
+        ManifestRecord::Died { node, tick } => {
+            if let Some(entry) = nodes.get_mut(&node) {
+                entry.died_tick = Some(tick);
+            }
+        }
+        ManifestRecord::Stopped { node, tick } => {
+            if let Some(entry) = nodes.get_mut(&node) {
+                entry.stopped_tick = Some(tick);
+            }
+        }
+    }
+}
+
+/// 📋 Свернуть манифест: последняя запись о ноде решает, жива ли она
+pub fn read_manifest(root: &Path) -> io::Result<BTreeMap<String, NodeEntry>> {
+    let file = match File::open(root.join(MANIFEST_FILE)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e),
+    };
+    let mut nodes = BTreeMap::new();
+    for line in BufReader::new(file).lines() {
+        // оборванная последняя строка (сбой посреди записи) пропускается
+        let Ok(record) = serde_json::from_str::<ManifestRecord>(&line?) else {
+            continue;
+        };
+        fold(&mut nodes, record);
+    }
+    Ok(nodes)
+}
+
+/// Итог `--gc`
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct GcReport {
+    /// удалённые каталоги умерших и остановленных нод (архивные — с префиксом `archive/`)
+    pub removed: Vec<String>,
+    pub bytes_freed: u64,
+    /// живые ноды, оставшиеся в манифесте
+    pub alive: usize,
+    /// каталоги, о которых манифест ничего не знает (не трогаются)
+    pub unknown: Vec<String>,
+}
+
+/// 🧹 Удалить каталоги нод, умерших или остановленных по манифесту, и архив,
+/// и переписать манифест только с живыми нодами. Живыми остаются и ноды прогона,
+/// оборвавшегося без штатного завершения. Запускать, пока организм с этим корнем не работает.
+pub fn collect_garbage(root: &Path, fsync: bool) -> io::Result<GcReport> {
+    let nodes = read_manifest(root)?;
+    let mut report = GcReport::default();
+
+    for (name, entry) in nodes.iter() {
+        if entry.is_alive() {
+            report.alive += 1;
+            continue;
+        }
+        let dir = node_dir(root, name);
+        if dir.exists() {
+            report.bytes_freed += dir_size(&dir)?;
+            fs::remove_dir_all(&dir)?;
+        }
+        report.removed.push(name.clone());
+    }
+
+    if let Ok(entries) = fs::read_dir(root.join(ARCHIVE_DIR)) {
+        let mut archived: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+        archived.sort_by_key(|e| e.file_name());
+        for entry in archived {
+            report.bytes_freed += dir_size(&entry.path())?;
+            fs::remove_dir_all(entry.path())?;
+            report.removed.push(format!("{}/{}", ARCHIVE_DIR, entry.file_name().to_string_lossy()));
+        }
+    }
+
+    if let Ok(entries) = fs::read_dir(root.join(NODES_DIR)) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !nodes.contains_key(&name) {
+                report.unknown.push(name);
+            }
+        }
+    }
+    report.unknown.sort();
+
+    // сжатый манифест: раскладка и рождения живых нод
+    let path = root.join(MANIFEST_FILE);
+    if path.exists() {
+        let tmp = path.with_extension("jsonl.tmp");
+        {
+            let mut out = File::create(&tmp)?;
+            let mut records = vec![ManifestRecord::Layout { version: LAYOUT_VERSION }];
+            records.extend(nodes.into_iter().filter(|(_, e)| e.is_alive()).map(|(node, e)| {
+                ManifestRecord::Born { node, parent: e.parent, tick: e.born_tick }
+            }));
+            for record in records.iter() {
+                serde_json::to_writer(&mut out, record)?;
+                out.write_all(b"\n")?;
+            }
+            if fsync {
+                out.sync_all()?;
+            }
+        }
+        fs::rename(&tmp, &path)?;
+    }
+    Ok(report)
+}
+
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let mut total = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        total += if meta.is_dir() { dir_size(&entry.path())? } else { meta.len() };
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Чистый корень данных для теста
+    fn scratch(name: &str) -> StorageConfig {
+        let dir = std::env::temp_dir().join(format!("organism-data-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        StorageConfig { enabled: true, dir, fsync: false, ..StorageConfig::default() }
+    }
+
+    #[test]
+    fn manifest_folds_to_the_last_record_of_each_node() {
+        let config = scratch("manifest");
+        let data = DataRoot::open(&config).unwrap();
+        data.record_birth("node0", None, 0);
+        data.record_birth("node1", Some("node0"), 3);
+        data.record_birth("node2", None, 4);
+        data.record_death("node1", 7);
+        data.record_stop("node2", 9);
+        // нода родилась заново после остановки — снова жива
+        data.record_birth("node3", None, 1);
+        data.record_stop("node3", 2);
+        data.record_birth("node3", None, 5);
+        // запись о неизвестной ноде и оборванная строка не ломают свёртку
+        data.record_death("ghost", 8);
+        drop(data);
+        let mut file = OpenOptions::new().append(true).open(config.dir.join(MANIFEST_FILE)).unwrap();
+        file.write_all(b"{\"event\":\"died\",\"node\":\"no").unwrap();
+
+        let nodes = read_manifest(&config.dir).unwrap();
+        assert_eq!(nodes.keys().collect::<Vec<_>>(), ["node0", "node1", "node2", "node3"]);
+        assert!(nodes["node0"].is_alive());
+        assert_eq!(nodes["node1"].parent.as_deref(), Some("node0"));
+        assert_eq!(nodes["node1"].died_tick, Some(7));
+        assert_eq!(nodes["node2"].stopped_tick, Some(9));
+        assert!(!nodes["node2"].is_alive());
+        assert!(nodes["node3"].is_alive());
+        assert_eq!(nodes["node3"].born_tick, 5);
+
+        assert!(read_manifest(&config.dir.join("missing")).unwrap().is_empty());
+        let _ = fs::remove_dir_all(&config.dir);
+    }
+
+    #[test]
+    fn dead_node_dir_is_archived_instead_of_reopened() {
+        let config = scratch("reopen");
+        let data = DataRoot::open(&config).unwrap();
+        for node in ["node0", "node1", "node2"] {
+            data.write_json(node, "memory.json", &node).unwrap();
+            data.record_birth(node, None, 0);
+        }
+        data.record_death("node0", 4);
+        data.record_stop("node1", 6);
+        drop(data);
+
+        // корень открывается заново: правила берутся из манифеста на диске
+        let data = DataRoot::open(&config).unwrap();
+        for node in ["node0", "node1", "node2", "stray"] {
+            data.claim(node);
+        }
+        assert_eq!(data.read_json::<String>("node0", "memory.json"), None);
+        let archived = config.dir.join(ARCHIVE_DIR).join("node0-4");
+        assert_eq!(fs::read_to_string(archived.join("memory.json")).unwrap(), "\"node0\"");
+        // остановленная и живая ноды открываются как есть
+        assert_eq!(data.read_json::<String>("node1", "memory.json").as_deref(), Some("node1"));
+        assert_eq!(data.read_json::<String>("node2", "memory.json").as_deref(), Some("node2"));
+
+        // новая нода с именем умершей начинает с чистого листа и жива по манифесту
+        data.write_json("node0", "memory.json", &"reborn").unwrap();
+        data.record_birth("node0", None, 10);
+        data.claim("node0");
+        assert_eq!(data.read_json::<String>("node0", "memory.json").as_deref(), Some("reborn"));
+        assert!(read_manifest(&config.dir).unwrap()["node0"].is_alive());
+
+        // выключенный корень ничего не трогает
+        DataRoot::disabled().claim("node0");
+        let _ = fs::remove_dir_all(&config.dir);
+    }
+
+    #[test]
+    fn gc_reclaims_dead_stopped_and_archived_dirs() {
+        let config = scratch("gc");
+        let data = DataRoot::open(&config).unwrap();
+        for node in ["dead", "stopped", "alive"] {
+            data.write_json(node, "memory.json", &node).unwrap();
+            data.record_birth(node, None, 0);
+        }
+        data.write_json("stray", "memory.json", &"stray").unwrap();
+        data.record_death("dead", 2);
+        data.record_stop("stopped", 5);
+        // умершая нода без каталога тоже уходит из манифеста
+        data.record_birth("gone", None, 1);
+        data.record_death("gone", 2);
+        // имя умершей ноды занято заново — её старый каталог в архиве
+        data.record_birth("reused", None, 0);
+        data.write_json("reused", "memory.json", &"old").unwrap();
+        data.record_death("reused", 3);
+        data.claim("reused");
+        data.record_birth("reused", Some("alive"), 4);
+        drop(data);
+
+        let report = collect_garbage(&config.dir, false).unwrap();
+        assert_eq!(report.removed, ["dead", "gone", "stopped", "archive/reused-3"]);
+        assert_eq!(report.alive, 2);
+        assert_eq!(report.unknown, ["stray"]);
+        assert!(report.bytes_freed > 0);
+
+        assert!(!node_dir(&config.dir, "dead").exists());
+        assert!(!node_dir(&config.dir, "stopped").exists());
+        assert!(node_dir(&config.dir, "alive").exists());
+        assert!(node_dir(&config.dir, "stray").exists());
+        assert_eq!(fs::read_dir(config.dir.join(ARCHIVE_DIR)).unwrap().count(), 0);
+
+        // манифест сжат до раскладки и рождений живых нод
+        let manifest = fs::read_to_string(config.dir.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(manifest.lines().count(), 3);
+        assert_eq!(layout_version(&config.dir.join(MANIFEST_FILE)).unwrap(), Some(LAYOUT_VERSION));
+        let nodes = read_manifest(&config.dir).unwrap();
+        assert_eq!(nodes.keys().collect::<Vec<_>>(), ["alive", "reused"]);
+        assert_eq!(nodes["reused"].parent.as_deref(), Some("alive"));
+
+        // повторный прогон ничего не находит
+        let again = collect_garbage(&config.dir, false).unwrap();
+        assert!(again.removed.is_empty());
+        assert_eq!(again.alive, 2);
+        let _ = fs::remove_dir_all(&config.dir);
+    }
+}
@@ -1,25 +1,52 @@
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use crate::events::{Event, EventLog};
+use crate::token_amount::TokenAmount;
+use crate::token_ledger::{Account, TokenLedger, TokenReason};
 
 #[derive(Clone)]
 pub struct NetworkFund {
-    pub total: Arc<Mutex<f64>>,
+    pub total: Arc<Mutex<TokenAmount>>,
+    pub log: EventLog,
+    pub ledger: TokenLedger,
 }
 
 impl NetworkFund {
-    pub fn new() -> Self {
+    pub fn new(log: EventLog, ledger: TokenLedger) -> Self {
         Self {
-            total: Arc::new(Mutex::new(0.0)),
+            total: Arc::new(Mutex::new(TokenAmount::ZERO)),
+            log,
+            ledger,
         }
     }
 
-    pub async fn add(&self, amount: f64) {
+    /// Пополнить фонд новыми токенами (эмиссия)
+    pub async fn add(&self, amount: TokenAmount, reason: TokenReason) -> bool {
         let mut fund = self.total.lock().await;
-        *fund += amount;
+        let Some(total) = fund.checked_add(amount) else {
+            println!("⚠️ Фонд переполнен — пополнение {} отклонено", amount);
+            return false;
+        };
+        *fund = total;
+        self.ledger.record(Account::Mint, Account::Fund, amount, reason);
+        self.log.emit(Event::FundAdd { amount, total: *fund });
         println!("🏦 Фонд развития пополнен на {:.2} (всего: {:.2})", amount, *fund);
+        true
     }
 
-    pub async fn get_balance(&self) -> f64 {
+    /// Списать из фонда (сжигание), если хватает средств
+    pub async fn withdraw(&self, amount: TokenAmount, reason: TokenReason) -> bool {
+        let mut fund = self.total.lock().await;
+        let Some(total) = fund.checked_sub(amount) else {
+            return false;
+        };
+        *fund = total;
+        self.ledger.record(Account::Fund, Account::Burn, amount, reason);
+        self.log.emit(Event::FundWithdraw { amount, total: *fund });
+        true
+    }
+
+    pub async fn get_balance(&self) -> TokenAmount {
         *self.total.lock().await
     }
 }
@@ -1,85 +1,87 @@
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use rand::{Rng, rngs::StdRng, SeedableRng};
-use crate::{node::Node, economy::NetworkFund};
+use rand::Rng;
+use crate::{node::Node, economy::NetworkFund, rng::SimRng, config::EconomyConfig};
+use crate::energy_ledger::EnergyFlow;
+use crate::token_amount::TokenAmount;
+use crate::token_ledger::TokenReason;
 
 pub struct EconomyCycle;
 
 impl EconomyCycle {
-    /// Главный цикл перераспределения энергии и ресурсов
-    pub async fn run(nodes: Arc<Mutex<Vec<Arc<Mutex<Node>>>>>, fund: Arc<Mutex<NetworkFund>>) {
-        loop {
-            tokio::time::sleep(std::time::Duration::from_secs(15)).await;
-
-            let mut total_energy = 0.0;
-            let mut active_nodes = 0;
-
-            {
-                println!("💫 [DEBUG] Цикл экономики активен...");
-                let nodes_guard = nodes.lock().await;
-                for node in nodes_guard.iter() {
-                    let n = node.lock().await;
-                    let mut energy = n.energy.lock().await;
-                    let balance = *n.wallet.balance.lock().await;
-
-                    // 🔋 Естественные потери энергии
-                    energy.level -= 2.0;
-
-                    // ⚙️ Эффективность влияет на потери
-                    energy.level *= n.efficiency.max(0.1);
-
-                    // 💚 Минимальный порог — не позволяем умереть
-                    if energy.level < 5.0 {
-                        energy.level = 5.0;
-                    }
-
-                    total_energy += energy.level;
-                    active_nodes += 1;
+    /// Один шаг перераспределения энергии и ресурсов (фаза экономики планировщика)
+    pub async fn step(
+        nodes: Arc<Mutex<Vec<Arc<Mutex<Node>>>>>,
+        fund: Arc<Mutex<NetworkFund>>,
+        rng: &mut SimRng,
+        config: &EconomyConfig,
+    ) {
+        let mut total_energy = 0.0;
+        let mut active_nodes = 0;
+
+        {
+            println!("💫 [DEBUG] Цикл экономики активен...");
+            let nodes_guard = nodes.lock().await;
+            for node in nodes_guard.iter() {
+                let n = node.lock().await;
+                let mut energy = n.energy.lock().await;
+                let balance = *n.wallet.balance.lock().await;
+
+                // 🔋 Естественные потери энергии,
+                // ⚙️ эффективность влияет на потери
+                let level = (energy.level - 2.0) * n.efficiency.max(0.1);
+                energy.set_level(level, EnergyFlow::Upkeep);
+
+                // 💚 Минимальный порог — не позволяем умереть
+                if energy.level < config.energy_floor {
+                    energy.set_level(config.energy_floor, EnergyFlow::Floor);
+                }
 
-                    // 💸 Энергия влияет на токен: немного расходов
-                    if balance > 0.5 {
-                        n.wallet.spend(0.5).await;
-                    }
+                total_energy += energy.level;
+                active_nodes += 1;
 
-                    // 🤝 Попробуем помочь слабому
-                    if energy.level < 20.0 {
-                        Self::help_weak_node(&nodes_guard, n.name.clone()).await;
-                    }
+                // 💸 Энергия влияет на токен: немного расходов
+                if balance > config.upkeep_fee {
+                    n.wallet.spend(config.upkeep_fee, TokenReason::Upkeep).await;
                 }
-            }
 
-            let avg_energy = total_energy / active_nodes.max(1) as f64;
-
-            // ⚡ Если вся сеть устала — подпитываем из фонда
-            if avg_energy < 25.0 {
-                let fund_guard = fund.lock().await;
-                let mut total = fund_guard.total.lock().await; // получаем доступ к значению f64
-
-                if *total > 5.0 {
-                    println!("⚡ Сеть получает подпитку от NetworkFund!");
-                    for node in nodes.lock().await.iter() {
-                        let n = node.lock().await;
-                        let mut e = n.energy.lock().await;
-                        e.level += 10.0;
-                    }
-                    *total -= 5.0; // уменьшаем значение фонда
-                } else {
-                    println!("⚠️ Фонд пуст — сеть слабеет...");
+                // 🤝 Попробуем помочь слабому
+                if energy.level < 20.0 {
+                    Self::help_weak_node(&nodes_guard, n.name.clone(), rng).await;
                 }
             }
+        }
 
+        let avg_energy = total_energy / active_nodes.max(1) as f64;
 
+        // ⚡ Если вся сеть устала — подпитываем из фонда
+        if avg_energy < config.fund_trigger_avg_energy {
+            let fund_guard = fund.lock().await;
 
-            println!("🌍 Средняя энергия сети: {:.2}", avg_energy);
+            if fund_guard.get_balance().await > config.fund_injection_cost {
+                println!("⚡ Сеть получает подпитку от NetworkFund!");
+                for node in nodes.lock().await.iter() {
+                    let n = node.lock().await;
+                    let mut e = n.energy.lock().await;
+                    e.gain(config.fund_injection_energy, EnergyFlow::FundInjection);
+                }
+                fund_guard.withdraw(config.fund_injection_cost, TokenReason::FundInjection).await; // уменьшаем значение фонда
+            } else {
+                println!("⚠️ Фонд пуст — сеть слабеет...");
+            }
         }
+
+        println!("🌍 Средняя энергия сети: {:.2}", avg_energy);
     }
 
     /// Функция помощи слабому узлу
-    async fn help_weak_node(nodes: &Vec<Arc<Mutex<Node>>>, weak_name: String) {
-        let mut rng = StdRng::from_entropy(); 
+    async fn help_weak_node(nodes: &[Arc<Mutex<Node>>], weak_name: String, rng: &mut SimRng) {
 
         if let Some(helper) = nodes.get(rng.gen_range(0..nodes.len())) {
-            let h = helper.lock().await;
+            // слабая нода уже заблокирована вызывающим кодом — lock() на ней повис бы навсегда
+            let Ok(h) = helper.try_lock() else {
+                return;
+            };
             if h.name == weak_name {
                 return;
             }
@@ -88,9 +90,9 @@ impl EconomyCycle {
             let mut helper_energy = h.energy.lock().await;
 
             // 💡 Помощь возможна, только если у хелпера достаточно ресурсов
-            if helper_energy.level > 40.0 && helper_balance > 10.0 {
-                helper_energy.level -= 5.0;
-                h.wallet.spend(2.0).await;
+            if helper_energy.level > 40.0 && helper_balance > TokenAmount::whole(10) {
+                helper_energy.consume(5.0, EnergyFlow::HelpCost);
+                h.wallet.spend(TokenAmount::whole(2), TokenReason::HelpCost).await;
 
                 println!("🤝 {} помогает {}", h.name, weak_name);
             }
@@ -1,11 +1,6 @@
-use crate::node::Node;
-use rand::seq::SliceRandom;
-use rand::{Rng, SeedableRng, rngs::StdRng};
-use crate::energy_evolution::EnergyEvolution;
+use crate::events::{Event, EventLog};
+use crate::energy_ledger::{EnergyFlow, EnergyLedger};
 use std::fmt;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use futures::future::join_all;
 
  
 
@@ -13,30 +8,88 @@ use futures::future::join_all;
 pub struct Energy {
     pub level: f64,
     pub node_name: String,
+    pub log: EventLog,
+    pub ledger: EnergyLedger,
 }
 
 impl Energy {
-    pub fn new(name: &str) -> Self {
+    pub fn new(name: &str, log: EventLog, ledger: EnergyLedger) -> Self {
         Self {
             level: 100.0, // стартовая энергия
             node_name: name.to_string(),
+            log,
+            ledger,
         }
     }
 
-    pub fn consume(&mut self, amount: f64) {
+    /// Записать изменение в учёт; стороны переводов — ещё и поимённо, для блоков
+    fn book(&self, flow: EnergyFlow, delta: f64) {
+        self.ledger.record(flow, delta);
+        if flow == EnergyFlow::Transfer {
+            self.ledger.record_transfer(&self.node_name, delta);
+        }
+    }
+
+    pub fn consume(&mut self, amount: f64, flow: EnergyFlow) {
+        let before = self.level;
         self.level = (self.level - amount).max(0.0);
+        self.book(flow, self.level - before);
+        self.log.emit(Event::EnergyConsumed {
+            node: self.node_name.clone(),
+            amount,
+            level: self.level,
+            flow,
+        });
     }
 
-    pub fn restore(&mut self, amount: f64) {
+    /// Восстановить не выше 100; срезанное потолком учитывается как `EnergyFlow::Cap`
+    pub fn restore(&mut self, amount: f64, flow: EnergyFlow) {
+        let before = self.level;
         self.level = (self.level + amount).min(100.0);
+        self.book(flow, amount);
+        self.book(EnergyFlow::Cap, (self.level - before) - amount);
+        self.log.emit(Event::EnergyRestored {
+            node: self.node_name.clone(),
+            amount,
+            level: self.level,
+            flow,
+        });
+    }
+
+    /// Прибавить энергию без потолка в 100 (награды, подпитка)
+    pub fn gain(&mut self, amount: f64, flow: EnergyFlow) {
+        let before = self.level;
+        self.level += amount;
+        self.book(flow, self.level - before);
+        self.log.emit(Event::EnergyRestored {
+            node: self.node_name.clone(),
+            amount,
+            level: self.level,
+            flow,
+        });
     }
 
-    pub fn is_exhausted(&self) -> bool {
-        self.level <= 0.0
+    /// Установить уровень напрямую (пороги, потолки, ручная правка через API)
+    pub fn set_level(&mut self, level: f64, flow: EnergyFlow) {
+        self.book(flow, level - self.level);
+        self.level = level;
+        self.log.emit(Event::EnergySet {
+            node: self.node_name.clone(),
+            level,
+            flow,
+        });
     }
 
-    pub fn status(&self) -> String {
-        format!("⚡ {}: {:.2}", self.node_name, self.level)
+    /// Нода вошла в популяцию: вся её стартовая энергия — источник
+    pub fn born(&self) {
+        self.book(EnergyFlow::Birth, self.level);
+    }
+
+    /// Нода покинула популяцию: её энергия уходит стоком,
+    /// дальнейшие изменения в учёт популяции не попадают
+    pub fn retire(&mut self) {
+        self.book(EnergyFlow::Death, -self.level);
+        self.ledger = EnergyLedger::detached();
     }
 }
 
@@ -45,69 +98,3 @@ impl fmt::Display for Energy {
         write!(f, "Energy(level: {:.2}, node: {})", self.level, self.node_name)
     }
 }
-pub struct EnergySystem;
-
-impl EnergySystem {
-    pub async fn tick(nodes: &mut Vec<Node>) {
-        // 1️⃣ Сначала делаем снимок уровней энергии (чтобы избежать пересечений borrow)
-        let energy_snapshot: Vec<f64> = join_all(
-            nodes.iter().map(|n| async {
-                let e = n.energy.lock().await;
-                e.level
-            })
-        ).await;
-
-        // 2️⃣ Теперь спокойно проходим по всем нодам
-        let total_nodes = nodes.len();
-        for i in 0..total_nodes {
-            // Естественное восстановление энергии
-            {
-                let mut e = nodes[i].energy.lock().await;
-                e.restore(0.3);
-                if e.level > 100.0 {
-                    e.level = 100.0;
-                }
-            }
-
-            // Выбираем ноды, у которых мало энергии (кроме текущей)
-            let weak_nodes: Vec<usize> = (0..total_nodes)
-                .filter(|&j| j != i && energy_snapshot[j] < 20.0)
-                .collect();
-
-            if weak_nodes.is_empty() {
-                continue;
-            }
-
-            // Выбираем случайную слабую ноду
-            let mut rng = StdRng::from_entropy();
-            if let Some(&target_idx) = weak_nodes.choose(&mut rng) {
-                let giver_level = {
-                    let e = nodes[i].energy.lock().await;
-                    e.level
-                };
-
-                if giver_level > 30.0 {
-                    let transfer = (giver_level * 0.1).min(10.0);
-
-                    {
-                        let mut giver_e = nodes[i].energy.lock().await;
-                        giver_e.consume(transfer);
-                    }
-
-                    {
-                        let mut receiver_e = nodes[target_idx].energy.lock().await;
-                        receiver_e.restore(transfer);
-                    }
-
-                    let giver_now = nodes[i].energy.lock().await.level;
-                    let receiver_now = nodes[target_idx].energy.lock().await.level;
-
-                    println!(
-                        "🔋 {} → {} передача {:.2} энергии (теперь {:.2}/{:.2})",
-                        nodes[i].name, nodes[target_idx].name, transfer, giver_now, receiver_now
-                    );
-                }
-            }
-        }
-    }
-}
\ No newline at end of file
@@ -1,35 +1,33 @@
 use crate::node::Node;
+use crate::rng::SimRng;
+use crate::energy_ledger::EnergyFlow;
+use crate::token_amount::TokenAmount;
+use crate::token_ledger::TokenReason;
 use rand::Rng;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use rand::{SeedableRng, rngs::StdRng};
 
 
 pub struct EnergyEvolution;
 
 impl EnergyEvolution {
-    pub async fn evolve(nodes: &mut Vec<Arc<Mutex<Node>>>) {
-        /* let mut rng = rand::thread_rng(); */
-        let mut rng = StdRng::from_entropy();
+    pub async fn evolve(nodes: &mut [Arc<Mutex<Node>>], rng: &mut SimRng) {
 
         for node in nodes.iter() {
             let mut n = node.lock().await;
 
             // --- Работаем с энергией ---
-            let (energy_level, efficiency, resilience) = {
+            let energy_level = {
                 let mut energy = n.energy.lock().await;
 
                 // 🧮 Энергозатраты
                 let consumption = 5.0 * (1.0 - n.efficiency).max(0.1);
-                energy.consume(consumption);
+                energy.consume(consumption, EnergyFlow::Consumption);
 
-                // 🔋 Восстановление
-                energy.restore(0.5 * n.resilience);
+                // 🔋 Восстановление (consume/restore уже держат энергию в 0..100)
+                energy.restore(0.5 * n.resilience, EnergyFlow::Regen);
 
-                // Ограничиваем энергию
-                energy.level = energy.level.clamp(0.0, 100.0);
-
-                (energy.level, n.efficiency, n.resilience)
+                energy.level
             }; // <-- Здесь блокировка энергии завершается!
 
             // --- Работаем с остальными параметрами ---
@@ -41,6 +39,9 @@ impl EnergyEvolution {
             if energy_level < 10.0 {
                 n.efficiency = (n.efficiency * 0.95).max(0.1);
             }
+            if !(10.0..=50.0).contains(&energy_level) {
+                n.log_traits();
+            }
 
             println!(
                 "⚡ {} → энергия: {:.2}, опыт: {:.2}",
@@ -80,9 +81,10 @@ impl EnergyEvolution {
             n.efficiency = (n.efficiency + rng.gen_range(0.02..0.07)).min(1.0);
             n.altruism = (n.altruism + rng.gen_range(0.01..0.04)).min(1.0);
             n.resilience = (n.resilience + rng.gen_range(0.02..0.06)).min(1.5);
+            n.log_traits();
 
             // 💰 Награда за эволюцию
-            n.wallet.reward(10.0).await;
+            n.wallet.reward(TokenAmount::whole(10), TokenReason::EvolutionReward).await;
 
             println!(
                 "🌟 {} эволюционирует! (eff={:.2}, alt={:.2}, res={:.2})",
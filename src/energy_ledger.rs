@@ -0,0 +1,429 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// К какой стороне баланса относится поток энергии
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowCategory {
+    /// энергия появляется в системе
+    Source,
+    /// энергия уходит из системы
+    Sink,
+    /// энергия переходит от ноды к ноде (в сумме должно быть ≈ 0)
+    Transfer,
+}
+
+/// 🔋 Причина изменения энергии ноды
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnergyFlow {
+    // --- источники ---
+    /// награда за работу в `tick_node`
+    Work,
+    /// естественное восстановление
+    Regen,
+    /// подпитка из `NetworkFund`
+    FundInjection,
+    /// случайная подпитка мозгом в режимах rest / evolve
+    BrainStimulus,
+    /// подпитка выживших в конце `evolve_network`
+    SurvivorTopUp,
+    /// восстановление за победу в PoC
+    Mining,
+    /// подъём до `economy.energy_floor`
+    Floor,
+    /// стартовая энергия новой ноды
+    Birth,
+    /// ручная правка через `POST /update/:id`
+    Manual,
+
+    // --- стоки ---
+    /// метаболизм в `tick_node`
+    Decay,
+    /// затраты на эволюцию и участие в PoC
+    Consumption,
+    /// цена размножения
+    Reproduction,
+    /// потери в цикле экономики
+    Upkeep,
+    /// помощь слабой ноде, которую никто не получает
+    HelpCost,
+    /// срезано потолком (restore до 100, `survivor_energy_cap`)
+    Cap,
+    /// энергия, ушедшая вместе с умершей нодой
+    Death,
+
+    // --- переводы ---
+    Transfer,
+}
+
+impl EnergyFlow {
+    pub fn category(&self) -> FlowCategory {
+        match self {
+            EnergyFlow::Work
+            | EnergyFlow::Regen
+            | EnergyFlow::FundInjection
+            | EnergyFlow::BrainStimulus
+            | EnergyFlow::SurvivorTopUp
+            | EnergyFlow::Mining
+            | EnergyFlow::Floor
+            | EnergyFlow::Birth
+            | EnergyFlow::Manual => FlowCategory::Source,
+            EnergyFlow::Decay
+            | EnergyFlow::Consumption
+            | EnergyFlow::Reproduction
+            | EnergyFlow::Upkeep
+            | EnergyFlow::HelpCost
+            | EnergyFlow::Cap
+            | EnergyFlow::Death => FlowCategory::Sink,
+            EnergyFlow::Transfer => FlowCategory::Transfer,
+        }
+    }
+}
+
+/// 🔁 Одна сторона перевода энергии: у отправителя `delta` < 0, у получателя > 0.
+/// Попадает в содержимое ближайшего ключевого блока.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EnergyTransfer {
+    pub tick: u64,
+    pub node: String,
+    pub delta: f64,
+}
+
+/// Сколько последних нарушений хранить для отчёта
+const RECENT_VIOLATIONS: usize = 50;
+
+/// Необъяснённое расхождение на одном тике
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Drift {
+    pub tick: u64,
+    /// сумма энергии нод на конец прошлого тика
+    pub expected: f64,
+    pub actual: f64,
+    pub drift: f64,
+}
+
+/// 📒 Учёт энергии: каждое изменение записывается с причиной,
+/// в конце тика сумма изменений сверяется с фактической энергией популяции.
+/// Клонирование дешёвое; отсоединённый учёт ничего не записывает.
+#[derive(Clone, Debug, Default)]
+pub struct EnergyLedger {
+    inner: Option<Arc<Mutex<LedgerState>>>,
+}
+
+#[derive(Debug, Default)]
+struct LedgerState {
+    /// энергия популяции на конец последнего проверенного тика
+    baseline: Option<f64>,
+    tick_flows: BTreeMap<EnergyFlow, f64>,
+    /// стороны переводов текущего тика (нода, изменение)
+    tick_transfers: Vec<(String, f64)>,
+    /// закрытые переводы, ещё не попавшие в блок
+    sealed_transfers: Vec<EnergyTransfer>,
+    last_tick_flows: BTreeMap<EnergyFlow, f64>,
+    totals: BTreeMap<EnergyFlow, f64>,
+    ticks_checked: u64,
+    violations: u64,
+    max_abs_drift: f64,
+    recent: VecDeque<Drift>,
+}
+
+/// Итоги учёта с начала прогона — для контрольной точки
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EnergyTotals {
+    /// энергия популяции на конец последнего тика
+    pub baseline: Option<f64>,
+    pub totals: BTreeMap<EnergyFlow, f64>,
+    pub last_tick: BTreeMap<EnergyFlow, f64>,
+    pub ticks_checked: u64,
+    pub violations: u64,
+    pub max_abs_drift: f64,
+    pub recent: Vec<Drift>,
+}
+
+impl EnergyLedger {
+    pub fn new() -> Self {
+        Self { inner: Some(Arc::new(Mutex::new(LedgerState::default()))) }
+    }
+
+    /// Учёт, продолжающий итоги и переводы из контрольной точки
+    pub fn from_totals(totals: EnergyTotals, transfers: Vec<EnergyTransfer>) -> Self {
+        let state = LedgerState {
+            baseline: totals.baseline,
+            sealed_transfers: transfers,
+            last_tick_flows: totals.last_tick,
+            totals: totals.totals,
+            ticks_checked: totals.ticks_checked,
+            violations: totals.violations,
+            max_abs_drift: totals.max_abs_drift,
+            recent: totals.recent.into_iter().collect(),
+            ..LedgerState::default()
+        };
+        Self { inner: Some(Arc::new(Mutex::new(state))) }
+    }
+
+    /// Итоги с начала прогона (для контрольной точки)
+    pub fn totals(&self) -> EnergyTotals {
+        self.with_state(|s| EnergyTotals {
+            baseline: s.baseline,
+            totals: s.totals.clone(),
+            last_tick: s.last_tick_flows.clone(),
+            ticks_checked: s.ticks_checked,
+            violations: s.violations,
+            max_abs_drift: s.max_abs_drift,
+            recent: s.recent.iter().cloned().collect(),
+        })
+        .unwrap_or_default()
+    }
+
+    /// Учёт, который ничего не записывает (умершие ноды)
+    pub fn detached() -> Self {
+        Self::default()
+    }
+
+    /// Записать изменение энергии (знак: + источник, − сток)
+    pub fn record(&self, flow: EnergyFlow, delta: f64) {
+        if delta == 0.0 {
+            return;
+        }
+        if let Some(inner) = &self.inner {
+            if let Ok(mut state) = inner.lock() {
+                *state.tick_flows.entry(flow).or_insert(0.0) += delta;
+            }
+        }
+    }
+
+    /// Записать сторону перевода энергии ноды `node` (вдобавок к `record`)
+    pub fn record_transfer(&self, node: &str, delta: f64) {
+        if delta == 0.0 {
+            return;
+        }
+        self.with_state(|s| s.tick_transfers.push((node.to_string(), delta)));
+    }
+
+    /// Забрать закрытые переводы для нового блока
+    pub fn take_transfers(&self) -> Vec<EnergyTransfer> {
+        self.with_state(|s| std::mem::take(&mut s.sealed_transfers)).unwrap_or_default()
+    }
+
+    /// Вернуть переводы отклонённого блока: они попадут в следующий, раньше новых
+    pub fn requeue_transfers(&self, transfers: Vec<EnergyTransfer>) {
+        self.with_state(|s| {
+            let newer = std::mem::replace(&mut s.sealed_transfers, transfers);
+            s.sealed_transfers.extend(newer);
+        });
+    }
+
+    /// Переводы, ещё не попавшие в блок (для контрольной точки)
+    pub fn pending_transfers(&self) -> Vec<EnergyTransfer> {
+        self.with_state(|s| s.sealed_transfers.clone()).unwrap_or_default()
+    }
+
+    pub fn has_baseline(&self) -> bool {
+        self.with_state(|s| s.baseline.is_some()).unwrap_or(true)
+    }
+
+    /// Отсчёт для первой проверки (старт или восстановление из контрольной точки)
+    pub fn set_baseline(&self, total: f64) {
+        self.with_state(|s| {
+            s.baseline = Some(total);
+            s.tick_flows.clear();
+        });
+    }
+
+    /// ⚖️ Закрыть тик: сверить `actual` с ожиданием.
+    /// Возвращает расхождение, если оно больше погрешности вычислений.
+    pub fn close_tick(&self, tick: u64, actual: f64) -> Option<Drift> {
+        self.with_state(|s| {
+            let flows = std::mem::take(&mut s.tick_flows);
+            let transfers = std::mem::take(&mut s.tick_transfers);
+            s.sealed_transfers
+                .extend(transfers.into_iter().map(|(node, delta)| EnergyTransfer { tick, node, delta }));
+            let net: f64 = flows.values().sum();
+            for (flow, delta) in flows.iter() {
+                *s.totals.entry(*flow).or_insert(0.0) += delta;
+            }
+            s.last_tick_flows = flows;
+
+            // первый тик после старта без отсчёта не проверяется
+            let baseline = s.baseline.replace(actual)?;
+            s.ticks_checked += 1;
+
+            let expected = baseline + net;
+            let drift = actual - expected;
+            // погрешность f64 растёт с масштабом суммы
+            let tolerance = 1e-6 + 1e-9 * actual.abs().max(expected.abs());
+            if drift.abs() <= tolerance {
+                return None;
+            }
+
+            let record = Drift { tick, expected, actual, drift };
+            s.violations += 1;
+            s.max_abs_drift = s.max_abs_drift.max(drift.abs());
+            if s.recent.len() == RECENT_VIOLATIONS {
+                s.recent.pop_front();
+            }
+            s.recent.push_back(record.clone());
+            Some(record)
+        })
+        .flatten()
+    }
+
+    /// 📊 Отчёт для `/energy/budget`
+    pub fn budget(&self, tick: u64, total_energy: f64) -> EnergyBudget {
+        self.with_state(|s| {
+            let mut budget = EnergyBudget {
+                tick,
+                total_energy,
+                sources: BTreeMap::new(),
+                sinks: BTreeMap::new(),
+                transfer_imbalance: 0.0,
+                net: 0.0,
+                last_tick: s.last_tick_flows.clone(),
+                invariant: InvariantReport {
+                    ticks_checked: s.ticks_checked,
+                    violations: s.violations,
+                    max_abs_drift: s.max_abs_drift,
+                    recent: s.recent.iter().cloned().collect(),
+                },
+            };
+            for (flow, delta) in s.totals.iter() {
+                match flow.category() {
+                    FlowCategory::Source => {
+                        budget.sources.insert(*flow, *delta);
+                    }
+                    FlowCategory::Sink => {
+                        budget.sinks.insert(*flow, *delta);
+                    }
+                    FlowCategory::Transfer => budget.transfer_imbalance += delta,
+                }
+                budget.net += delta;
+            }
+            budget
+        })
+        .unwrap_or_else(|| EnergyBudget::empty(tick, total_energy))
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&mut LedgerState) -> T) -> Option<T> {
+        let inner = self.inner.as_ref()?;
+        let mut state = inner.lock().ok()?;
+        Some(f(&mut state))
+    }
+}
+
+/// Разбивка потоков энергии с начала прогона (значения со знаком: стоки отрицательны)
+#[derive(Clone, Debug, Serialize)]
+pub struct EnergyBudget {
+    pub tick: u64,
+    pub total_energy: f64,
+    pub sources: BTreeMap<EnergyFlow, f64>,
+    pub sinks: BTreeMap<EnergyFlow, f64>,
+    /// переводы должны взаимно гаситься; ненулевой остаток — энергия,
+    /// отправленная без получателя или полученная без отправителя
+    pub transfer_imbalance: f64,
+    pub net: f64,
+    /// потоки последнего закрытого тика
+    pub last_tick: BTreeMap<EnergyFlow, f64>,
+    pub invariant: InvariantReport,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InvariantReport {
+    pub ticks_checked: u64,
+    pub violations: u64,
+    pub max_abs_drift: f64,
+    pub recent: Vec<Drift>,
+}
+
+impl EnergyBudget {
+    fn empty(tick: u64, total_energy: f64) -> Self {
+        Self {
+            tick,
+            total_energy,
+            sources: BTreeMap::new(),
+            sinks: BTreeMap::new(),
+            transfer_imbalance: 0.0,
+            net: 0.0,
+            last_tick: BTreeMap::new(),
+            invariant: InvariantReport {
+                ticks_checked: 0,
+                violations: 0,
+                max_abs_drift: 0.0,
+                recent: Vec::new(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::energy::Energy;
+    use crate::events::EventLog;
+    use crate::rng::Seed;
+    use crate::scheduler::Scheduler;
+
+    #[tokio::test]
+    async fn full_run_keeps_the_budget_balanced() {
+        let mut config = Config::default();
+        config.simulation.initial_nodes = 4;
+        config.population.cull_above = 12;
+        config.population.cull_keep = 8;
+        let world = crate::test_world(&config).await;
+        let start = world.total_energy().await;
+        let mut scheduler = Scheduler::new(
+            config.scheduler.clone(),
+            config.economy,
+            config.consensus,
+            config.checkpoint.clone(),
+            Seed::new(1),
+            world.clone(),
+        );
+        for _ in 0..40 {
+            scheduler.step().await;
+        }
+
+        let total = world.total_energy().await;
+        let budget = world.energy.budget(40, total);
+        assert_eq!(budget.invariant.ticks_checked, 40);
+        assert_eq!(budget.invariant.violations, 0, "{:?}", budget.invariant.recent);
+        assert!(!budget.sources.is_empty() && !budget.sinks.is_empty());
+        assert!(budget.transfer_imbalance.abs() < 1e-6);
+        assert!((start + budget.net - total).abs() < 1e-6, "{} + {} != {}", start, budget.net, total);
+    }
+
+    #[test]
+    fn unbooked_change_is_flagged_as_drift() {
+        let ledger = EnergyLedger::new();
+        let mut a = Energy::new("a", EventLog::disabled(), ledger.clone());
+        let mut b = Energy::new("b", EventLog::disabled(), ledger.clone());
+        ledger.set_baseline(a.level + b.level);
+
+        // тик с записанными потоками сходится
+        a.consume(10.0, EnergyFlow::Decay);
+        a.consume(5.0, EnergyFlow::Transfer);
+        b.gain(5.0, EnergyFlow::Transfer);
+        b.restore(20.0, EnergyFlow::Regen);
+        assert!(ledger.close_tick(1, a.level + b.level).is_none());
+        assert_eq!(ledger.take_transfers().len(), 2);
+
+        // энергия, появившаяся мимо учёта
+        a.level += 7.0;
+        let drift = ledger.close_tick(2, a.level + b.level).expect("дрейф не замечен");
+        assert_eq!(drift.tick, 2);
+        assert!((drift.drift - 7.0).abs() < 1e-9);
+
+        // отсчёт сдвигается на факт — следующий честный тик снова сходится
+        a.consume(1.0, EnergyFlow::Decay);
+        assert!(ledger.close_tick(3, a.level + b.level).is_none());
+
+        let budget = ledger.budget(3, a.level + b.level);
+        assert_eq!((budget.invariant.ticks_checked, budget.invariant.violations), (3, 1));
+        assert!((budget.invariant.max_abs_drift - 7.0).abs() < 1e-9);
+        assert_eq!(budget.invariant.recent.iter().map(|d| d.tick).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(budget.sinks[&EnergyFlow::Cap], -25.0);
+        assert_eq!(budget.transfer_imbalance, 0.0);
+    }
+}
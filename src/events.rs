@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::clock::SimClock;
+use crate::energy_ledger::EnergyFlow;
+use crate::token_amount::TokenAmount;
+
+/// 📜 Типизированное изменение состояния организма.
+/// Каждое событие несёт итоговое значение (`level`, `balance`, `total`),
+/// поэтому при воспроизведении его можно применить без пересчёта.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// нода появилась: стартовая, потомок или восстановленная из контрольной точки
+    NodeSpawned {
+        node: String,
+        parent: Option<String>,
+        energy: f64,
+        balance: TokenAmount,
+        efficiency: f64,
+        altruism: f64,
+        resilience: f64,
+        experience: f64,
+        data_chain_height: usize,
+        key_chain_height: usize,
+    },
+    NodeDied {
+        node: String,
+        energy: f64,
+        cause: DeathCause,
+    },
+    /// изменились черты ноды (эволюция, обучение, `POST /update/:id`)
+    TraitsChanged {
+        node: String,
+        efficiency: f64,
+        altruism: f64,
+        resilience: f64,
+        experience: f64,
+    },
+    EnergyConsumed { node: String, amount: f64, level: f64, flow: EnergyFlow },
+    EnergyRestored { node: String, amount: f64, level: f64, flow: EnergyFlow },
+    /// прямое присваивание уровня (порог, потолок, `POST /update/:id`)
+    EnergySet { node: String, level: f64, flow: EnergyFlow },
+    WalletReward { node: String, amount: TokenAmount, balance: TokenAmount },
+    WalletSpend { node: String, amount: TokenAmount, balance: TokenAmount },
+    FundAdd { amount: TokenAmount, total: TokenAmount },
+    FundWithdraw { amount: TokenAmount, total: TokenAmount },
+    BlockCommitted {
+        node: String,
+        chain: ChainKind,
+        index: u64,
+        validator: String,
+        hash: String,
+    },
+    /// блок выпал из цепи ноды при смене ветки — нейроны и синапсы,
+    /// записанные им, откатываются
+    BlockOrphaned {
+        node: String,
+        chain: ChainKind,
+        index: u64,
+        validator: String,
+        hash: String,
+    },
+    /// нода перешла на лучшую ветку; `height` — новая длина цепи
+    ChainReorg {
+        node: String,
+        chain: ChainKind,
+        common_ancestor: u64,
+        orphaned: usize,
+        adopted: usize,
+        height: usize,
+    },
+    /// мозг выбрал действие (`help` / `evolve` / `rest`)
+    BrainDecision { action: String, avg_energy: f64 },
+    /// раунд Proof-of-Contribution: один победитель на высоту
+    ConsensusRound {
+        height: u64,
+        winner: String,
+        weight: f64,
+        total_weight: f64,
+        participants: usize,
+        hash: String,
+    },
+    /// победитель объявил блок; для принятия нужно `needed` голосов «за» из `voters`
+    BlockProposed { height: u64, proposer: String, hash: String, voters: usize, needed: usize },
+    /// кворум набран, блок вошёл в `key_chain` нод (следом идёт `ConsensusRound`)
+    BlockFinalized { height: u64, hash: String, approvals: usize, rejections: usize },
+    /// блок не принят: против большинство или голосование не уложилось в срок
+    BlockRejected { height: u64, hash: String, approvals: usize, rejections: usize, reason: String },
+    /// итог цикла мозга после адаптации
+    BrainAdapted { result: f64, aggressiveness: f64 },
+    /// `Brain::redistribute_energy` — сами переводы записаны событиями энергии
+    EnergyRedistributed { from: String, to: String, amount: f64 },
+    /// прогон продолжен из контрольной точки; дальше идут `NodeSpawned` всех нод
+    Restored { fund: TokenAmount, aggressiveness: f64 },
+    /// 📡 симулятор сети исказил доставку сообщения; `due` — тик доставки копии
+    /// или придержанного сообщения
+    NetFault {
+        fault: NetFault,
+        from: String,
+        to: String,
+        message: String,
+        due: Option<u64>,
+    },
+    /// просьба о помощи исполнена: энергия списана у помощника и зачислена
+    /// просившему одной операцией
+    HelpSettled { request: u64, requester: String, giver: String, amount: f64 },
+    /// запрос закрыт по сроку, так и не приняв ни одного ответа
+    RequestExpired { request: u64, from: String, message: String, replies: usize },
+    /// началось разделение `netsim.partitions[index]`
+    PartitionStarted { index: usize, groups: Vec<Vec<String>> },
+    PartitionHealed { index: usize },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeathCause {
+    /// энергия опустилась до `population.death_energy`
+    Starved,
+    /// удалена при перенаселении
+    Culled,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetFault {
+    /// сообщение потеряно
+    Lost,
+    /// доставлено дважды
+    Duplicated,
+    /// придержано — отправленные позже придут раньше
+    Reordered,
+    /// отправитель и получатель по разные стороны разделения
+    Partitioned,
+    /// получатель умер, пока сообщение было в пути
+    RecipientGone,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainKind {
+    Data,
+    Key,
+}
+
+/// Одна строка журнала
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub seq: u64,
+    pub tick: u64,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+/// 🗒️ Append-only журнал событий (JSON Lines).
+/// Клонирование дешёвое; выключенный журнал ничего не пишет.
+#[derive(Clone, Default)]
+pub struct EventLog {
+    sink: Option<Arc<Mutex<Sink>>>,
+}
+
+struct Sink {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    clock: SimClock,
+    seq: u64,
+}
+
+impl fmt::Debug for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.sink {
+            Some(sink) => match sink.lock() {
+                Ok(sink) => write!(f, "EventLog({})", sink.path.display()),
+                Err(_) => write!(f, "EventLog(poisoned)"),
+            },
+            None => write!(f, "EventLog(off)"),
+        }
+    }
+}
+
+impl EventLog {
+    /// Журнал, который ничего не записывает
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Открыть файл журнала. `resume = None` начинает его заново.
+    /// `Some(seq)` — продолжение с контрольной точки: записи после `seq`
+    /// (прогон, брошенный после её снятия) отрезаются, нумерация продолжается с `seq`.
+    pub fn open(path: &Path, clock: SimClock, resume: Option<u64>) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        if let Some(seq) = resume {
+            keep_until(path, seq)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resume.is_some())
+            .truncate(resume.is_none())
+            .open(path)?;
+        let seq = resume.unwrap_or(0);
+
+        Ok(Self {
+            sink: Some(Arc::new(Mutex::new(Sink {
+                path: path.to_path_buf(),
+                writer: Some(BufWriter::new(file)),
+                clock,
+                seq,
+            }))),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Номер последней записанной записи
+    pub fn seq(&self) -> u64 {
+        self.sink.as_ref().and_then(|sink| sink.lock().ok().map(|sink| sink.seq)).unwrap_or(0)
+    }
+
+    /// Записать событие с номером текущего тика
+    pub fn emit(&self, event: Event) {
+        let Some(sink) = &self.sink else {
+            return;
+        };
+        let Ok(mut sink) = sink.lock() else {
+            return;
+        };
+        sink.seq += 1;
+        let record = LogRecord { seq: sink.seq, tick: sink.clock.tick(), event };
+
+        let path = sink.path.clone();
+        let Some(writer) = sink.writer.as_mut() else {
+            return;
+        };
+        let written = serde_json::to_writer(&mut *writer, &record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"));
+        if let Err(e) = written {
+            // журнал с дырой бесполезен для воспроизведения — прекращаем запись
+            eprintln!("❌ Журнал событий {} отключён: {}", path.display(), e);
+            sink.writer = None;
+        }
+    }
+
+    /// Сбросить буфер на диск (планировщик вызывает в конце каждого тика)
+    pub fn flush(&self) {
+        if let Some(sink) = &self.sink {
+            if let Ok(mut sink) = sink.lock() {
+                if let Some(writer) = sink.writer.as_mut() {
+                    if let Err(e) = writer.flush() {
+                        eprintln!("⚠️ Не удалось сбросить журнал событий: {}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// ✂️ Оставить в журнале записи с номерами до `seq` включительно: сначала
+/// во временный файл, затем переименовать. Нет журнала — нечего резать.
+fn keep_until(path: &Path, seq: u64) -> std::io::Result<()> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let tmp = path.with_extension("jsonl.tmp");
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    for line in text.lines() {
+        // оборванная строка бывает только последней — дальше читать нечего
+        match serde_json::from_str::<LogRecord>(line) {
+            Ok(record) if record.seq <= seq => writeln!(writer, "{}", line)?,
+            _ => break,
+        }
+    }
+    writer.flush()?;
+    drop(writer);
+    std::fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(path: &Path) -> Vec<LogRecord> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn resume_drops_the_abandoned_run() {
+        let dir = std::env::temp_dir().join(format!("organism-events-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("events.jsonl");
+        let clock = SimClock::new(0, 100);
+        let fund = |total: u64| Event::FundAdd { amount: TokenAmount::ZERO, total: TokenAmount::from_minor(total) };
+
+        let log = EventLog::open(&path, clock.clone(), None).unwrap();
+        for tick in 1..=6 {
+            clock.set_tick(tick);
+            log.emit(fund(tick));
+        }
+        log.flush();
+        drop(log);
+
+        // контрольная точка снята после третьей записи, прогон после неё брошен
+        clock.set_tick(3);
+        let log = EventLog::open(&path, clock.clone(), Some(3)).unwrap();
+        assert_eq!(log.seq(), 3);
+        clock.set_tick(4);
+        log.emit(fund(40));
+        log.flush();
+
+        let seen = records(&path);
+        assert_eq!(seen.iter().map(|r| r.seq).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert_eq!(seen.iter().map(|r| r.tick).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert!(matches!(&seen[3].event, Event::FundAdd { total, .. } if *total == TokenAmount::from_minor(40)));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,198 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::rng::SimRng;
+
+/// Номер потока ChaCha, из которого выводится ключ. Основной поток ноды
+/// (stream 0) при этом не сдвигается — прогон с тем же seed не меняется.
+const IDENTITY_STREAM: u64 = 0x1d;
+
+/// 🔑 Личность ноды: имя и пара ключей Ed25519.
+/// Подписывает блоки, сообщения шины и TCP-пакеты; в контрольную точку
+/// попадает вместе с секретным ключом.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "IdentityFile", try_from = "IdentityFile")]
+pub struct Identity {
+    pub name: String,
+    signing: SigningKey,
+}
+
+/// Как личность лежит в контрольной точке
+#[derive(Serialize, Deserialize)]
+struct IdentityFile {
+    name: String,
+    secret_key: String,
+}
+
+impl From<Identity> for IdentityFile {
+    fn from(id: Identity) -> Self {
+        Self { name: id.name, secret_key: hex::encode(id.signing.to_bytes()) }
+    }
+}
+
+impl TryFrom<IdentityFile> for Identity {
+    type Error = IdentityError;
+
+    fn try_from(file: IdentityFile) -> Result<Self, Self::Error> {
+        let bytes: [u8; 32] = decode_fixed(&file.secret_key).ok_or(IdentityError::BadKey(file.name.clone()))?;
+        Ok(Self { name: file.name, signing: SigningKey::from_bytes(&bytes) })
+    }
+}
+
+impl Identity {
+    /// Вывести ключ из потока RNG ноды, не расходуя сам поток
+    pub fn derive(name: &str, rng: &SimRng) -> Self {
+        let mut keygen = rng.clone();
+        keygen.set_stream(IDENTITY_STREAM);
+        let mut secret = [0u8; 32];
+        keygen.fill_bytes(&mut secret);
+        Self { name: name.to_string(), signing: SigningKey::from_bytes(&secret) }
+    }
+
+    /// 🎲 Новый ключ из системного источника случайности (`OsRng`): по seed
+    /// его не восстановить, поэтому он хранится в контрольной точке и каталоге ноды
+    pub fn generate(name: &str) -> Self {
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        Self { name: name.to_string(), signing: SigningKey::from_bytes(&secret) }
+    }
+
+    /// Открытый ключ в hex
+    pub fn public_key(&self) -> String {
+        hex::encode(self.signing.verifying_key().to_bytes())
+    }
+
+    /// ✍️ Подпись в hex
+    pub fn sign(&self, bytes: &[u8]) -> String {
+        hex::encode(self.signing.sign(bytes).to_bytes())
+    }
+}
+
+impl fmt::Debug for Identity {
+    /// Секретный ключ в отладочный вывод не попадает
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Identity({}, {})", self.name, self.public_key())
+    }
+}
+
+/// 🔍 Проверить подпись `signature` под `bytes` открытым ключом `public_key` (всё в hex)
+pub fn verify(public_key: &str, bytes: &[u8], signature: &str) -> Result<(), IdentityError> {
+    let key: [u8; 32] = decode_fixed(public_key).ok_or_else(|| IdentityError::BadKey(public_key.to_string()))?;
+    let key = VerifyingKey::from_bytes(&key).map_err(|_| IdentityError::BadKey(public_key.to_string()))?;
+    let sig: [u8; 64] = decode_fixed(signature).ok_or(IdentityError::BadSignature)?;
+    key.verify(bytes, &Signature::from_bytes(&sig))
+        .map_err(|_| IdentityError::BadSignature)
+}
+
+fn decode_fixed<const N: usize>(value: &str) -> Option<[u8; N]> {
+    hex::decode(value).ok()?.try_into().ok()
+}
+
+/// Почему подпись или ключ не приняты
+#[derive(Debug)]
+pub enum IdentityError {
+    /// ключ не разбирается как Ed25519
+    BadKey(String),
+    /// подпись не сходится с данными
+    BadSignature,
+    /// об отправителе ничего не известно
+    UnknownSigner(String),
+    /// под именем известной ноды пришёл чужой ключ
+    KeyMismatch(String),
+}
+
+impl fmt::Display for IdentityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentityError::BadKey(key) => write!(f, "некорректный ключ {}", key),
+            IdentityError::BadSignature => write!(f, "подпись не сходится"),
+            IdentityError::UnknownSigner(name) => write!(f, "неизвестный отправитель {}", name),
+            IdentityError::KeyMismatch(name) => write!(f, "ключ не совпадает с известным ключом {}", name),
+        }
+    }
+}
+
+impl std::error::Error for IdentityError {}
+
+/// 📒 Открытые ключи всех нод, которых видел организм: имя → ключ (hex).
+/// Умершие ноды не удаляются — их блоки и сообщения остаются проверяемыми.
+#[derive(Clone, Default)]
+pub struct KeyRegistry {
+    inner: Arc<Mutex<BTreeMap<String, String>>>,
+    /// новым нодам — случайные ключи (`Identity::generate`), а не выведенные из seed
+    random: bool,
+}
+
+impl KeyRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Реестр из контрольной точки
+    pub fn from_keys(keys: BTreeMap<String, String>) -> Self {
+        Self { inner: Arc::new(Mutex::new(keys)), random: false }
+    }
+
+    /// Выдавать новым нодам случайные ключи. Нужно, когда ключи видят другие
+    /// процессы: ключ из seed восстановит любой, кто знает seed.
+    pub fn with_random_keys(mut self, random: bool) -> Self {
+        self.random = random;
+        self
+    }
+
+    /// 🔑 Ключ для новой ноды `name` с потоком RNG `rng`: случайный или выведенный
+    /// из потока (один процесс, прогон повторяется по seed)
+    pub fn issue(&self, name: &str, rng: &SimRng) -> Identity {
+        match self.random {
+            true => Identity::generate(name),
+            false => Identity::derive(name, rng),
+        }
+    }
+
+    pub fn keys(&self) -> BTreeMap<String, String> {
+        self.inner.lock().unwrap().clone()
+    }
+
+    /// Записать ключ своей ноды (при рождении)
+    pub fn register(&self, identity: &Identity) {
+        self.inner.lock().unwrap().insert(identity.name.clone(), identity.public_key());
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.inner.lock().unwrap().get(name).cloned()
+    }
+
+    /// Ключ `public_key` действительно принадлежит ноде `name`
+    pub fn check(&self, name: &str, public_key: &str) -> Result<(), IdentityError> {
+        match self.get(name) {
+            Some(known) if known == public_key => Ok(()),
+            Some(_) => Err(IdentityError::KeyMismatch(name.to_string())),
+            None => Err(IdentityError::UnknownSigner(name.to_string())),
+        }
+    }
+
+    /// Для пиров по TCP: первый ключ, пришедший под именем, запоминается,
+    /// дальше под этим именем принимается только он
+    pub fn pin(&self, name: &str, public_key: &str) -> Result<(), IdentityError> {
+        let mut keys = self.inner.lock().unwrap();
+        match keys.get(name) {
+            Some(known) if known != public_key => Err(IdentityError::KeyMismatch(name.to_string())),
+            Some(_) => Ok(()),
+            None => {
+                keys.insert(name.to_string(), public_key.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    /// 🔍 Подпись `signature` под `bytes` поставлена известной нодой `name`
+    pub fn verify_from(&self, name: &str, bytes: &[u8], signature: &str) -> Result<(), IdentityError> {
+        let key = self.get(name).ok_or_else(|| IdentityError::UnknownSigner(name.to_string()))?;
+        verify(&key, bytes, signature)
+    }
+}
@@ -1,19 +1,32 @@
 use serde::{Serialize, Deserialize};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use crate::node::Node;
+use crate::chain::Block;
+use crate::consensus::{self, Verdict};
+use crate::energy_ledger::EnergyFlow;
+use crate::events::Event;
+use crate::identity::{Identity, IdentityError, KeyRegistry};
+use crate::message_log::MessageLog;
+use crate::world::World;
 
 /// Типы сообщений между нодами
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MessageType {
     HelpRequest,       // просьба о помощи (энергии)
     EnergyTransfer,    // передача энергии
     BlockAnnouncement, // новый блок найден
     ValidateBlock,     // запрос на валидацию
+    Ack,               // подтверждение ответа: `value` — сколько принято, 0 — отказ
 }
 
-/// Сообщение, пересылаемое между нодами
+/// Сообщение, пересылаемое между нодами.
+/// Отправитель подписывает его своим ключом (`signed`), получатель
+/// проверяет подпись по реестру ключей (`verify`).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub from: String,
@@ -21,6 +34,14 @@ pub struct Message {
     pub msg_type: MessageType,
     pub value: f64,         // энергия или значимость
     pub content: Option<String>,
+    /// номер, выданный шиной процесса отправителя (`NetworkBus::post`); 0 — не выдан
+    #[serde(default)]
+    pub id: u64,
+    /// номер сообщения, на которое это ответ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<u64>,
+    /// подпись `from` под остальными полями (hex), пустая — не подписано
+    pub signature: String,
 }
 
 impl Message {
@@ -31,6 +52,9 @@ impl Message {
             msg_type,
             value,
             content: content.map(|s| s.to_string()),
+            id: 0,
+            reply_to: None,
+            signature: String::new(),
         }
     }
 
@@ -41,82 +65,893 @@ impl Message {
             msg_type: MessageType::EnergyTransfer,           // 👈 тип сообщения
             value: amount,                                  // 👈 передаём энергию
             content: Some("Energy transfer 💡".to_string()), // 👈 описание
+            id: 0,
+            reply_to: None,
+            signature: String::new(),
+        }
+    }
+
+    /// Пометить сообщение ответом на сообщение `id`
+    pub fn reply_to(mut self, id: u64) -> Self {
+        self.reply_to = Some(id);
+        self
+    }
+
+    /// ✍️ Подписать сообщение ключом отправителя
+    pub fn signed(mut self, identity: &Identity) -> Self {
+        self.signature = identity.sign(&self.signing_bytes());
+        self
+    }
+
+    /// 🔍 Подпись поставлена известной нодой `from`, и поля после этого не менялись
+    pub fn verify(&self, keys: &KeyRegistry) -> Result<(), IdentityError> {
+        keys.verify_from(&self.from, &self.signing_bytes(), &self.signature)
+    }
+
+    /// 🛡️ Значение годится для исполнения: конечное, неотрицательное,
+    /// перевод энергии — не больше `MAX_TRANSFER`
+    pub fn check_value(&self) -> Result<(), String> {
+        if !self.value.is_finite() || self.value < 0.0 {
+            return Err(format!("недопустимое значение {} в сообщении от {}", self.value, self.from));
+        }
+        if self.msg_type == MessageType::EnergyTransfer && self.value > MAX_TRANSFER {
+            return Err(format!("перевод {:.1} от {} больше предела {:.1}", self.value, self.from, MAX_TRANSFER));
+        }
+        Ok(())
+    }
+
+    /// Всё, кроме подписи, в каноническом JSON
+    fn signing_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(&(&self.from, &self.to, &self.msg_type, self.value, &self.content, self.id, self.reply_to))
+            .unwrap_or_default()
+    }
+}
+
+/// Темы шины. Широковещательное сообщение получают только ноды,
+/// подписанные на его тему; адресное доставляется всегда.
+pub const TOPICS: &[&str] = &["help", "energy", "blocks"];
+
+impl MessageType {
+    pub fn topic(&self) -> &'static str {
+        match self {
+            MessageType::HelpRequest | MessageType::Ack => "help",
+            MessageType::EnergyTransfer => "energy",
+            MessageType::BlockAnnouncement | MessageType::ValidateBlock => "blocks",
+        }
+    }
+}
+
+/// 📨 Открытый запрос: ждёт ответов до тика `deadline` включительно
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenRequest {
+    pub id: u64,
+    pub from: String,
+    pub msg_type: MessageType,
+    pub deadline: u64,
+    /// сколько ответов дошло до спросившего
+    pub replies: usize,
+    /// спросивший принял один из ответов (`NetworkBus::claim`)
+    pub claimed: bool,
+}
+
+/// Почему `NetworkBus::request` остался без ответа
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestError {
+    /// к сроку не пришло ни одного ответа
+    TimedOut,
+    /// шина закрыла запрос, не дождавшись срока (например, после восстановления)
+    Closed,
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::TimedOut => write!(f, "ответа нет: истёк срок запроса"),
+            RequestError::Closed => write!(f, "запрос закрыт без ответа"),
         }
     }
 }
 
-/// Каналы связи между нодами
+impl std::error::Error for RequestError {}
+
+/// Ответ на запрос придёт в одну из следующих фаз доставки
+pub type PendingReply = oneshot::Receiver<Result<Message, RequestError>>;
+
+struct Waiting {
+    open: OpenRequest,
+    waiter: Option<oneshot::Sender<Result<Message, RequestError>>>,
+}
+
+/// Входящая очередь ноды
+struct Inbox {
+    queue: VecDeque<Message>,
+    topics: BTreeSet<String>,
+    received: u64,
+    dropped: u64,
+}
+
+impl Inbox {
+    fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            topics: TOPICS.iter().map(|t| t.to_string()).collect(),
+            received: 0,
+            dropped: 0,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct Counters {
+    sent: u64,
+    /// не поместились в исходящую очередь
+    dropped: u64,
+    unicast: u64,
+    broadcast: u64,
+    /// адресат не известен шине (умер или не существовал)
+    undeliverable: u64,
+    requests: u64,
+    claimed: u64,
+    expired: u64,
+}
+
+/// Счётчики шины для `GET /bus`
+#[derive(Clone, Debug, Serialize)]
+pub struct BusStats {
+    /// сообщений в исходящей очереди сейчас
+    pub queued: usize,
+    pub capacity: usize,
+    pub inbox_capacity: usize,
+    pub sent: u64,
+    pub dropped: u64,
+    pub unicast: u64,
+    pub broadcast: u64,
+    pub undeliverable: u64,
+    /// сообщений, не поместившихся во входящие очереди
+    pub inbox_dropped: u64,
+    /// запросов отправлено, с принятым ответом, закрыто без него, открыто сейчас
+    pub requests: u64,
+    pub requests_claimed: u64,
+    pub requests_expired: u64,
+    pub requests_open: usize,
+    pub inboxes: Vec<InboxStats>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InboxStats {
+    pub node: String,
+    pub depth: usize,
+    pub received: u64,
+    pub dropped: u64,
+    pub topics: Vec<String>,
+}
+
+/// 🚌 Шина сообщений между нодами. Отправленное попадает в общую исходящую
+/// очередь; фаза доставки разбирает её по входящим очередям нод: адресное —
+/// ноде `to`, широковещательное — всем подписчикам темы, кроме отправителя.
+/// Ноды, появившиеся позже, подключаются к шине в следующей фазе доставки.
+/// Запрос (`request`) ждёт ответа с `reply_to` своего номера до срока.
 pub struct NetworkBus {
     pub sender: mpsc::Sender<Message>,
     pub receiver: Arc<Mutex<mpsc::Receiver<Message>>>,
+    capacity: usize,
+    inbox_capacity: usize,
+    /// сколько тиков запрос ждёт ответов
+    request_timeout: u64,
+    next_id: AtomicU64,
+    inboxes: std::sync::Mutex<BTreeMap<String, Inbox>>,
+    requests: std::sync::Mutex<BTreeMap<u64, Waiting>>,
+    counters: std::sync::Mutex<Counters>,
+    /// журнал доставляемых сообщений (`[messages]`)
+    messages: MessageLog,
 }
 
 impl NetworkBus {
-    pub fn new(buffer: usize) -> Self {
+    pub fn new(buffer: usize, inbox_capacity: usize, request_timeout: u64, messages: MessageLog) -> Self {
         let (tx, rx) = mpsc::channel(buffer);
         Self {
             sender: tx,
             receiver: Arc::new(Mutex::new(rx)),
+            capacity: buffer,
+            inbox_capacity,
+            request_timeout,
+            next_id: AtomicU64::new(1),
+            inboxes: std::sync::Mutex::new(BTreeMap::new()),
+            requests: std::sync::Mutex::new(BTreeMap::new()),
+            counters: std::sync::Mutex::new(Counters::default()),
+            messages,
         }
     }
 
-    /// Отправить сообщение
+    pub fn messages(&self) -> &MessageLog {
+        &self.messages
+    }
+
+    fn count(&self, f: impl FnOnce(&mut Counters)) {
+        if let Ok(mut counters) = self.counters.lock() {
+            f(&mut counters);
+        }
+    }
+
+    /// Отправить сообщение.
+    /// Не ждёт места в очереди: доставка идёт в отдельной фазе планировщика,
+    /// и ожидание внутри тика повесило бы его навсегда.
     pub async fn send(&self, msg: Message) {
-        if let Err(e) = self.sender.send(msg).await {
-            eprintln!("❌ Ошибка отправки сообщения: {}", e);
+        match self.sender.try_send(msg) {
+            Ok(()) => self.count(|c| c.sent += 1),
+            Err(e) => {
+                self.count(|c| c.dropped += 1);
+                eprintln!("❌ Ошибка отправки сообщения: {}", e);
+            }
         }
     }
 
-    /// Получить следующее сообщение
-    pub async fn receive(&self) -> Option<Message> {
+    /// ✍️ Выдать сообщению номер, подписать и отправить. Возвращает номер.
+    pub async fn post(&self, mut msg: Message, identity: &Identity) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        msg.id = id;
+        self.send(msg.signed(identity)).await;
+        id
+    }
+
+    /// 📨 Отправить запрос, не дожидаясь ответа: первый ответ (или ошибку по сроку)
+    /// получит `PendingReply`. Ответы приходят только в фазе доставки, поэтому
+    /// внутри фазы ответ не ждут — планировщик его просто отбрасывает.
+    pub async fn open_request(&self, msg: Message, identity: &Identity, tick: u64) -> (u64, PendingReply) {
+        let (tx, rx) = oneshot::channel();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let open = OpenRequest {
+            id,
+            from: msg.from.clone(),
+            msg_type: msg.msg_type.clone(),
+            deadline: tick + self.request_timeout,
+            replies: 0,
+            claimed: false,
+        };
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.insert(id, Waiting { open, waiter: Some(tx) });
+        }
+        self.count(|c| c.requests += 1);
+        let mut msg = msg;
+        msg.id = id;
+        self.send(msg.signed(identity)).await;
+        (id, rx)
+    }
+
+    /// Отправить запрос и дождаться первого ответа
+    pub async fn request(&self, msg: Message, identity: &Identity, tick: u64) -> Result<Message, RequestError> {
+        let (_, reply) = self.open_request(msg, identity, tick).await;
+        reply.await.unwrap_or(Err(RequestError::Closed))
+    }
+
+    /// Ответ дошёл до спросившего: отдать первый ответ ожидающему
+    fn note_reply(&self, to: &str, msg: &Message) {
+        let Some(id) = msg.reply_to else {
+            return;
+        };
+        let Ok(mut requests) = self.requests.lock() else {
+            return;
+        };
+        let Some(waiting) = requests.get_mut(&id).filter(|w| w.open.from == to) else {
+            return;
+        };
+        waiting.open.replies += 1;
+        if let Some(waiter) = waiting.waiter.take() {
+            let _ = waiter.send(Ok(msg.clone()));
+        }
+    }
+
+    /// 🤝 Спросивший `requester` принимает ответ на запрос `id`.
+    /// `true` — только для первого принятого ответа, пока запрос открыт.
+    pub fn claim(&self, id: u64, requester: &str) -> bool {
+        let claimed = self
+            .requests
+            .lock()
+            .ok()
+            .and_then(|mut requests| {
+                let waiting = requests.get_mut(&id).filter(|w| w.open.from == requester && !w.open.claimed)?;
+                waiting.open.claimed = true;
+                Some(())
+            })
+            .is_some();
+        if claimed {
+            self.count(|c| c.claimed += 1);
+        }
+        claimed
+    }
+
+    /// Принятый ответ исполнить не удалось — запрос снова ждёт других ответов
+    pub fn release(&self, id: u64) {
+        let released = self
+            .requests
+            .lock()
+            .ok()
+            .and_then(|mut requests| {
+                let waiting = requests.get_mut(&id).filter(|w| w.open.claimed)?;
+                waiting.open.claimed = false;
+                Some(())
+            })
+            .is_some();
+        if released {
+            self.count(|c| c.claimed -= 1);
+        }
+    }
+
+    /// ⌛ Закрыть запросы, срок которых прошёл до тика `tick`. Возвращает
+    /// закрытые без принятого ответа; их ожидающие получают `TimedOut`.
+    pub fn expire(&self, tick: u64) -> Vec<OpenRequest> {
+        let Ok(mut requests) = self.requests.lock() else {
+            return Vec::new();
+        };
+        let due: Vec<u64> = requests.values().filter(|w| w.open.deadline < tick).map(|w| w.open.id).collect();
+        let mut expired = Vec::new();
+        for id in due {
+            let Some(mut waiting) = requests.remove(&id) else {
+                continue;
+            };
+            if let Some(waiter) = waiting.waiter.take() {
+                let _ = waiter.send(Err(RequestError::TimedOut));
+            }
+            if !waiting.open.claimed {
+                expired.push(waiting.open);
+            }
+        }
+        drop(requests);
+        let count = expired.len() as u64;
+        self.count(|c| c.expired += count);
+        expired
+    }
+
+    /// Закрыть запрос `id` досрочно: ответов на него больше не ждут,
+    /// его ожидающий получает `Closed`
+    pub fn close(&self, id: u64) {
+        let waiting = self.requests.lock().ok().and_then(|mut requests| requests.remove(&id));
+        if let Some(waiter) = waiting.and_then(|mut w| w.waiter.take()) {
+            let _ = waiter.send(Err(RequestError::Closed));
+        }
+    }
+
+    /// Открытые запросы и следующий номер — для контрольной точки
+    pub fn open_requests(&self) -> (u64, Vec<OpenRequest>) {
+        let requests = self
+            .requests
+            .lock()
+            .map(|requests| requests.values().map(|w| w.open.clone()).collect())
+            .unwrap_or_default();
+        (self.next_id.load(Ordering::Relaxed), requests)
+    }
+
+    /// Продолжить нумерацию и открытые запросы из контрольной точки
+    /// (их ожидающие остались в прошлом процессе)
+    pub fn restore_requests(&self, next_id: u64, open: Vec<OpenRequest>) {
+        self.next_id.store(next_id.max(1), Ordering::Relaxed);
+        if let Ok(mut requests) = self.requests.lock() {
+            for open in open {
+                requests.insert(open.id, Waiting { open, waiter: None });
+            }
+        }
+    }
+
+    /// 📋 Сообщения исходящей очереди для контрольной точки: очередь остаётся
+    /// прежней, а счётчик отправленных не растёт
+    pub async fn pending(&self) -> Vec<Message> {
+        let messages = self.drain().await;
+        self.requeue(messages.clone());
+        messages
+    }
+
+    /// Вернуть сообщения в исходящую очередь, не считая их отправленными заново
+    /// (снимок очереди, восстановление из контрольной точки)
+    pub fn requeue(&self, messages: Vec<Message>) {
+        for msg in messages {
+            if let Err(e) = self.sender.try_send(msg) {
+                self.count(|c| c.dropped += 1);
+                eprintln!("❌ Сообщение не вернулось в очередь: {}", e);
+            }
+        }
+    }
+
+    /// Забрать все накопившиеся сообщения (фаза доставки)
+    pub async fn drain(&self) -> Vec<Message> {
         let mut rx = self.receiver.lock().await;
-        rx.recv().await
+        let mut messages = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            messages.push(msg);
+        }
+        messages
+    }
+
+    /// 👥 Привести состав шины к живым нодам: новым — очередь с подпиской
+    /// на все темы, у исчезнувших очередь удаляется вместе с содержимым
+    pub fn sync_members(&self, names: &[String]) {
+        let Ok(mut inboxes) = self.inboxes.lock() else {
+            return;
+        };
+        let alive: BTreeSet<&String> = names.iter().collect();
+        let mut lost = 0;
+        inboxes.retain(|name, inbox| {
+            let keep = alive.contains(name);
+            if !keep {
+                lost += inbox.queue.len() as u64;
+            }
+            keep
+        });
+        for name in names {
+            inboxes.entry(name.clone()).or_insert_with(Inbox::new);
+        }
+        drop(inboxes);
+        self.count(|c| c.undeliverable += lost);
+    }
+
+    /// Кому достанется сообщение: адресату или подписчикам темы
+    pub fn recipients(&self, msg: &Message) -> Vec<String> {
+        let Ok(inboxes) = self.inboxes.lock() else {
+            return Vec::new();
+        };
+        let recipients: Vec<String> = match &msg.to {
+            Some(to) => inboxes.contains_key(to).then(|| to.clone()).into_iter().collect(),
+            None => inboxes
+                .iter()
+                .filter(|(name, inbox)| **name != msg.from && inbox.topics.contains(msg.msg_type.topic()))
+                .map(|(name, _)| name.clone())
+                .collect(),
+        };
+        drop(inboxes);
+        match &msg.to {
+            Some(_) if recipients.is_empty() => self.count(|c| c.undeliverable += 1),
+            Some(_) => self.count(|c| c.unicast += 1),
+            None => self.count(|c| c.broadcast += 1),
+        }
+        recipients
+    }
+
+    /// 📥 Положить сообщение во входящую очередь ноды.
+    /// `false` — такой ноды у шины нет; переполненная очередь теряет новое сообщение.
+    pub fn deliver(&self, to: &str, msg: Message) -> bool {
+        let Ok(mut inboxes) = self.inboxes.lock() else {
+            return false;
+        };
+        let Some(inbox) = inboxes.get_mut(to) else {
+            drop(inboxes);
+            self.count(|c| c.undeliverable += 1);
+            return false;
+        };
+        if inbox.queue.len() >= self.inbox_capacity {
+            inbox.dropped += 1;
+            println!("📪 Очередь {} переполнена, сообщение от {} потеряно", to, msg.from);
+            return true;
+        }
+        inbox.received += 1;
+        let reply = msg.reply_to.is_some().then(|| msg.clone());
+        inbox.queue.push_back(msg);
+        drop(inboxes);
+        if let Some(reply) = reply {
+            self.note_reply(to, &reply);
+        }
+        true
+    }
+
+    /// Забрать входящие сообщения ноды
+    pub fn take(&self, name: &str) -> Vec<Message> {
+        self.inboxes
+            .lock()
+            .ok()
+            .and_then(|mut inboxes| inboxes.get_mut(name).map(|inbox| inbox.queue.drain(..).collect()))
+            .unwrap_or_default()
+    }
+
+    /// 🏷️ Заменить подписки ноды; неизвестная тема — ошибка
+    pub fn set_topics(&self, name: &str, topics: &[String]) -> Result<(), String> {
+        if let Some(unknown) = topics.iter().find(|t| !TOPICS.contains(&t.as_str())) {
+            return Err(format!("неизвестная тема '{}', есть: {}", unknown, TOPICS.join(", ")));
+        }
+        let mut inboxes = self.inboxes.lock().map_err(|_| "шина недоступна".to_string())?;
+        let inbox = inboxes.get_mut(name).ok_or_else(|| format!("нода {} не подключена к шине", name))?;
+        inbox.topics = topics.iter().cloned().collect();
+        Ok(())
+    }
+
+    /// Подписки, отличные от подписки на все темы, — для контрольной точки
+    pub fn custom_topics(&self) -> BTreeMap<String, Vec<String>> {
+        let Ok(inboxes) = self.inboxes.lock() else {
+            return BTreeMap::new();
+        };
+        inboxes
+            .iter()
+            .filter(|(_, inbox)| inbox.topics.len() != TOPICS.len())
+            .map(|(name, inbox)| (name.clone(), inbox.topics.iter().cloned().collect()))
+            .collect()
+    }
+
+    pub fn stats(&self) -> BusStats {
+        let inboxes: Vec<InboxStats> = self
+            .inboxes
+            .lock()
+            .map(|inboxes| {
+                inboxes
+                    .iter()
+                    .map(|(name, inbox)| InboxStats {
+                        node: name.clone(),
+                        depth: inbox.queue.len(),
+                        received: inbox.received,
+                        dropped: inbox.dropped,
+                        topics: inbox.topics.iter().cloned().collect(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let counters = self.counters.lock().map(|c| c.clone()).unwrap_or_default();
+        let requests_open = self.requests.lock().map(|r| r.len()).unwrap_or(0);
+        BusStats {
+            queued: self.capacity - self.sender.capacity(),
+            capacity: self.capacity,
+            inbox_capacity: self.inbox_capacity,
+            sent: counters.sent,
+            dropped: counters.dropped,
+            unicast: counters.unicast,
+            broadcast: counters.broadcast,
+            undeliverable: counters.undeliverable,
+            inbox_dropped: inboxes.iter().map(|i| i.dropped).sum(),
+            requests: counters.requests,
+            requests_claimed: counters.claimed,
+            requests_expired: counters.expired,
+            requests_open,
+            inboxes,
+        }
     }
 }
 
+/// Живые ноды процесса по именам — через них ответ на просьбу о помощи
+/// находит энергию помощника
+pub type Directory = BTreeMap<String, Arc<Mutex<Node>>>;
+
+/// Сколько энергии помощник предлагает в ответ на просьбу
+pub const HELP_AMOUNT: f64 = 5.0;
+
+/// Больше этого за один перевод энергии не передаётся
+pub const MAX_TRANSFER: f64 = 50.0;
+
 /// Пример поведения ноды при получении сообщения
-pub async fn handle_message(node: Arc<Mutex<Node>>, msg: Message, network: Arc<NetworkBus>) {
-    let mut n = node.lock().await;
+pub async fn handle_message(node: Arc<Mutex<Node>>, msg: Message, world: &World, directory: &Directory) {
+    let n = node.lock().await;
+    let network = &world.net;
 
     match msg.msg_type {
-        // 🔋 Получение энергии
-        MessageType::EnergyTransfer => {
-            let mut energy = n.energy.lock().await;
-            energy.restore(msg.value);
-            println!("🔋 {} получил {:.1} энергии от {}", n.name, msg.value, msg.from);
+        // 🔋 Ответ на просьбу о помощи: перевод проводится у обеих сторон сразу
+        MessageType::EnergyTransfer if msg.reply_to.is_some() => {
+            settle_help(&n, &msg, network, directory).await;
         }
 
-        // 🙋 Запрос на помощь
+        // 🔋 Получение энергии: списывается у отправителя при зачислении,
+        // поэтому из сообщения энергия не возникает
+        MessageType::EnergyTransfer => match transfer(directory, &msg.from, &n, msg.value).await {
+            Ok(amount) => println!("🔋 {} получил {:.1} энергии от {}", n.name, amount, msg.from),
+            Err(reason) => println!("⚠️ {} не получил энергию от {}: {}", n.name, msg.from, reason),
+        },
+
+        // 🙋 Запрос на помощь: помощник только предлагает энергию —
+        // списание будет, когда просивший примет предложение.
+        // Ноде другого процесса не предлагаем: энергия не пересекает границу процесса
+        MessageType::HelpRequest if !directory.contains_key(&msg.from) => {}
         MessageType::HelpRequest => {
             let current_energy = n.energy.lock().await.level;
             if current_energy > 30.0 && n.altruism > 0.5 {
-                // формируем ответ
-                let response = Message::new(
+                let offer = Message::new(
                     &n.name,
-                    msg.to.as_deref(),
+                    Some(&msg.from),
                     MessageType::EnergyTransfer,
-                    5.0,
+                    HELP_AMOUNT,
                     Some("Помогаю соседу 🔋"),
+                )
+                .reply_to(msg.id);
+                network.post(offer, &n.identity).await;
+                println!("🤝 {} предложил энергию по запросу {}", n.name, msg.from);
+            }
+        }
+
+        // ✅ Просивший ответил на наше предложение
+        MessageType::Ack => {
+            if msg.value > 0.0 {
+                println!("✅ {} подтвердил помощь от {}: {:.1} энергии", msg.from, n.name, msg.value);
+            } else {
+                println!(
+                    "↩️ {} отклонил помощь от {}: {}",
+                    msg.from,
+                    n.name,
+                    msg.content.as_deref().unwrap_or("без причины")
                 );
-                network.send(response).await;
-                println!("🤝 {} отправил энергию по запросу {}", n.name, msg.from);
             }
         }
 
-        // 🧱 Объявление нового блока
+        // 🧱 Объявление нового блока: проверить и ответить голосом
         MessageType::BlockAnnouncement => {
-            println!("🧱 {} получил уведомление о новом блоке от {}", n.name, msg.from);
+            let block = msg.content.as_deref().and_then(|c| serde_json::from_str::<Block>(c).ok());
+            let (approve, content) = match &block {
+                None => (false, "в объявлении нет блока".to_string()),
+                Some(block) => match n.validate_keyblock(block).await {
+                    Ok(()) => (true, block.hash.clone()),
+                    Err(e) => (false, e.to_string()),
+                },
+            };
+            println!(
+                "🧱 {} {} блок #{} от {}",
+                n.name,
+                if approve { "одобрил" } else { "отверг" },
+                msg.value,
+                msg.from
+            );
+            let vote = Message::new(
+                &n.name,
+                Some(&msg.from),
+                MessageType::ValidateBlock,
+                if approve { 1.0 } else { 0.0 },
+                Some(&content),
+            )
+            .reply_to(msg.id);
+            network.post(vote, &n.identity).await;
         }
 
-        // 🧐 Проверка блока
+        // 🧐 Голос за наш блок
         MessageType::ValidateBlock => {
-            println!("🧐 {} проверяет блок от {}", n.name, msg.from);
+            let Some(announcement) = msg.reply_to else {
+                return;
+            };
+            match world.ballots.vote(announcement, &msg.from, msg.value > 0.0) {
+                Verdict::Finalize(proposal) => consensus::finalize(proposal, &n, directory, world).await,
+                Verdict::Reject(proposal) => consensus::reject(proposal, world, "против большинство нод"),
+                Verdict::Pending => println!("🧐 {} учёл голос {} за блок", n.name, msg.from),
+                Verdict::Ignored => {}
+            }
+        }
+    }
+}
+
+/// 🤝 Принять первое предложение помощи: списать энергию у помощника и
+/// зачислить просившему под обеими блокировками сразу, затем подтвердить.
+/// Остальные предложения и предложения, которые нечем оплатить, отклоняются.
+async fn settle_help(n: &Node, msg: &Message, network: &NetworkBus, directory: &Directory) {
+    let Some(request) = msg.reply_to else {
+        return;
+    };
+    let settled = if msg.from == n.name || !network.claim(request, &n.name) {
+        Err("помощь уже получена или запрос закрыт")
+    } else {
+        let settled = transfer(directory, &msg.from, n, msg.value).await;
+        // не исполненный ответ освобождает запрос для других помощников
+        if settled.is_err() {
+            network.release(request);
         }
+        settled
+    };
 
-        // ⚙️ По умолчанию
-        _ => {
-            println!("ℹ️ {} получил сообщение {:?} от {}", n.name, msg.msg_type, msg.from);
+    let ack = match settled {
+        Ok(amount) => {
+            n.log.emit(Event::HelpSettled {
+                request,
+                requester: n.name.clone(),
+                giver: msg.from.clone(),
+                amount,
+            });
+            println!("🔋 {} получил {:.1} энергии от {} по запросу #{}", n.name, amount, msg.from, request);
+            Message::new(&n.name, Some(&msg.from), MessageType::Ack, amount, Some("помощь принята"))
         }
+        Err(reason) => Message::new(&n.name, Some(&msg.from), MessageType::Ack, 0.0, Some(reason)),
+    };
+    network.post(ack.reply_to(msg.id), &n.identity).await;
+}
+
+/// 🔋 Списать до `amount` энергии у ноды `from` и зачислить `taker` под обеими
+/// блокировками сразу. Переводы от нод другого процесса отсекает ещё
+/// `Network::apply`; уже умершего отправителя нет в справочнике — списать у него нечего.
+async fn transfer(directory: &Directory, from: &str, taker: &Node, amount: f64) -> Result<f64, &'static str> {
+    if from == taker.name {
+        return Err("перевод самому себе");
+    }
+    let Some(giver) = directory.get(from) else {
+        return Err("отправитель недоступен");
+    };
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err("нечего переводить");
+    }
+    let giver_energy = giver.lock().await.energy.clone();
+    let mut give = giver_energy.lock().await;
+    let mut take = taker.energy.lock().await;
+    let amount = amount.min(MAX_TRANSFER).min(give.level);
+    if amount > 0.0 {
+        give.consume(amount, EnergyFlow::Transfer);
+        take.gain(amount, EnergyFlow::Transfer);
+        Ok(amount)
+    } else {
+        Err("у отправителя не осталось энергии")
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn bus(inbox_capacity: usize) -> NetworkBus {
+        let bus = NetworkBus::new(8, inbox_capacity, 3, MessageLog::disabled());
+        bus.sync_members(&names(&["a", "b", "c"]));
+        bus
+    }
+
+    fn broadcast(from: &str, msg_type: MessageType) -> Message {
+        Message::new(from, None, msg_type, 1.0, None)
+    }
+
+    #[test]
+    fn unicast_reaches_the_addressee_and_broadcast_everyone_else() {
+        let bus = bus(4);
+        let unicast = Message::new("a", Some("b"), MessageType::Ack, 1.0, None);
+        assert_eq!(bus.recipients(&unicast), names(&["b"]));
+        // широковещательное не возвращается отправителю
+        assert_eq!(bus.recipients(&broadcast("a", MessageType::HelpRequest)), names(&["b", "c"]));
+
+        for to in bus.recipients(&broadcast("b", MessageType::BlockAnnouncement)) {
+            assert!(bus.deliver(&to, broadcast("b", MessageType::BlockAnnouncement)));
+        }
+        assert_eq!(bus.take("a").len(), 1);
+        assert!(bus.take("a").is_empty());
+        assert!(bus.take("b").is_empty());
+        assert_eq!(bus.take("c").len(), 1);
+
+        let stats = bus.stats();
+        assert_eq!((stats.unicast, stats.broadcast, stats.undeliverable), (1, 2, 0));
+    }
+
+    #[test]
+    fn broadcast_reaches_only_topic_subscribers() {
+        let bus = bus(4);
+        bus.set_topics("c", &names(&["blocks"])).unwrap();
+        assert_eq!(bus.recipients(&broadcast("a", MessageType::HelpRequest)), names(&["b"]));
+        assert_eq!(bus.recipients(&broadcast("a", MessageType::EnergyTransfer)), names(&["b"]));
+        assert_eq!(bus.recipients(&broadcast("a", MessageType::ValidateBlock)), names(&["b", "c"]));
+        // адресное доставляется без оглядки на подписки
+        let unicast = Message::new("a", Some("c"), MessageType::HelpRequest, 1.0, None);
+        assert_eq!(bus.recipients(&unicast), names(&["c"]));
+
+        bus.set_topics("b", &[]).unwrap();
+        assert!(bus.recipients(&broadcast("a", MessageType::HelpRequest)).is_empty());
+        assert_eq!(bus.custom_topics().len(), 2);
+
+        assert!(bus.set_topics("a", &names(&["gossip"])).is_err());
+        assert!(bus.set_topics("z", &names(&["help"])).is_err());
+    }
+
+    #[test]
+    fn nodes_spawned_later_join_on_sync() {
+        let bus = bus(4);
+        let to_d = Message::new("a", Some("d"), MessageType::Ack, 1.0, None);
+        assert!(bus.recipients(&to_d).is_empty());
+        assert!(!bus.deliver("d", to_d.clone()));
+        assert_eq!(bus.stats().undeliverable, 2);
+
+        bus.sync_members(&names(&["a", "b", "c", "d"]));
+        assert_eq!(bus.recipients(&to_d), names(&["d"]));
+        assert_eq!(bus.recipients(&broadcast("a", MessageType::HelpRequest)), names(&["b", "c", "d"]));
+        assert!(bus.deliver("d", to_d));
+        assert_eq!(bus.take("d").len(), 1);
+
+        // ушедшая нода теряет недочитанное, и это засчитывается как недоставленное
+        assert!(bus.deliver("c", broadcast("a", MessageType::HelpRequest)));
+        bus.sync_members(&names(&["a", "b", "d"]));
+        assert_eq!(bus.stats().undeliverable, 3);
+        assert!(bus.recipients(&Message::new("a", Some("c"), MessageType::Ack, 1.0, None)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn full_queues_drop_and_count() {
+        let bus = bus(2);
+        for _ in 0..3 {
+            assert!(bus.deliver("b", broadcast("a", MessageType::HelpRequest)));
+        }
+        let stats = bus.stats();
+        let b = stats.inboxes.iter().find(|i| i.node == "b").unwrap();
+        assert_eq!((b.depth, b.received, b.dropped), (2, 2, 1));
+        assert_eq!(stats.inbox_dropped, 1);
+        assert_eq!(bus.take("b").len(), 2);
+        // после разбора очередь снова принимает
+        assert!(bus.deliver("b", broadcast("a", MessageType::HelpRequest)));
+        assert_eq!(bus.stats().inbox_dropped, 1);
+
+        // исходящая очередь на 1 сообщение
+        let small = NetworkBus::new(1, 2, 3, MessageLog::disabled());
+        small.send(broadcast("a", MessageType::HelpRequest)).await;
+        small.send(broadcast("a", MessageType::HelpRequest)).await;
+        let stats = small.stats();
+        assert_eq!((stats.sent, stats.dropped, stats.queued), (1, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn request_is_claimed_once_and_expires_after_deadline() {
+        let bus = bus(4);
+        let identity = Identity::derive("a", &crate::rng::Seed::new(1).stream("node/a"));
+        let ask = broadcast("a", MessageType::HelpRequest);
+        let (id, reply) = bus.open_request(ask.clone(), &identity, 10).await;
+
+        // первый ответ достаётся ожидающему, последующие только считаются
+        assert!(bus.deliver("a", Message::new("b", Some("a"), MessageType::EnergyTransfer, 5.0, None).reply_to(id)));
+        assert!(bus.deliver("a", Message::new("c", Some("a"), MessageType::EnergyTransfer, 5.0, None).reply_to(id)));
+        assert_eq!(reply.await.unwrap().unwrap().from, "b");
+        assert_eq!(bus.open_requests().1[0].replies, 2);
+
+        // принять может только спросивший и только один раз
+        assert!(!bus.claim(id, "b"));
+        assert!(bus.claim(id, "a"));
+        assert!(!bus.claim(id, "a"));
+        bus.release(id);
+        assert!(bus.claim(id, "a"));
+        assert_eq!(bus.stats().requests_claimed, 1);
+
+        let (unanswered, pending) = bus.open_request(ask, &identity, 10).await;
+        // срок — `request_timeout` тиков включительно
+        assert!(bus.expire(13).is_empty());
+        let expired = bus.expire(14);
+        assert_eq!(expired.iter().map(|r| r.id).collect::<Vec<_>>(), vec![unanswered]);
+        assert_eq!(pending.await.unwrap().unwrap_err(), RequestError::TimedOut);
+        assert!(!bus.claim(id, "a"));
+
+        let stats = bus.stats();
+        assert_eq!((stats.requests, stats.requests_expired, stats.requests_open), (2, 1, 0));
+    }
+
+    #[tokio::test]
+    async fn help_is_settled_once() {
+        let mut config = crate::config::Config::default();
+        config.simulation.initial_nodes = 4;
+        let world = crate::test_world(&config).await;
+        let nodes = world.nodes.lock().await.clone();
+        let mut directory = Directory::new();
+        for node in nodes.iter() {
+            directory.insert(node.lock().await.name.clone(), node.clone());
+        }
+        let level = |i: usize| {
+            let node = nodes[i].clone();
+            async move {
+                let energy = node.lock().await.energy.clone();
+                let level = energy.lock().await.level;
+                level
+            }
+        };
+        let (requester, broke, giver, late) = {
+            let mut names = Vec::new();
+            for node in nodes.iter() {
+                names.push(node.lock().await.name.clone());
+            }
+            (names[0].clone(), names[1].clone(), names[2].clone(), names[3].clone())
+        };
+        nodes[1].lock().await.energy.lock().await.level = 0.0;
+        let (before, giver_before, late_before) = (level(0).await, level(2).await, level(3).await);
+
+        let ask = Message::new(&requester, None, MessageType::HelpRequest, 0.0, None);
+        let identity = nodes[0].lock().await.identity.clone();
+        let (id, _reply) = world.net.open_request(ask, &identity, 0).await;
+        world.net.drain().await;
+        let offer = |from: &str| Message::new(from, Some(&requester), MessageType::EnergyTransfer, HELP_AMOUNT, None).reply_to(id);
+
+        // помощнику нечем платить — запрос остаётся открытым для других
+        handle_message(nodes[0].clone(), offer(&broke), &world, &directory).await;
+        handle_message(nodes[0].clone(), offer(&giver), &world, &directory).await;
+        handle_message(nodes[0].clone(), offer(&late), &world, &directory).await;
+
+        assert_eq!(level(0).await, before + HELP_AMOUNT);
+        assert_eq!(level(1).await, 0.0);
+        assert_eq!(level(2).await, giver_before - HELP_AMOUNT);
+        assert_eq!(level(3).await, late_before);
+
+        let acks: Vec<(String, f64)> = world
+            .net
+            .drain()
+            .await
+            .into_iter()
+            .map(|ack| (ack.to.unwrap(), ack.value))
+            .collect();
+        assert_eq!(acks, vec![(broke, 0.0), (giver, HELP_AMOUNT), (late, 0.0)]);
+        assert_eq!(world.net.stats().requests_claimed, 1);
+    }
+}
@@ -1,8 +1,17 @@
+
 mod node;
 mod network;
+mod netsim;
+mod peers;
+mod wire;
+mod block_store;
 mod chain;
+mod data_dir;
 mod synapse;
 mod energy;
+mod energy_ledger;
+mod token_amount;
+mod token_ledger;
 mod neuron;
 mod energy_evolution;
 mod api;
@@ -11,26 +20,50 @@ mod wallet;
 mod economy;
 mod economy_cycle;
 mod brain;
+mod brain_policy;
 mod memory;
+mod rng;
+mod clock;
+mod scheduler;
+mod world;
+mod batch;
+mod config;
+mod checkpoint;
+mod consensus;
+mod events;
+mod identity;
+mod merkle;
+mod payload;
+mod replay;
+mod message_log;
 
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tokio::task;
-/* use std::time::Duration; */
-use axum::{Router}; 
+use axum::{Router};
 use tokio::net::TcpListener;
-use tokio::time::{sleep, Duration};  
-use tower_http::cors::{CorsLayer, Any};
 use tokio::sync::RwLock;
 use interaction::*;
 use crate::node::Node;
 use crate::api::{AppState, create_router};
-use crate::energy_evolution::EnergyEvolution;
 use crate::economy::NetworkFund;
-use crate::economy_cycle::EconomyCycle; 
-use crate::brain::{Brain, BrainSnapshot}; 
-use rand::thread_rng;
+use crate::brain::{Brain, BrainSnapshot};
+use crate::rng::Seed;
+use crate::clock::SimClock;
+use crate::scheduler::{RunMode, Scheduler};
+use crate::config::{CliArgs, Config};
+use crate::world::World;
+use crate::checkpoint::Checkpoint;
+use crate::energy_ledger::EnergyLedger;
+use crate::token_ledger::TokenLedger;
+use crate::identity::KeyRegistry;
+use crate::payload::BlockBodies;
+use crate::events::EventLog;
+use crate::data_dir::DataRoot;
+use crate::network::Network;
+use crate::netsim::NetSim;
+use crate::message_log::MessageLog;
+use crate::consensus::Ballots;
 use chrono::Utc;
 
 
@@ -38,200 +71,364 @@ use chrono::Utc;
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
-    println!("🚀 Запуск системы ORGANISM...");
-    env_logger::init();
-
-    // 🧠 Создаём общий канал связи
-    let network = Arc::new(NetworkBus::new(100));
+    // ⌨️ Командная строка целиком: неизвестный флаг останавливает запуск
+    let args = match CliArgs::from_env() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(2);
+        }
+    };
 
-    let count = 10;
-    // 🧩 Создаём несколько нод
-   let nodes: Vec<_> = (0..count)
-    .map(|i| Node::new(&format!("node{}", i)))
-    .collect();
+    // ⏪ --replay журнал [--until N]: пересобрать состояние из журнала событий,
+    // напечатать его в stdout как JSON и выйти
+    if let Some(options) = &args.replay {
+        match replay::replay(&options.path, options.until) {
+            Ok(state) => println!("{}", serde_json::to_string_pretty(&state).unwrap()),
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
-    // Оборачиваем в Arc<Mutex<Vec<...>>> — общий доступ
-    let shared_nodes = Arc::new(Mutex::new(nodes));
-     // ✅ создаём общий фонд
-    let fund = Arc::new(Mutex::new(NetworkFund::new()));
+    println!("🚀 Запуск системы ORGANISM...");
+    env_logger::init();
 
-    // ✅ создаём мозг
-    let brain = Arc::new(RwLock::new(Brain::new()));
+    // ⚙️ Конфигурация: organism.toml / --config, затем ORGANISM__*, затем --set и флаги
+    let config = match Config::load(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(2);
+        }
+    };
+    let mut scheduler_config = config.scheduler.clone();
 
-    let snapshot = Arc::new(RwLock::new(BrainSnapshot {
-        aggressiveness: 1.0,
-        avg_recent_result: 0.0,
-        recent_memory: Vec::new(),
-        last_update: chrono::Utc::now().timestamp(),
-    }));
- 
-
-    // ✅ создаём клоны для потоков
-    let nodes_ref = Arc::clone(&shared_nodes);
-    let fund_ref = Arc::clone(&fund);
-    let brain_ref: Arc<RwLock<Brain>> = Arc::clone(&brain);
-    let brain_clone: Arc<RwLock<Brain>> = Arc::clone(&brain);
-    let snapshot_ref: Arc<RwLock<BrainSnapshot>> = Arc::clone(&snapshot);
-    let snapshot_task_ref = Arc::clone(&snapshot_ref);
-
-    // 🧬 Запускаем обработчик сообщений
-    for node in shared_nodes.lock().await.iter().cloned() {
-        let net = network.clone();
-        task::spawn(async move {
-            loop {
-                if let Some(msg) = net.receive().await {
-                    handle_message(node.clone(), msg, net.clone()).await;
+    // 🧹 --gc: удалить каталоги умерших и остановленных нод из корня данных и выйти
+    if args.gc {
+        match data_dir::collect_garbage(&config.storage.dir, config.storage.fsync) {
+            Ok(report) => {
+                println!(
+                    "🧹 {}: удалено {} каталогов умерших и остановленных нод ({} байт), живых нод {}",
+                    config.storage.dir.display(),
+                    report.removed.len(),
+                    report.bytes_freed,
+                    report.alive
+                );
+                if !report.unknown.is_empty() {
+                    println!("⚠️ Каталоги без записи в манифесте (не тронуты): {}", report.unknown.join(", "));
                 }
             }
-        });
-    }
-
-    // 🔁 Периодическое взаимодействие между нодами
-    {
-        let net = network.clone();
-        let nodes_ref = shared_nodes.clone();
-        task::spawn(async move {
-            let mut tick = 0;
-            loop {
-                sleep(Duration::from_secs(5)).await;
-                tick += 1;
-
-                let nodes = nodes_ref.lock().await;
-                let idx = tick % nodes.len();
-                let sender = nodes[idx].lock().await;
-                let msg = Message::new(
-                    &sender.name,
-                    None,
-                    MessageType::HelpRequest,
-                    0.0,
-                    Some("Мне нужна энергия ⚡"),
-                );
-                println!("📡 {} отправил сигнал помощи", sender.name);
-                net.send(msg).await;
+            Err(e) => {
+                eprintln!("❌ Сборка мусора в {} не удалась: {}", config.storage.dir.display(), e);
+                std::process::exit(1);
             }
-        });
+        }
+        return;
     }
 
-    // 🌱 Запускаем фоновую эволюцию
-    {
-        let evolution_nodes = shared_nodes.clone();
-        task::spawn(async move {
-            loop {
-                {
-                    let mut guard = evolution_nodes.lock().await;
-                    EnergyEvolution::evolve(&mut guard).await;
-                }
-                println!("💓 Пульс организма (эволюция прошла)");
-                sleep(Duration::from_secs(10)).await;
+    // 💾 --restore путь: продолжить организм из контрольной точки
+    let restored = match &args.restore {
+        Some(path) => match Checkpoint::load(path) {
+            Ok(checkpoint) => {
+                println!("♻️ Восстановление из {} (тик {})", path.display(), checkpoint.tick);
+                Some(checkpoint)
             }
-        });
-    }
-    
-
-    {
-        let nodes_ref = shared_nodes.clone();
-        let fund_ref = fund.clone();
-        task::spawn(async move {
-            loop {  
-                {
-                    EconomyCycle::run(nodes_ref.clone(), fund_ref.clone()).await;
-                }
-                tokio::time::sleep(Duration::from_secs(8)).await;
-                println!("💫 [DEBUG] Цикл экономики активен...");
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                std::process::exit(2);
             }
-        });
+        },
+        None => None,
+    };
+
+    // 🎲 Главный seed: из контрольной точки, из конфига / --seed / ORGANISM_SEED, иначе случайный
+    let seed = match &restored {
+        Some(checkpoint) => {
+            if config.seed.is_some_and(|s| s != checkpoint.seed) {
+                println!("⚠️ Seed из конфига игнорируется: используется seed контрольной точки");
+            }
+            Seed::new(checkpoint.seed)
+        }
+        None => config.seed.map(Seed::new).unwrap_or_else(Seed::random),
+    };
+    println!("🎲 Seed симуляции: {} (повтор: --seed {})", seed.value(), seed.value());
+
+    // 🧪 Пакетный режим: --headless --ticks N --report путь
+    let batch = args.batch.clone();
+    if batch.is_some() {
+        // без API ждать нечего — тики идут подряд
+        scheduler_config.mode = RunMode::AsFastAsPossible;
     }
-    // 🧠 Запускаем сознание (координация между нодами)
-    {
-        let nodes_ref = shared_nodes.clone();
-        let fund_ref = fund.clone(); 
-        let net = network.clone();
-        println!("🧠 Инициализация мозга");
-
-        // === Задача 1: основной цикл мозга ===
-        let brain_clone_for_run = Arc::clone(&brain_clone);
-        let nodes_ref_clone = Arc::clone(&nodes_ref);
-        let fund_ref_clone = Arc::clone(&fund_ref);
-        let net_clone = Arc::clone(&net);
-        println!("🧠 [INIT] Запуск основного цикла мозга...");  
-
-        // === Запускаем run() для когнитивной активности мозга ===
-        let brain_for_run_task = Arc::clone(&brain_clone_for_run);
-        let nodes_for_run_task = Arc::clone(&nodes_ref_clone);
-        let fund_for_run_task = Arc::clone(&fund_ref_clone);
-        let net_for_run_task = Arc::clone(&net_clone);
-        /* tokio::spawn(async move {
-            let mut brain_guard = brain_for_run_task.write().await;
-            brain_guard.run(nodes_for_run_task, fund_for_run_task, net_for_run_task).await;
-        }); */
-        tokio::spawn(async move {
-            loop {
-                {
-                    // 🔹 Берём brain на короткий момент
-                    let brain_arc = brain_for_run_task.clone();
-                    let mut brain_guard = brain_arc.write().await;
-
-                    // 🔹 Клонируем внутреннее состояние, чтобы освободить блокировку
-                    let mut brain_copy = brain_guard.clone();
-                    drop(brain_guard); // <— снимаем lock прямо здесь!
-
-                    // 🔹 Теперь можно вызывать .await безопасно — brain не заблокирован
-                    brain_copy.run(nodes_for_run_task.clone(), fund_for_run_task.clone(), net_for_run_task.clone()).await;
-                }
 
-                // 🔁 Небольшая пауза между циклами
-                tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    // ⏱️ Часы симуляции: после восстановления — с тика контрольной точки,
+    // в реальном времени без seed отсчёт от текущего момента;
+    // с seed или в быстром режиме — от нуля, чтобы прогоны совпадали байт в байт
+    let clock = match (&restored, scheduler_config.mode) {
+        (Some(checkpoint), _) => checkpoint.clock(scheduler_config.tick_ms),
+        (None, RunMode::RealTime) if config.seed.is_none() => SimClock::new(Utc::now().timestamp(), scheduler_config.tick_ms),
+        (None, _) => SimClock::new(0, scheduler_config.tick_ms),
+    };
+
+    // 📜 Журнал событий: новый прогон начинает файл заново, восстановленный —
+    // продолжает с записи контрольной точки
+    let log = if config.events.enabled {
+        match EventLog::open(&config.events.path, clock.clone(), restored.as_ref().map(|c| c.event_seq)) {
+            Ok(log) => {
+                println!("📜 Журнал событий → {}", config.events.path.display());
+                log
             }
-        });
-
-        tokio::spawn(async move {
-            let mut brain_copy = {
-                let brain_ref = brain_clone_for_run.read().await;
-                brain_ref.clone() // ✅ клонируем сам Brain, не guard
-            };
-            brain_copy.evolve_network(nodes_ref_clone, fund_ref_clone, net_clone).await;
-        });
-        // === Задача 2: периодическое обновление снимка состояния ===
-        let brain_for_snapshot = Arc::clone(&brain_clone);
-        let snapshot_task_ref_clone = Arc::clone(&snapshot_task_ref);
-        println!("🧠 [DEBUG] Инициализация таски snapshot...");
-       tokio::spawn(async move {
-        println!("📸 [DEBUG] Snapshot task started!");
-            // внутри snapshot таски
-            loop {
-                let snapshot = BrainSnapshot::from_brain_lock(&brain_for_snapshot).await;
-
-                {
-                    let mut snap = snapshot_task_ref_clone.write().await;
-                    *snap = snapshot.clone();
-                }
+            Err(e) => {
+                eprintln!("❌ Не удалось открыть журнал {}: {}", config.events.path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        EventLog::disabled()
+    };
 
-                println!(
-                    "📸 [Snapshot] обновлён: память = {} событий, агрессивность = {:.2}",
-                    snapshot.recent_memory.len(),
-                    snapshot.aggressiveness
-                );
+    // 🗂️ Корень данных: каталоги нод с цепями, нейронами, синапсами, кошельком и памятью
+    let data = match DataRoot::open(&config.storage) {
+        Ok(data) => {
+            if data.is_enabled() {
+                println!("🗂️ Данные нод → {}", config.storage.dir.display());
+            }
+            data
+        }
+        Err(e) => {
+            eprintln!("❌ Не удалось открыть корень данных {}: {}", config.storage.dir.display(), e);
+            std::process::exit(1);
+        }
+    };
 
-                // ⏸️ обновляем раз в 10 секунд (а не в 1)
-                tokio::time::sleep(Duration::from_secs(10)).await;
+    // 📨 Журнал сообщений шины: как и журнал событий, восстановленный прогон
+    // продолжает его с записи контрольной точки
+    let messages = match MessageLog::open(&config.messages, clock.clone(), restored.as_ref().map(|c| c.message_seq)) {
+        Ok(messages) => {
+            if messages.is_enabled() {
+                println!("📨 Журнал сообщений → {}", config.messages.path.display());
             }
+            messages
+        }
+        Err(e) => {
+            eprintln!("❌ Не удалось открыть журнал сообщений {}: {}", config.messages.path.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    // 🚌 Шина сообщений между нодами
+    let bus = Arc::new(NetworkBus::new(
+        config.simulation.bus_capacity,
+        config.simulation.inbox_capacity,
+        config.simulation.request_timeout_ticks,
+        messages,
+    ));
+    let network = Network::new(&config.network);
+    // 📡 Симулятор сети: задержки, потери и разделения между нодами
+    let netsim = NetSim::new(&config.netsim, seed, log.clone());
+    if netsim.is_enabled() {
+        println!("📡 Симулятор сети включён: {} правил связей, {} разделений", config.netsim.links.len(), config.netsim.partitions.len());
+    }
 
-        });
-        
+    let (world, streams) = match restored {
+        Some(checkpoint) => {
+            let (world, streams) = checkpoint
+                .into_world(
+                    clock,
+                    log,
+                    bus,
+                    config.population,
+                    brain_policy::from_config(&config.brain),
+                    data,
+                    network.clone(),
+                    netsim,
+                )
+                .await;
+            (world, Some(streams))
+        }
+        None => (new_world(&config, seed, clock, log, bus, data, network.clone(), netsim).await, None),
+    };
+
+    // 🌐 Сетевой режим: принимать пакеты пиров и рассылать им своё состояние
+    if let Err(e) = network.start(world.clone()).await {
+        eprintln!("❌ Не удалось занять {}: {}", config.network.listen, e);
+        std::process::exit(1);
     }
-    
-    
+
+    // ⏲️ Все циклы организма — фазы одного глобального тика
+    let mut scheduler = Scheduler::new(
+        scheduler_config,
+        config.economy,
+        config.consensus,
+        config.checkpoint.clone(),
+        seed,
+        world.clone(),
+    );
+    if let Some(streams) = streams {
+        scheduler.restore_streams(streams);
+    }
+
+    if let Some(options) = batch {
+        let report = batch::run(scheduler, seed.value(), &options).await;
+        if let Err(e) = report.write(&options.report_path) {
+            eprintln!("❌ Не удалось записать отчёт {}: {}", options.report_path.display(), e);
+            std::process::exit(1);
+        }
+        println!(
+            "📊 Прогон завершён: {} тиков, отчёт → {}",
+            report.ticks_run,
+            options.report_path.display()
+        );
+        world.record_shutdown().await;
+        return;
+    }
+
+    let checkpoints = scheduler.checkpoint_handle();
+    tokio::spawn(scheduler.run());
+
     // 🌍 API сервер
-    let state = api::AppState {
-        nodes: shared_nodes.clone(),
-        fund: Arc::clone(&fund),
-        brain: brain_ref.clone(),
-        snapshot: snapshot_ref.clone(),
+    let state = AppState {
+        nodes: world.nodes.clone(),
+        fund: world.fund.clone(),
+        brain: world.brain.clone(),
+        snapshot: world.snapshot.clone(),
+        rng: world.api_rng.clone(),
+        mining: config.mining,
+        checkpoints,
+        energy: world.energy.clone(),
+        tokens: world.tokens.clone(),
+        clock: world.clock.clone(),
+        bodies: world.bodies.clone(),
+        network: world.network.clone(),
+        bus: world.net.clone(),
     };
     let app: Router = create_router(state);
 
-    let listener = TcpListener::bind("127.0.0.1:3000").await.unwrap();
-    println!("🌐 API доступно на http://127.0.0.1:3000");
+    let listener = match TcpListener::bind(&config.api.bind).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("❌ Не удалось занять {}: {}", config.api.bind, e);
+            std::process::exit(1);
+        }
+    };
+    println!("🌐 API доступно на http://{}", config.api.bind);
 
-    axum::serve(listener, app).await.unwrap();
-}
\ No newline at end of file
+    // 🛑 Ctrl+C: сервер дорабатывает текущие запросы, живые ноды отмечаются в манифесте
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .unwrap();
+    world.record_shutdown().await;
+    println!("🛑 Организм остановлен на тике {}", world.clock.tick());
+}
+
+/// 🧪 Мир для тестов: без диска, журналов и симулятора сети
+#[cfg(test)]
+async fn test_world(config: &Config) -> World {
+    let seed = Seed::new(config.seed.unwrap_or(1));
+    let bus = Arc::new(NetworkBus::new(
+        config.simulation.bus_capacity,
+        config.simulation.inbox_capacity,
+        config.simulation.request_timeout_ticks,
+        MessageLog::disabled(),
+    ));
+    new_world(
+        config,
+        seed,
+        SimClock::new(0, config.scheduler.tick_ms),
+        EventLog::disabled(),
+        bus,
+        DataRoot::disabled(),
+        Network::new(&config.network),
+        NetSim::disabled(),
+    )
+    .await
+}
+
+/// 🌱 Новый организм: `simulation.initial_nodes` нод, пустой фонд, свежий мозг
+#[allow(clippy::too_many_arguments)]
+async fn new_world(
+    config: &Config,
+    seed: Seed,
+    clock: SimClock,
+    log: EventLog,
+    bus: Arc<NetworkBus>,
+    data: DataRoot,
+    network: Network,
+    netsim: NetSim,
+) -> World {
+    // 🔋 Учёт энергии: отсчёт берётся с энергии стартовых нод
+    let energy = EnergyLedger::new();
+    // 📗 Книга токенов: все начисления и списания с тиком и причиной
+    let tokens = TokenLedger::new(clock.clone());
+    // 🔑 Реестр открытых ключей нод; в сети ключи случайные, а не из seed
+    let keys = KeyRegistry::new().with_random_keys(config.network.enabled);
+
+    let count = config.simulation.initial_nodes;
+    // 🧩 Создаём несколько нод (в сетевом режиме — с префиксом процесса)
+    let prefix = config.network.node_prefix();
+    let nodes: Vec<_> = (0..count)
+        .map(|i| {
+            let name = format!("{}node{}", prefix, i);
+            Node::new(
+                &name,
+                seed.stream(&format!("node/{}", name)),
+                config.node,
+                log.clone(),
+                energy.clone(),
+                tokens.clone(),
+                clock.clone(),
+                keys.clone(),
+                data.clone(),
+            )
+        })
+        .collect();
+    let mut names = Vec::with_capacity(nodes.len());
+    for node in nodes.iter() {
+        let node = node.lock().await;
+        node.log_spawn(None).await;
+        names.push(node.name.clone());
+    }
+    bus.sync_members(&names);
+
+    // Оборачиваем в Arc<Mutex<Vec<...>>> — общий доступ
+    let shared_nodes = Arc::new(Mutex::new(nodes));
+    // ✅ создаём общий фонд
+    let fund = Arc::new(Mutex::new(NetworkFund::new(log.clone(), tokens.clone())));
+
+    // ✅ создаём мозг
+    println!("🧠 Инициализация мозга");
+    let policy = brain_policy::from_config(&config.brain);
+    let brain = Arc::new(RwLock::new(Brain::new(seed.stream("brain"), clock.clone(), config.population, log.clone(), policy)));
+
+    let snapshot = Arc::new(RwLock::new(BrainSnapshot {
+        policy: config.brain.policy.name().to_string(),
+        aggressiveness: 1.0,
+        avg_recent_result: 0.0,
+        recent_memory: Vec::new(),
+        last_update: clock.now(),
+    }));
+
+    World {
+        nodes: shared_nodes,
+        fund,
+        brain,
+        snapshot,
+        net: bus,
+        clock,
+        log,
+        energy,
+        tokens,
+        keys,
+        bodies: BlockBodies::new(),
+        data,
+        network,
+        netsim,
+        ballots: Ballots::new(),
+        api_rng: Arc::new(Mutex::new(seed.stream("api"))),
+    }
+}
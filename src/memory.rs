@@ -1,9 +1,7 @@
-use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 use std::sync::Arc;
 use serde::{Serialize, Deserialize};
-use std::cmp::Reverse;
-use chrono::Utc;
+use crate::clock::SimClock;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BrainEvent {
@@ -31,25 +29,25 @@ pub struct Memory {
     pub max_short: usize,
     pub max_long: usize,
     pub retention_time: i64,
+    pub clock: SimClock,
 }
 
 impl Memory { 
-    pub fn new(max_short: usize, max_long: usize, retention_time: i64) -> Self {
+    pub fn new(max_short: usize, max_long: usize, retention_time: i64, clock: SimClock) -> Self {
         Self { 
             short: Arc::new(Mutex::new(Vec::new())),
             long: Arc::new(Mutex::new(Vec::new())),
             max_short,
             max_long,
             retention_time,
+            clock,
         }
     }
 
-    pub async fn record(&self, event: BrainEvent) {
-        self.add_event(event).await;
-    }
     // 💾 Добавление события в память
     pub async fn add_event(&self, mut event: BrainEvent) {
-        let now = Utc::now().timestamp();
+        // метка времени — по часам симуляции, чтобы прогоны были воспроизводимы
+        let now = self.clock.now();
         event.timestamp = now;
 
         println!("🧠 [Memory::add_event] Добавлено событие: {} | {} | {:.2}",
@@ -116,15 +114,4 @@ impl Memory {
         let sum: f64 = s.iter().rev().take(take_n).map(|e| e.result).sum();
         sum / (take_n as f64)
     }
-
-    /// 🧠 Комбинированная память — объединяет short + long память
-    pub async fn get_combined_memory(&self, count: usize) -> Vec<BrainEvent> {
-        let half = count / 2;
-        let mut combined = self.get_recent(half).await;
-        let mut long = self.get_long(count - half).await;
-        combined.append(&mut long);
-        // сортируем по убыванию timestamp: используем Reverse
-        combined.sort_by_key(|e| Reverse(e.timestamp));
-        combined
-    }
 }
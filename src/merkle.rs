@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub type Hash = [u8; 32];
+
+/// Префиксы разделяют листья и внутренние узлы: лист нельзя выдать за поддерево
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// 🌿 Хэш листа: SHA-256(0x00 ‖ данные)
+pub fn leaf_hash(bytes: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(bytes);
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Корень пустого дерева — SHA-256 от пустой строки
+pub fn empty_root() -> Hash {
+    Sha256::digest([]).into()
+}
+
+/// 🌳 Корень Меркла. Непарный последний узел уровня поднимается выше
+/// без изменений (не дублируется), поэтому разные списки листьев не дают один корень.
+pub fn root(leaves: &[Hash]) -> Hash {
+    if leaves.is_empty() {
+        return empty_root();
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Следующий уровень дерева: пары склеиваются, непарный узел поднимается как есть
+fn next_level(level: &[Hash]) -> Vec<Hash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+pub fn root_hex(leaves: &[Hash]) -> String {
+    hex::encode(root(leaves))
+}
+
+/// С какой стороны от пути стоит соседний узел
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Один шаг доказательства: соседний узел и его сторона
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub hash: String,
+    pub side: Side,
+}
+
+/// 🧾 Путь от листа `index` до корня; `None`, если такого листа нет
+pub fn prove(leaves: &[Hash], index: usize) -> Option<Vec<ProofStep>> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut steps = Vec::new();
+    let mut level = leaves.to_vec();
+    let mut position = index;
+    while level.len() > 1 {
+        let sibling = position ^ 1;
+        // у непарного последнего узла соседа нет — он поднимается без шага
+        if sibling < level.len() {
+            let side = if sibling < position { Side::Left } else { Side::Right };
+            steps.push(ProofStep { hash: hex::encode(level[sibling]), side });
+        }
+        level = next_level(&level);
+        position /= 2;
+    }
+    Some(steps)
+}
+
+/// 🔍 Пройти доказательство от листа и сравнить результат с `root` (hex)
+pub fn verify(leaf: Hash, steps: &[ProofStep], root: &str) -> bool {
+    let mut acc = leaf;
+    for step in steps {
+        let Some(sibling) = hex::decode(&step.hash).ok().and_then(|b| Hash::try_from(b).ok()) else {
+            return false;
+        };
+        acc = match step.side {
+            Side::Left => node_hash(&sibling, &acc),
+            Side::Right => node_hash(&acc, &sibling),
+        };
+    }
+    hex::encode(acc) == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: usize) -> Vec<Hash> {
+        (0..count).map(|i| leaf_hash(format!("item {}", i).as_bytes())).collect()
+    }
+
+    #[test]
+    fn every_leaf_proves_against_the_root() {
+        // нечётные размеры проверяют подъём непарного узла на каждом уровне
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let root = root_hex(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let steps = prove(&leaves, index).unwrap();
+                assert!(verify(*leaf, &steps, &root), "лист {} из {}", index, count);
+            }
+            assert!(prove(&leaves, count).is_none());
+        }
+    }
+
+    #[test]
+    fn single_leaf_is_the_root() {
+        let leaves = leaves(1);
+        assert_eq!(root(&leaves), leaves[0]);
+        assert!(prove(&leaves, 0).unwrap().is_empty());
+        assert_eq!(root(&[]), empty_root());
+    }
+
+    #[test]
+    fn odd_leaf_is_lifted_not_duplicated() {
+        let l = leaves(3);
+        assert_eq!(root(&l), node_hash(&node_hash(&l[0], &l[1]), &l[2]));
+        assert_ne!(root(&l), root(&[l[0], l[1], l[2], l[2]]));
+        // последний лист поднимается без шага на первом уровне
+        assert_eq!(prove(&l, 2).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn leaves_and_inner_nodes_are_separated() {
+        let l = leaves(2);
+        let mut inner = vec![NODE_PREFIX];
+        inner.extend_from_slice(&l[0]);
+        inner.extend_from_slice(&l[1]);
+        assert_eq!(root(&l), <Hash>::from(Sha256::digest(&inner)));
+
+        // те же 64 байта как данные листа дают другой хэш: поддерево не выдать за лист
+        let forged = leaf_hash(&inner[1..]);
+        assert_ne!(forged, root(&l));
+        assert_eq!(leaf_hash(b"x"), <Hash>::from(Sha256::digest([LEAF_PREFIX, b'x'])));
+
+        // внутренний узел не проходит проверку как лист дерева на уровень выше
+        let four = leaves(4);
+        let subtree = node_hash(&four[0], &four[1]);
+        let steps = vec![ProofStep { hash: hex::encode(node_hash(&four[2], &four[3])), side: Side::Right }];
+        assert!(verify(subtree, &steps, &root_hex(&four)));
+        assert!(!verify(leaf_hash(&[&four[0][..], &four[1][..]].concat()), &steps, &root_hex(&four)));
+    }
+
+    #[test]
+    fn tampered_path_is_rejected() {
+        let leaves = leaves(5);
+        let root = root_hex(&leaves);
+        let steps = prove(&leaves, 1).unwrap();
+        assert!(verify(leaves[1], &steps, &root));
+
+        for i in 0..steps.len() {
+            let mut sibling = steps.clone();
+            let mut bytes = hex::decode(&sibling[i].hash).unwrap();
+            bytes[0] ^= 1;
+            sibling[i].hash = hex::encode(bytes);
+            assert!(!verify(leaves[1], &sibling, &root), "сосед {}", i);
+
+            let mut side = steps.clone();
+            side[i].side = if side[i].side == Side::Left { Side::Right } else { Side::Left };
+            assert!(!verify(leaves[1], &side, &root), "сторона {}", i);
+        }
+
+        let mut garbage = steps.clone();
+        garbage[0].hash = "не hex".into();
+        assert!(!verify(leaves[1], &garbage, &root));
+        assert!(!verify(leaves[2], &steps, &root));
+        assert!(!verify(leaves[1], &steps[1..], &root));
+    }
+}
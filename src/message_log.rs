@@ -0,0 +1,439 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::clock::SimClock;
+use crate::config::MessagesConfig;
+use crate::interaction::{Message, MessageType};
+
+/// Больше записей за одну страницу `/messages` не отдаётся
+pub const MAX_PAGE: usize = 1000;
+
+/// Строка журнала сообщений: кто, кому, что и когда сказал
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MessageRecord {
+    pub seq: u64,
+    pub tick: u64,
+    /// время симуляции (unix-секунды, `SimClock::now`)
+    pub time: i64,
+    /// номер сообщения на шине отправителя (0 — не выдан)
+    pub id: u64,
+    pub from: String,
+    /// `None` — широковещательное
+    pub to: Option<String>,
+    pub msg_type: MessageType,
+    pub value: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<u64>,
+}
+
+/// Фильтр `/messages`: все условия должны выполняться
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct MessageQuery {
+    /// нода — отправитель или получатель
+    pub node: Option<String>,
+    /// тип сообщения: `HelpRequest`, `EnergyTransfer`, ...
+    #[serde(rename = "type")]
+    pub msg_type: Option<String>,
+    /// окно по тикам, границы включительно
+    pub from_tick: Option<u64>,
+    pub to_tick: Option<u64>,
+    /// окно по времени симуляции (unix-секунды), границы включительно
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub offset: usize,
+    /// по умолчанию 100, не больше `MAX_PAGE`
+    pub limit: Option<usize>,
+}
+
+/// Страница ответа `/messages`
+#[derive(Clone, Debug, Serialize)]
+pub struct MessagePage {
+    /// сколько записей подошло под фильтр
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    /// `offset` следующей страницы, если она есть
+    pub next_offset: Option<usize>,
+    /// подошедшие записи по отправителям — видно, кто шлёт больше всех
+    pub by_sender: BTreeMap<String, usize>,
+    pub messages: Vec<MessageRecord>,
+}
+
+/// 📨 Журнал сообщений шины: каждое доставляемое сообщение — строка JSON Lines
+/// в файле `messages.path`. Хранится не больше `messages.capacity` последних
+/// записей: они же лежат в памяти для запросов `/messages`, а файл
+/// переписывается, когда в нём набирается вдвое больше строк.
+/// Клонирование дешёвое; выключенный журнал ничего не пишет.
+#[derive(Clone, Default)]
+pub struct MessageLog {
+    inner: Option<Arc<Mutex<Inner>>>,
+}
+
+struct Inner {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    clock: SimClock,
+    capacity: usize,
+    seq: u64,
+    records: VecDeque<MessageRecord>,
+    /// строк в файле с последнего сжатия
+    lines: usize,
+}
+
+impl fmt::Debug for MessageLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.inner {
+            Some(inner) => match inner.lock() {
+                Ok(inner) => write!(f, "MessageLog({})", inner.path.display()),
+                Err(_) => write!(f, "MessageLog(poisoned)"),
+            },
+            None => write!(f, "MessageLog(off)"),
+        }
+    }
+}
+
+impl MessageLog {
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Открыть журнал из `[messages]`; при `enabled = false` — выключенный.
+    /// `resume = None` начинает файл заново. `Some(seq)` — продолжение с
+    /// контрольной точки: из файла читаются последние записи до `seq`
+    /// включительно, более поздние (брошенный прогон) отрезаются,
+    /// нумерация продолжается с `seq`.
+    pub fn open(config: &MessagesConfig, clock: SimClock, resume: Option<u64>) -> io::Result<Self> {
+        if !config.enabled {
+            return Ok(Self::disabled());
+        }
+        let path = config.path.clone();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut records = VecDeque::new();
+        if let Some(seq) = resume {
+            for line in fs::read_to_string(&path).unwrap_or_default().lines() {
+                // оборванная последняя строка (процесс убит посреди записи) пропускается
+                match serde_json::from_str::<MessageRecord>(line) {
+                    Ok(record) if record.seq <= seq => {
+                        if records.len() == config.capacity {
+                            records.pop_front();
+                        }
+                        records.push_back(record);
+                    }
+                    _ => break,
+                }
+            }
+            rewrite(&path, records.iter())?;
+        }
+        let lines = records.len();
+        let seq = resume.unwrap_or(0);
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resume.is_some())
+            .truncate(resume.is_none())
+            .open(&path)?;
+
+        Ok(Self {
+            inner: Some(Arc::new(Mutex::new(Inner {
+                path,
+                writer: Some(BufWriter::new(file)),
+                clock,
+                capacity: config.capacity,
+                seq,
+                records,
+                lines,
+            }))),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Номер последней записанной записи
+    pub fn seq(&self) -> u64 {
+        self.inner.as_ref().and_then(|inner| inner.lock().ok().map(|inner| inner.seq)).unwrap_or(0)
+    }
+
+    /// Записать сообщение с текущим тиком
+    pub fn record(&self, msg: &Message) {
+        let Some(inner) = &self.inner else {
+            return;
+        };
+        let Ok(mut inner) = inner.lock() else {
+            return;
+        };
+        inner.seq += 1;
+        let record = MessageRecord {
+            seq: inner.seq,
+            tick: inner.clock.tick(),
+            time: inner.clock.now(),
+            id: msg.id,
+            from: msg.from.clone(),
+            to: msg.to.clone(),
+            msg_type: msg.msg_type.clone(),
+            value: msg.value,
+            reply_to: msg.reply_to,
+        };
+        inner.write(&record);
+        if inner.records.len() == inner.capacity {
+            inner.records.pop_front();
+        }
+        inner.records.push_back(record);
+        if inner.lines >= inner.capacity * 2 {
+            inner.compact();
+        }
+    }
+
+    /// Сбросить буфер на диск (планировщик вызывает в конце каждого тика)
+    pub fn flush(&self) {
+        if let Some(inner) = &self.inner {
+            if let Ok(mut inner) = inner.lock() {
+                if let Some(writer) = inner.writer.as_mut() {
+                    if let Err(e) = writer.flush() {
+                        eprintln!("⚠️ Не удалось сбросить журнал сообщений: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 🔎 Записи, подходящие под фильтр, по странице. Неизвестный тип — ошибка.
+    pub fn query(&self, query: &MessageQuery) -> Result<MessagePage, String> {
+        let msg_type = match &query.msg_type {
+            Some(name) => Some(
+                serde_json::from_value::<MessageType>(serde_json::Value::String(name.clone()))
+                    .map_err(|_| format!("неизвестный тип сообщения '{}'", name))?,
+            ),
+            None => None,
+        };
+        let limit = query.limit.unwrap_or(100).min(MAX_PAGE);
+
+        let Some(inner) = &self.inner else {
+            return Err("журнал сообщений выключен (messages.enabled = false)".into());
+        };
+        let inner = inner.lock().map_err(|_| "журнал сообщений недоступен".to_string())?;
+
+        let mut total = 0;
+        let mut by_sender = BTreeMap::new();
+        let mut messages = Vec::new();
+        for record in inner.records.iter() {
+            let matches = query.node.as_ref().is_none_or(|n| record.from == *n || record.to.as_ref() == Some(n))
+                && msg_type.as_ref().is_none_or(|t| record.msg_type == *t)
+                && query.from_tick.is_none_or(|t| record.tick >= t)
+                && query.to_tick.is_none_or(|t| record.tick <= t)
+                && query.since.is_none_or(|t| record.time >= t)
+                && query.until.is_none_or(|t| record.time <= t);
+            if !matches {
+                continue;
+            }
+            if total >= query.offset && messages.len() < limit {
+                messages.push(record.clone());
+            }
+            total += 1;
+            *by_sender.entry(record.from.clone()).or_insert(0) += 1;
+        }
+
+        let next = query.offset + messages.len();
+        Ok(MessagePage {
+            total,
+            offset: query.offset,
+            limit,
+            next_offset: (limit > 0 && next < total).then_some(next),
+            by_sender,
+            messages,
+        })
+    }
+}
+
+impl Inner {
+    fn write(&mut self, record: &MessageRecord) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+        let written = serde_json::to_writer(&mut *writer, record)
+            .map_err(io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"));
+        match written {
+            Ok(()) => self.lines += 1,
+            Err(e) => {
+                // запросы продолжают работать по записям в памяти
+                eprintln!("❌ Журнал сообщений {} больше не пишется: {}", self.path.display(), e);
+                self.writer = None;
+            }
+        }
+    }
+
+    /// ✂️ Переписать файл последними `capacity` записями: сначала во временный
+    /// файл, затем переименовать — оборванное сжатие не теряет журнал
+    fn compact(&mut self) {
+        let Some(mut writer) = self.writer.take() else {
+            return;
+        };
+        if let Err(e) = writer.flush() {
+            eprintln!("⚠️ Не удалось сбросить журнал сообщений: {}", e);
+        }
+        drop(writer);
+
+        match rewrite(&self.path, self.records.iter()) {
+            Ok(()) => self.lines = self.records.len(),
+            Err(e) => eprintln!("⚠️ Журнал сообщений {} не сжат: {}", self.path.display(), e),
+        }
+        match OpenOptions::new().append(true).open(&self.path) {
+            Ok(file) => self.writer = Some(BufWriter::new(file)),
+            Err(e) => eprintln!("❌ Журнал сообщений {} больше не пишется: {}", self.path.display(), e),
+        }
+    }
+}
+
+fn rewrite<'a>(path: &Path, records: impl Iterator<Item = &'a MessageRecord>) -> io::Result<()> {
+    let tmp = path.with_extension("jsonl.tmp");
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    for record in records {
+        serde_json::to_writer(&mut writer, record)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resume_drops_the_abandoned_run() {
+        let dir = std::env::temp_dir().join(format!("organism-messages-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let config = MessagesConfig { enabled: true, path: dir.join("messages.jsonl"), capacity: 4 };
+        let clock = SimClock::new(0, 100);
+        let message = |value: f64| Message::new("node0", None, MessageType::HelpRequest, value, None);
+
+        let log = MessageLog::open(&config, clock.clone(), None).unwrap();
+        for tick in 1..=6 {
+            clock.set_tick(tick);
+            log.record(&message(tick as f64));
+        }
+        log.flush();
+        drop(log);
+
+        // контрольная точка снята после пятой записи; в памяти — последние `capacity`
+        clock.set_tick(5);
+        let log = MessageLog::open(&config, clock.clone(), Some(5)).unwrap();
+        assert_eq!(log.seq(), 5);
+        log.record(&message(60.0));
+        log.flush();
+
+        let page = log.query(&MessageQuery::default()).unwrap();
+        assert_eq!(page.messages.iter().map(|r| r.seq).collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+        assert_eq!(page.messages[3].value, 60.0);
+        let text = fs::read_to_string(&config.path).unwrap();
+        let seqs: Vec<u64> = text.lines().map(|l| serde_json::from_str::<MessageRecord>(l).unwrap().seq).collect();
+        assert_eq!(seqs, vec![2, 3, 4, 5, 6]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// 12 сообщений по одному на тик: нечётные — широковещательные `HelpRequest`,
+    /// чётные — адресные `EnergyTransfer`; время тика — `1000 + 2·tick`
+    fn twelve(name: &str) -> (MessageLog, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("organism-messages-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let config = MessagesConfig { enabled: true, path: dir.join("messages.jsonl"), capacity: 100 };
+        let clock = SimClock::new(1000, 2000);
+        let log = MessageLog::open(&config, clock.clone(), None).unwrap();
+        for tick in 1..=12u64 {
+            clock.set_tick(tick);
+            let from = format!("node{}", tick % 3);
+            let msg = if tick % 2 == 1 {
+                Message::new(&from, None, MessageType::HelpRequest, tick as f64, None)
+            } else {
+                Message::new_energy_transfer(&from, &format!("node{}", (tick + 1) % 3), tick as f64)
+            };
+            log.record(&msg);
+        }
+        (log, dir)
+    }
+
+    fn ticks(page: &MessagePage) -> Vec<u64> {
+        page.messages.iter().map(|r| r.tick).collect()
+    }
+
+    #[test]
+    fn query_filters_by_node_type_and_window() {
+        let (log, dir) = twelve("filters");
+        let query = |q: MessageQuery| log.query(&q).unwrap();
+
+        // нода — и отправитель, и адресат; чужие широковещательные не в счёт
+        let page = query(MessageQuery { node: Some("node0".into()), ..MessageQuery::default() });
+        assert_eq!(ticks(&page), vec![2, 3, 6, 8, 9, 12]);
+        assert_eq!(page.by_sender, BTreeMap::from([("node0".to_string(), 4), ("node2".to_string(), 2)]));
+
+        let page = query(MessageQuery { msg_type: Some("EnergyTransfer".into()), ..MessageQuery::default() });
+        assert_eq!(ticks(&page), vec![2, 4, 6, 8, 10, 12]);
+        let page = query(MessageQuery {
+            node: Some("node0".into()),
+            msg_type: Some("EnergyTransfer".into()),
+            ..MessageQuery::default()
+        });
+        assert_eq!(ticks(&page), vec![2, 6, 8, 12]);
+
+        // окна по тикам и по времени, границы включительно
+        let by_tick = query(MessageQuery { from_tick: Some(4), to_tick: Some(7), ..MessageQuery::default() });
+        assert_eq!(ticks(&by_tick), vec![4, 5, 6, 7]);
+        let by_time = query(MessageQuery { since: Some(1008), until: Some(1014), ..MessageQuery::default() });
+        assert_eq!(ticks(&by_time), vec![4, 5, 6, 7]);
+        assert_eq!(by_time.messages[0].time, 1008);
+        let page = query(MessageQuery {
+            msg_type: Some("HelpRequest".into()),
+            from_tick: Some(4),
+            until: Some(1014),
+            ..MessageQuery::default()
+        });
+        assert_eq!(ticks(&page), vec![5, 7]);
+        assert_eq!(query(MessageQuery { from_tick: Some(13), ..MessageQuery::default() }).total, 0);
+
+        assert!(log.query(&MessageQuery { msg_type: Some("Gossip".into()), ..MessageQuery::default() }).is_err());
+        assert!(MessageLog::disabled().query(&MessageQuery::default()).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn query_pages_through_matches() {
+        let (log, dir) = twelve("pages");
+        let page = |offset: usize, limit: Option<usize>| log.query(&MessageQuery { offset, limit, ..MessageQuery::default() }).unwrap();
+
+        let mut seen = Vec::new();
+        let mut offset = Some(0);
+        while let Some(from) = offset {
+            let current = page(from, Some(5));
+            assert_eq!((current.total, current.offset, current.limit), (12, from, 5));
+            // сводка по отправителям — по всем подошедшим, а не по странице
+            assert_eq!(current.by_sender.values().sum::<usize>(), 12);
+            seen.extend(ticks(&current));
+            offset = current.next_offset;
+        }
+        assert_eq!(seen, (1..=12).collect::<Vec<_>>());
+
+        assert_eq!(page(5, Some(5)).next_offset, Some(10));
+        assert_eq!(page(10, Some(5)).next_offset, None);
+        assert_eq!(ticks(&page(10, Some(5))), vec![11, 12]);
+        let past = page(20, Some(5));
+        assert!(past.messages.is_empty() && past.next_offset.is_none());
+        let empty = page(0, Some(0));
+        assert!(empty.messages.is_empty() && empty.next_offset.is_none());
+        assert_eq!((page(0, None).limit, page(0, None).messages.len()), (100, 12));
+        assert_eq!(page(0, Some(MAX_PAGE * 10)).limit, MAX_PAGE);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,468 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use crate::config::{LinkRule, NetsimConfig};
+use crate::events::{Event, EventLog, NetFault};
+use crate::interaction::Message;
+use crate::rng::{Seed, SimRng};
+
+/// Сообщение в пути: будет доставлено ноде `to` в первой фазе доставки с тиком `due` или позже
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InFlight {
+    pub due: u64,
+    /// порядок постановки — при одинаковом `due` сообщения приходят в нём
+    pub seq: u64,
+    pub to: String,
+    pub message: Message,
+}
+
+/// Состояние симулятора для контрольной точки
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NetsimState {
+    pub rng: SimRng,
+    pub seq: u64,
+    pub in_flight: Vec<InFlight>,
+    /// разделения, уже объявленные событием `PartitionStarted` и ещё не залеченные
+    pub active: BTreeSet<usize>,
+}
+
+/// Параметры одной связи после применения правил `netsim.links`
+#[derive(Clone, Copy, Debug)]
+struct LinkParams {
+    latency_ticks: u64,
+    jitter_ticks: u64,
+    loss: f64,
+    duplicate: f64,
+    reorder: f64,
+}
+
+/// Правило связи с раскрытыми шаблонами
+struct Link {
+    from: Vec<String>,
+    to: Vec<String>,
+    rule: LinkRule,
+}
+
+/// Разделение с раскрытыми группами
+struct Partition {
+    at: u64,
+    heal: Option<u64>,
+    groups: Vec<Vec<String>>,
+}
+
+impl Partition {
+    fn active(&self, tick: u64) -> bool {
+        tick >= self.at && self.heal.is_none_or(|heal| tick < heal)
+    }
+
+    fn group_of(&self, node: &str) -> Option<usize> {
+        self.groups.iter().position(|group| matches_any(group, node))
+    }
+
+    /// Обе ноды в группах, и группы разные
+    fn separates(&self, a: &str, b: &str) -> bool {
+        matches!((self.group_of(a), self.group_of(b)), (Some(x), Some(y)) if x != y)
+    }
+}
+
+/// 📡 Симулятор сети между нодами: задержка, джиттер, потери, дубли,
+/// перестановки и разделения по расписанию. Стоит между шиной и фазой
+/// доставки; случайность — из отдельного потока `netsim`, поэтому прогон
+/// с тем же seed повторяется. Каждый внесённый сбой пишется в журнал.
+/// Клонирование дешёвое; выключенный симулятор ничего не делает.
+#[derive(Clone, Default)]
+pub struct NetSim {
+    inner: Option<Arc<Inner>>,
+}
+
+struct Inner {
+    defaults: LinkParams,
+    links: Vec<Link>,
+    partitions: Vec<Partition>,
+    log: EventLog,
+    state: Mutex<NetsimState>,
+}
+
+impl NetSim {
+    /// Симулятор выключен: сообщения доставляются сразу
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Шаблоны нод уже проверены `Config::validate`
+    pub fn new(config: &NetsimConfig, seed: Seed, log: EventLog) -> Self {
+        if !config.enabled {
+            return Self::disabled();
+        }
+        let expand_all = |patterns: &[String]| -> Vec<String> {
+            patterns.iter().flat_map(|p| expand(p).unwrap_or_default()).collect()
+        };
+        let links = config
+            .links
+            .iter()
+            .map(|rule| Link {
+                from: expand_all(std::slice::from_ref(&rule.from)),
+                to: expand_all(std::slice::from_ref(&rule.to)),
+                rule: rule.clone(),
+            })
+            .collect();
+        let partitions = config
+            .partitions
+            .iter()
+            .map(|p| Partition {
+                at: p.at,
+                heal: p.heal,
+                groups: p.groups.iter().map(|group| expand_all(group)).collect(),
+            })
+            .collect();
+        Self {
+            inner: Some(Arc::new(Inner {
+                defaults: LinkParams {
+                    latency_ticks: config.latency_ticks,
+                    jitter_ticks: config.jitter_ticks,
+                    loss: config.loss,
+                    duplicate: config.duplicate,
+                    reorder: config.reorder,
+                },
+                links,
+                partitions,
+                log,
+                state: Mutex::new(NetsimState {
+                    rng: seed.stream("netsim"),
+                    seq: 0,
+                    in_flight: Vec::new(),
+                    active: BTreeSet::new(),
+                }),
+            })),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Состояние для контрольной точки (`None` — симулятор выключен)
+    pub fn state(&self) -> Option<NetsimState> {
+        let inner = self.inner.as_ref()?;
+        inner.state.lock().ok().map(|state| state.clone())
+    }
+
+    /// Продолжить с состояния из контрольной точки
+    pub fn restore(&self, saved: NetsimState) {
+        if let Some(inner) = &self.inner {
+            if let Ok(mut state) = inner.state.lock() {
+                *state = saved;
+            }
+        }
+    }
+
+    /// 📤 Отправить сообщение ноде `to` на тике `tick`: оно теряется, ставится
+    /// в очередь с задержкой, иногда придерживается или дублируется
+    pub fn send(&self, message: Message, to: &str, tick: u64) {
+        let Some(inner) = &self.inner else {
+            return;
+        };
+        let Ok(mut state) = inner.state.lock() else {
+            return;
+        };
+        inner.advance(&mut state, tick);
+
+        if inner.partitioned(&message.from, to, tick) {
+            inner.fault(NetFault::Partitioned, &message, to, None);
+            return;
+        }
+        let link = inner.params(&message.from, to, tick);
+        if chance(&mut state.rng, link.loss) {
+            inner.fault(NetFault::Lost, &message, to, None);
+            return;
+        }
+
+        let mut due = tick + delay(&mut state.rng, &link);
+        if chance(&mut state.rng, link.reorder) {
+            // придерживаем дольше самой большой обычной задержки — следующие сообщения обгонят
+            due += link.latency_ticks + link.jitter_ticks + 1;
+            inner.fault(NetFault::Reordered, &message, to, Some(due));
+        }
+        if chance(&mut state.rng, link.duplicate) {
+            let copy_due = tick + delay(&mut state.rng, &link);
+            inner.fault(NetFault::Duplicated, &message, to, Some(copy_due));
+            state.push(copy_due, to, message.clone());
+        }
+        state.push(due, to, message);
+    }
+
+    /// 📥 Сообщения, которым пора прийти к тику `tick`, — в порядке доставки.
+    /// Попавшие в действующее разделение по дороге теряются.
+    pub fn due(&self, tick: u64) -> Vec<(Message, String)> {
+        let Some(inner) = &self.inner else {
+            return Vec::new();
+        };
+        let Ok(mut state) = inner.state.lock() else {
+            return Vec::new();
+        };
+        inner.advance(&mut state, tick);
+
+        let (mut ready, waiting): (Vec<InFlight>, Vec<InFlight>) =
+            std::mem::take(&mut state.in_flight).into_iter().partition(|m| m.due <= tick);
+        state.in_flight = waiting;
+        ready.sort_by_key(|m| (m.due, m.seq));
+
+        let mut delivered = Vec::with_capacity(ready.len());
+        for m in ready {
+            if inner.partitioned(&m.message.from, &m.to, tick) {
+                inner.fault(NetFault::Partitioned, &m.message, &m.to, None);
+                continue;
+            }
+            delivered.push((m.message, m.to));
+        }
+        delivered
+    }
+
+    /// Сообщение не доставлено: получателя больше нет
+    pub fn recipient_gone(&self, message: &Message, to: &str) {
+        if let Some(inner) = &self.inner {
+            inner.fault(NetFault::RecipientGone, message, to, None);
+        }
+    }
+}
+
+impl NetsimState {
+    fn push(&mut self, due: u64, to: &str, message: Message) {
+        self.seq += 1;
+        self.in_flight.push(InFlight { due, seq: self.seq, to: to.to_string(), message });
+    }
+}
+
+impl Inner {
+    /// ✂️ Объявить начавшиеся и залеченные к тику `tick` разделения
+    fn advance(&self, state: &mut NetsimState, tick: u64) {
+        for (index, partition) in self.partitions.iter().enumerate() {
+            let active = partition.active(tick);
+            if active && state.active.insert(index) {
+                println!("✂️ [netsim] Разделение #{} с тика {}: {:?}", index, partition.at, partition.groups);
+                self.log.emit(Event::PartitionStarted { index, groups: partition.groups.clone() });
+            } else if !active && state.active.remove(&index) {
+                println!("🩹 [netsim] Разделение #{} залечено на тике {}", index, tick);
+                self.log.emit(Event::PartitionHealed { index });
+            }
+        }
+    }
+
+    fn partitioned(&self, from: &str, to: &str, tick: u64) -> bool {
+        self.partitions.iter().any(|p| p.active(tick) && p.separates(from, to))
+    }
+
+    fn params(&self, from: &str, to: &str, tick: u64) -> LinkParams {
+        let mut params = self.defaults;
+        for link in self.links.iter() {
+            let rule = &link.rule;
+            let active = tick >= rule.at && rule.until.is_none_or(|until| tick < until);
+            if !active || !matches_any(&link.from, from) || !matches_any(&link.to, to) {
+                continue;
+            }
+            params.latency_ticks = rule.latency_ticks.unwrap_or(params.latency_ticks);
+            params.jitter_ticks = rule.jitter_ticks.unwrap_or(params.jitter_ticks);
+            params.loss = rule.loss.unwrap_or(params.loss);
+            params.duplicate = rule.duplicate.unwrap_or(params.duplicate);
+            params.reorder = rule.reorder.unwrap_or(params.reorder);
+        }
+        params
+    }
+
+    fn fault(&self, fault: NetFault, message: &Message, to: &str, due: Option<u64>) {
+        self.log.emit(Event::NetFault {
+            fault,
+            from: message.from.clone(),
+            to: to.to_string(),
+            message: format!("{:?}", message.msg_type),
+            due,
+        });
+    }
+}
+
+fn chance(rng: &mut SimRng, p: f64) -> bool {
+    p > 0.0 && rng.gen_bool(p)
+}
+
+fn delay(rng: &mut SimRng, link: &LinkParams) -> u64 {
+    link.latency_ticks + rng.gen_range(0..=link.jitter_ticks)
+}
+
+/// Нода `name` подходит под раскрытый шаблон: имя совпадает, это её потомок
+/// (`node3_child_0` для `node3`) или шаблон — `*`
+fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|p| {
+        p == "*" || name == p || name.strip_prefix(p.as_str()).is_some_and(|rest| rest.starts_with('_'))
+    })
+}
+
+/// 🔤 Раскрыть шаблон нод: `node3` — одна нода, `node0..4` — ноды с `node0`
+/// по `node4` включительно, `*` — любая нода
+pub fn expand(pattern: &str) -> Result<Vec<String>, String> {
+    if pattern.is_empty() {
+        return Err("пустой шаблон ноды".into());
+    }
+    let Some((first, last)) = pattern.split_once("..") else {
+        return Ok(vec![pattern.to_string()]);
+    };
+    let digits = first.len() - first.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (prefix, start) = first.split_at(first.len() - digits);
+    let start: u64 = start
+        .parse()
+        .map_err(|_| format!("шаблон '{}': ожидается вида node0..4", pattern))?;
+    let end: u64 = last
+        .strip_prefix(prefix)
+        .unwrap_or(last)
+        .parse()
+        .map_err(|_| format!("шаблон '{}': ожидается вида node0..4", pattern))?;
+    if end < start {
+        return Err(format!("шаблон '{}': конец диапазона меньше начала", pattern));
+    }
+    Ok((start..=end).map(|i| format!("{}{}", prefix, i)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimClock;
+    use crate::config::PartitionRule;
+    use crate::events::LogRecord;
+    use crate::interaction::MessageType;
+    use std::path::{Path, PathBuf};
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("organism-netsim-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("events.jsonl")
+    }
+
+    fn netsim(config: NetsimConfig, seed: u64, path: &Path) -> NetSim {
+        let log = EventLog::open(path, SimClock::new(0, 100), None).unwrap();
+        NetSim::new(&NetsimConfig { enabled: true, ..config }, Seed::new(seed), log)
+    }
+
+    fn events(path: &Path) -> Vec<Event> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<LogRecord>(line).unwrap().event)
+            .collect()
+    }
+
+    fn faults(events: &[Event], kind: NetFault) -> usize {
+        events.iter().filter(|e| matches!(e, Event::NetFault { fault, .. } if *fault == kind)).count()
+    }
+
+    fn message(from: &str, n: u64) -> Message {
+        Message::new(from, None, MessageType::HelpRequest, n as f64, None)
+    }
+
+    #[test]
+    fn patterns_expand_to_node_names() {
+        assert_eq!(expand("node3").unwrap(), vec!["node3"]);
+        assert_eq!(expand("node0..2").unwrap(), vec!["node0", "node1", "node2"]);
+        assert_eq!(expand("node1..node2").unwrap(), vec!["node1", "node2"]);
+        assert_eq!(expand("b_node7..7").unwrap(), vec!["b_node7"]);
+        assert_eq!(expand("*").unwrap(), vec!["*"]);
+        for bad in ["", "node3..1", "node..2", "node0..x"] {
+            assert!(expand(bad).is_err(), "'{}'", bad);
+        }
+
+        // шаблон задевает потомков ноды, но не ноды с похожим именем
+        let patterns = expand("node3").unwrap();
+        assert!(matches_any(&patterns, "node3_child_0"));
+        assert!(!matches_any(&patterns, "node30"));
+        assert!(matches_any(&["*".to_string()], "anyone"));
+    }
+
+    #[test]
+    fn partition_starts_drops_across_groups_and_heals() {
+        let path = scratch("partition");
+        let config = NetsimConfig {
+            latency_ticks: 1,
+            partitions: vec![PartitionRule {
+                at: 5,
+                heal: Some(10),
+                groups: vec![vec!["node0".into()], vec!["node1..2".into()]],
+            }],
+            ..NetsimConfig::default()
+        };
+        let sim = netsim(config, 1, &path);
+
+        sim.send(message("node0", 1), "node1", 3);
+        assert_eq!(sim.due(3).len(), 0);
+        assert_eq!(sim.due(4).len(), 1);
+
+        // в пути к началу разделения — теряется при доставке
+        sim.send(message("node0", 2), "node1", 4);
+        sim.send(message("node1", 3), "node2", 5);
+        sim.send(message("node0", 4), "node1", 6);
+        // нода вне групп связь не теряет
+        sim.send(message("node0", 5), "node3", 6);
+        let delivered: Vec<f64> = sim.due(7).into_iter().map(|(m, _)| m.value).collect();
+        assert_eq!(delivered, vec![3.0, 5.0]);
+
+        sim.send(message("node0", 6), "node1", 10);
+        assert_eq!(sim.due(11).len(), 1);
+        assert!(sim.state().unwrap().active.is_empty());
+
+        drop(sim);
+        let events = events(&path);
+        assert_eq!(faults(&events, NetFault::Partitioned), 2);
+        assert!(matches!(&events[0], Event::PartitionStarted { index: 0, groups } if groups[1] == ["node1", "node2"]));
+        assert!(matches!(events.last(), Some(Event::PartitionHealed { index: 0 })));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    /// Что пришло (отправитель, номер) за 30 тиков после отправки 200 сообщений
+    fn faulty_run(seed: u64, path: &Path) -> Vec<(String, u64)> {
+        let config = NetsimConfig {
+            latency_ticks: 1,
+            jitter_ticks: 2,
+            loss: 0.2,
+            duplicate: 0.2,
+            reorder: 0.2,
+            ..NetsimConfig::default()
+        };
+        let sim = netsim(config, seed, path);
+        let mut delivered = Vec::new();
+        for tick in 0..30 {
+            if tick < 10 {
+                for n in 0..20 {
+                    sim.send(message("node0", tick * 20 + n), "node1", tick);
+                }
+            }
+            delivered.extend(sim.due(tick).into_iter().map(|(m, _)| (m.from, m.value as u64)));
+        }
+        assert!(sim.state().unwrap().in_flight.is_empty());
+        delivered
+    }
+
+    #[test]
+    fn faults_repeat_with_the_same_seed_and_are_logged() {
+        let paths = [scratch("seed-a"), scratch("seed-b"), scratch("seed-c")];
+        let first = faulty_run(7, &paths[0]);
+        assert_eq!(first, faulty_run(7, &paths[1]));
+        assert_ne!(first, faulty_run(8, &paths[2]));
+
+        let events = events(&paths[0]);
+        assert_eq!(events.len(), std::fs::read_to_string(&paths[1]).unwrap().lines().count());
+        let (lost, duplicated, reordered) = (
+            faults(&events, NetFault::Lost),
+            faults(&events, NetFault::Duplicated),
+            faults(&events, NetFault::Reordered),
+        );
+        assert!(lost > 0 && duplicated > 0 && reordered > 0, "{} {} {}", lost, duplicated, reordered);
+        // каждое сообщение либо потеряно, либо пришло — и ещё раз на каждый дубль
+        assert_eq!(first.len(), 200 - lost + duplicated);
+
+        // придержанные сообщения обгоняются отправленными позже
+        let numbers: Vec<u64> = first.iter().map(|(_, n)| *n).collect();
+        assert!(numbers.windows(2).any(|w| w[0] > w[1]));
+        for path in paths.iter() {
+            let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        }
+    }
+}
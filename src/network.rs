@@ -1,106 +1,914 @@
-use crate::node::Node;
+use crate::chain::{Block, Chain};
+use crate::config::NetworkConfig;
+use crate::events::ChainKind;
+use crate::identity::{Identity, KeyRegistry};
+use crate::interaction::{Message, MessageType};
+use crate::neuron::Neuron;
+use crate::peers::{self, PeerInfo, PeerSource, PeerTable, GOSSIP_LIMIT};
+use crate::wire::{self, Envelope, Payload, WireError, MAX_HELLO_FRAME, MAX_SYNC_BLOCKS, PROTOCOL_VERSION};
+use crate::world::World;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use serde_json::{Value, json};
-use std::sync::Arc;
-use tokio::sync::Mutex;  
-use tokio::time::{sleep, Duration};
+use tokio::sync::{mpsc, Notify};
+use tokio::time::{interval, sleep, timeout, Duration};
 
-#[derive(Clone)]
+/// Сколько кадров ждёт отправки в одно соединение; лишние отбрасываются
+const OUTBOUND_QUEUE: usize = 256;
+
+/// Пир, молчащий дольше стольких интервалов keepalive, считается потерянным
+const SILENT_INTERVALS: u32 = 3;
+
+/// Ветка `key_chain`, собираемая из страниц `BlocksResponse`, не длиннее этого
+const MAX_BRANCH: usize = 64 * MAX_SYNC_BLOCKS;
+
+/// Что известно о ноде другого процесса
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RemoteNode {
+    /// нода-шлюз, подписавшая последний кадр о ноде
+    pub via: String,
+    /// нейроны из последней синхронизации
+    pub neurons: Vec<Neuron>,
+    /// наш тик, на котором нода последний раз упоминалась
+    pub last_seen_tick: u64,
+}
+
+/// Открытое соединение с пиром
+#[derive(Clone, Debug, Serialize)]
+pub struct Session {
+    /// адрес, который пир слушает, — ключ в таблице пиров
+    pub peer: String,
+    /// адрес другого конца сокета
+    pub remote: String,
+    /// мы подключились к пиру (а не он к нам)
+    pub outgoing: bool,
+    /// нода-шлюз пира из его приветствия
+    pub gateway: String,
+    /// открытый ключ шлюза — по нему пира штрафуют и банят
+    pub key: String,
+    pub since_tick: u64,
+}
+
+struct Link {
+    session: Session,
+    sender: mpsc::Sender<Envelope>,
+    /// сигнал закрыть соединение (пир забанен)
+    close: Arc<Notify>,
+}
+
+/// 🌐 Связь процесса с другими процессами организма по TCP.
+/// Каждый процесс держит свои ноды (с префиксом имени из `network.node_prefix`),
+/// а с пирами держит постоянные соединения с кадрами из `wire.rs`: пересылает
+/// сообщения шины своих нод, отдаёт и запрашивает `key_chain`, делится нейронами
+/// и адресами пиров. Кому звонить и кого банить, решает `PeerTable`.
+/// Клонирование дешёвое; выключенная сеть ничего не отправляет.
+#[derive(Clone, Default)]
 pub struct Network {
-    pub port: u16,
-    pub peers: Vec<String>,
-} 
+    inner: Option<Arc<Inner>>,
+}
+
+struct Inner {
+    config: NetworkConfig,
+    prefix: String,
+    remote: Mutex<BTreeMap<String, RemoteNode>>,
+    peers: Mutex<PeerTable>,
+    /// номер соединения → открытое соединение
+    links: Mutex<BTreeMap<u64, Link>>,
+    next_link: AtomicU64,
+    /// кадры, отброшенные из-за переполненной очереди (исходящей или входящей)
+    dropped: AtomicU64,
+    nonce: AtomicU64,
+    /// шлюз пира → ветка его `key_chain`, собранная из страниц: высота начала и блоки
+    branches: Mutex<BTreeMap<String, (u64, Vec<Block>)>>,
+}
 
 impl Network {
-    pub fn new(port: u16, peers: Vec<String>) -> Self {
-        Network { port, peers }
+    pub fn disabled() -> Self {
+        Self::default()
     }
 
-    pub async fn send_to_peer(&self, peer: &str, data: String) {
-        if let Ok(mut stream) = TcpStream::connect(peer).await {
-            if stream.write_all(data.as_bytes()).await.is_ok() {
-                println!("📤 Отправлен блок -> {}", peer);
-            }
+    /// Сеть из `[network]`; при `enabled = false` — выключенная
+    pub fn new(config: &NetworkConfig) -> Self {
+        if !config.enabled {
+            return Self::disabled();
+        }
+        Self {
+            inner: Some(Arc::new(Inner {
+                config: config.clone(),
+                prefix: config.node_prefix(),
+                remote: Mutex::new(BTreeMap::new()),
+                peers: Mutex::new(PeerTable::new(config)),
+                links: Mutex::new(BTreeMap::new()),
+                next_link: AtomicU64::new(0),
+                dropped: AtomicU64::new(0),
+                branches: Mutex::new(BTreeMap::new()),
+                nonce: AtomicU64::new(0),
+            })),
         }
     }
 
-    /// 🔥 Теперь принимаем node как Arc<Mutex<Node>>
-     pub async fn start(&self, node: Arc<Mutex<Node>>) { 
-        let listener = TcpListener::bind(format!("127.0.0.1:{}", self.port))
-            .await
-            .expect("❌ Не удалось запустить TCP сервер");
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
 
-        println!("🌐 Нода слушает на порту {}", self.port);
+    /// Живёт ли нода `name` в этом процессе (без сети — все ноды местные)
+    pub fn is_local(&self, name: &str) -> bool {
+        match &self.inner {
+            Some(inner) => name.starts_with(&inner.prefix),
+            None => true,
+        }
+    }
 
-        // Обработка входящих соединений
-        let node_clone = node.clone();
+    /// Ноды других процессов, о которых сообщали пиры
+    pub fn remote_nodes(&self) -> BTreeMap<String, RemoteNode> {
+        match &self.inner {
+            Some(inner) => inner.remote.lock().unwrap().clone(),
+            None => BTreeMap::new(),
+        }
+    }
+
+    /// Таблица пиров для `GET /peers`
+    pub fn peers(&self) -> Vec<PeerInfo> {
+        match &self.inner {
+            Some(inner) => inner.peers.lock().unwrap().list(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Адреса пиров, с которыми открыто соединение, — это `connections` наших нод
+    pub fn connected_peers(&self) -> Vec<String> {
+        match &self.inner {
+            Some(inner) => inner.peers.lock().unwrap().connected_addrs(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Сводка для `GET /network`
+    pub fn status(&self) -> serde_json::Value {
+        let Some(inner) = &self.inner else {
+            return json!({ "enabled": false });
+        };
+        let remote: BTreeMap<String, serde_json::Value> = self
+            .remote_nodes()
+            .into_iter()
+            .map(|(name, node)| {
+                let summary = json!({
+                    "via": node.via,
+                    "neurons": node.neurons.len(),
+                    "last_seen_tick": node.last_seen_tick,
+                });
+                (name, summary)
+            })
+            .collect();
+        let sessions: Vec<Session> = inner.links.lock().unwrap().values().map(|l| l.session.clone()).collect();
+        json!({
+            "enabled": true,
+            "protocol_version": PROTOCOL_VERSION,
+            "listen": inner.config.listen,
+            "node_prefix": inner.prefix,
+            "seeds": inner.config.peers,
+            "max_peers": inner.config.max_peers,
+            "sessions": sessions,
+            "dropped_frames": inner.dropped.load(Ordering::Relaxed),
+            "remote_nodes": remote,
+        })
+    }
+
+    /// 🚀 Занять `network.listen`, звонить пирам из таблицы и запустить
+    /// периодическую синхронизацию
+    pub async fn start(&self, world: World) -> std::io::Result<()> {
+        let Some(inner) = self.inner.clone() else {
+            return Ok(());
+        };
+        let listener = TcpListener::bind(&inner.config.listen).await?;
+        println!(
+            "🌐 Процесс слушает {} (ноды {}*, протокол v{})",
+            inner.config.listen, inner.prefix, PROTOCOL_VERSION
+        );
+
+        let network = self.clone();
+        let accept_world = world.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Ok((socket, addr)) = listener.accept().await {
+                    let (network, world) = (network.clone(), accept_world.clone());
+                    tokio::spawn(async move { network.accept(socket, addr.to_string(), world).await });
+                }
+            }
+        });
+
+        // === Звонки: свободные места занимаются пирами из таблицы ===
+        let network = self.clone();
+        let dial_world = world.clone();
+        let keepalive = self.keepalive();
         tokio::spawn(async move {
+            let mut ticker = interval(keepalive);
             loop {
-                if let Ok((socket, _)) = listener.accept().await {
-                    let node_for_client = node_clone.clone();
-                    tokio::spawn(async move {
-                        handle_connection(socket, node_for_client).await;
-                    });
+                ticker.tick().await;
+                let candidates = inner.peers.lock().unwrap().dial_candidates();
+                for addr in candidates {
+                    let (network, world) = (network.clone(), dial_world.clone());
+                    tokio::spawn(async move { network.dial(addr, world).await });
+                }
+            }
+        });
+
+        // === Поток синхронизации: нейроны, запрос key_chain и обмен адресами ===
+        let network = self.clone();
+        let sync = Duration::from_secs(self.inner.as_ref().map(|i| i.config.sync_secs).unwrap_or(1));
+        tokio::spawn(async move {
+            loop {
+                sleep(sync).await;
+                network.sync_peers(&world).await;
+            }
+        });
+        Ok(())
+    }
+
+    /// 📤 Переслать пирам сообщение своей ноды. Кадр подписывает `signer`
+    /// и ручается за ключ отправителя сообщения. Переводы энергии не пересылаются:
+    /// энергия не пересекает границу процесса (см. `apply`).
+    pub fn forward(&self, msg: &Message, signer: &Identity, keys: &KeyRegistry) {
+        if !self.is_enabled() || msg.msg_type == MessageType::EnergyTransfer {
+            return;
+        }
+        let payload = Payload::BusMessage { message: msg.clone(), keys: vouch(keys, [msg.from.as_str()]) };
+        self.broadcast(Envelope::sign(signer, &payload));
+    }
+
+    /// 🔁 Отправить пирам нейроны своих нод, запросить их `key_chain` и адреса пиров.
+    /// Заодно обновить `connections` своих нод.
+    async fn sync_peers(&self, world: &World) {
+        let Some(signer) = gateway(world).await else {
+            return;
+        };
+        let connected = self.connected_peers();
+        let mut nodes = BTreeMap::new();
+        for n in world.nodes.lock().await.clone() {
+            let node = n.lock().await;
+            *node.connections.lock().await = connected.clone();
+            nodes.insert(node.name.clone(), node.neurons.lock().await.clone());
+        }
+        let keys = vouch(&world.keys, nodes.keys().map(|name| name.as_str()));
+        self.broadcast(Envelope::sign(&signer, &Payload::NeuronsSync { nodes, keys }));
+        // блоки — только после нашей вершины; если там форк, ответ покажет это
+        let first = world.nodes.lock().await.first().cloned();
+        let Some(first) = first else {
+            return;
+        };
+        let chain = first.lock().await.key_chain.clone();
+        let from_height = chain.lock().await.height();
+        self.broadcast(Envelope::sign(&signer, &Payload::BlocksRequest { from_height }));
+        self.broadcast(Envelope::sign(&signer, &Payload::GetPeers));
+    }
+
+    /// Отправить кадр каждому пиру — по одному соединению на пира
+    fn broadcast(&self, envelope: Envelope) {
+        let Some(inner) = &self.inner else {
+            return;
+        };
+        let links = inner.links.lock().unwrap();
+        let mut sent = BTreeSet::new();
+        for link in links.values() {
+            if sent.insert(link.session.peer.as_str()) && link.sender.try_send(envelope.clone()).is_err() {
+                self.count_dropped();
+            }
+        }
+    }
+
+    fn count_dropped(&self) {
+        if let Some(inner) = &self.inner {
+            inner.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// ⚖️ Оштрафовать пира `peer` с ключом шлюза `key`; забаненного отключить
+    /// (все соединения с этим ключом, под каким бы адресом они ни числились)
+    fn penalize(&self, peer: &str, key: &str, score: u32, reason: &str) {
+        let Some(inner) = &self.inner else {
+            return;
+        };
+        if !inner.peers.lock().unwrap().penalize(key, score) {
+            return;
+        }
+        println!("⛔ Пир {} забанен на {} с: {}", peer, inner.config.ban_secs, reason);
+        for link in inner.links.lock().unwrap().values() {
+            if link.session.key == key {
+                link.close.notify_one();
+            }
+        }
+    }
+
+    /// 📞 Позвонить пиру из таблицы
+    async fn dial(&self, addr: String, world: World) {
+        match TcpStream::connect(&addr).await {
+            Ok(stream) => {
+                if let Err(e) = self.session(stream, addr.clone(), Some(addr.clone()), &world).await {
+                    println!("⚠️ Связь с пиром {} прервана: {}", addr, e);
+                }
+            }
+            Err(e) => {
+                if let Some(inner) = &self.inner {
+                    inner.peers.lock().unwrap().dial_failed(&addr);
                 }
+                println!("⚠️ Пир {} недоступен: {}", addr, e);
             }
+        }
+    }
+
+    async fn accept(&self, stream: TcpStream, remote: String, world: World) {
+        if let Err(e) = self.session(stream, remote.clone(), None, &world).await {
+            println!("⚠️ Соединение от {} закрыто: {}", remote, e);
+        }
+    }
+
+    /// 🤝 Приветствие, допуск по таблице пиров, затем обмен кадрами: отдельная задача
+    /// читает кадры пира, а здесь пишутся исходящие кадры и раз в интервал keepalive —
+    /// `Ping`. Пир, молчащий `SILENT_INTERVALS` интервалов, отключается.
+    async fn session(
+        &self,
+        stream: TcpStream,
+        remote: String,
+        dialed: Option<String>,
+        world: &World,
+    ) -> Result<(), WireError> {
+        let Some(inner) = self.inner.clone() else {
+            return Ok(());
+        };
+        let handshake = match gateway(world).await {
+            Some(signer) => self.handshake(stream, &signer, &world.keys).await.map(|hello| (signer, hello)),
+            None => Err(WireError::Handshake("в процессе нет живых нод для подписи".into())),
+        };
+        let admitted = handshake.and_then(|(signer, (reader, writer, gateway_name, key, listen))| {
+            let mut table = inner.peers.lock().unwrap();
+            // адрес из приветствия пир сообщает сам — его проверяет таблица
+            let peer = match &dialed {
+                Some(addr) => addr.clone(),
+                None => table.inbound(&listen, &remote, &key).map_err(WireError::Refused)?,
+            };
+            table.admit(&peer, &key).map_err(WireError::Refused)?;
+            table.connected(&peer, &gateway_name, &key, world.clock.tick());
+            Ok((signer, reader, writer, gateway_name, key, peer))
         });
+        let (signer, mut reader, mut writer, gateway_name, key, peer) = match admitted {
+            Ok(admitted) => admitted,
+            Err(e) => {
+                if let Some(addr) = &dialed {
+                    inner.peers.lock().unwrap().dial_failed(addr);
+                }
+                return Err(e);
+            }
+        };
+
+        let session = Session {
+            peer: peer.clone(),
+            remote: remote.clone(),
+            outgoing: dialed.is_some(),
+            gateway: gateway_name,
+            key: key.clone(),
+            since_tick: world.clock.tick(),
+        };
+        println!("🤝 Соединение с {} ({}) установлено", session.gateway, peer);
+        let (sender, mut outbound) = mpsc::channel(OUTBOUND_QUEUE);
+        let close = Arc::new(Notify::new());
+        let id = inner.next_link.fetch_add(1, Ordering::Relaxed);
+        let link = Link { session, sender: sender.clone(), close: close.clone() };
+        inner.links.lock().unwrap().insert(id, link);
 
-        // === Поток для обучения + синхронизации нейронов ===
-        let peers = self.peers.clone();
-        tokio::spawn({
-            let node_ref = node.clone();
-            async move {
-                loop {
-                    {
-                        let mut n = node_ref.lock().await;
-                        let (data_root, key_root) = n.mine_data().await;
-                        n.try_commit_keyblock(data_root, key_root);
+        // кадры применяются отдельной задачей: долгий разбор цепи не мешает читать `Ping`
+        let (inbox, mut frames) = mpsc::channel::<(String, Payload)>(OUTBOUND_QUEUE);
+        let processing = {
+            let (network, world, peer, key, reply) = (self.clone(), world.clone(), peer.clone(), key.clone(), sender.clone());
+            tokio::spawn(async move {
+                while let Some((from, payload)) = frames.recv().await {
+                    let name = payload.name();
+                    if let Err(e) = network.apply(payload, &from, &reply, &world).await {
+                        println!("🚫 Кадр {} от {} ({}) отклонён: {}", name, from, peer, e);
+                        network.penalize(&peer, &key, peers::PENALTY_REJECTED, &e);
                     }
+                }
+            })
+        };
+        let last_seen = Arc::new(Mutex::new(Instant::now()));
+        let last_ping = Arc::new(Mutex::new(None));
+        let mut incoming = {
+            let reader_state = ReaderState {
+                peer: peer.clone(),
+                key,
+                signer: signer.clone(),
+                reply: sender,
+                inbox,
+                last_seen: last_seen.clone(),
+                last_ping: last_ping.clone(),
+            };
+            let (network, world) = (self.clone(), world.clone());
+            tokio::spawn(async move { network.read_loop(&mut reader, reader_state, &world).await })
+        };
 
-                    // Отправляем нейроны всем пирами
-                    for peer in &peers {
-                        let n = node_ref.lock().await;
-                        let neurons_json = n.export_neurons_json().await;
-                        drop(n);
-
-                        let msg = json!({
-                            "type": "neurons_sync",
-                            "data": neurons_json
-                        })
-                        .to_string();
-
-                        if let Ok(mut stream) = TcpStream::connect(peer).await {
-                            let _ = stream.write_all(msg.as_bytes()).await;
-                            println!("🔁 Отправлены нейроны → {}", peer);
+        let keepalive = self.keepalive();
+        let mut ticker = interval(keepalive);
+        ticker.tick().await;
+        let result = loop {
+            tokio::select! {
+                finished = &mut incoming => break finished.unwrap_or(Ok(())),
+                _ = close.notified() => break Err(WireError::Refused(format!("пир {} забанен", peer))),
+                envelope = outbound.recv() => {
+                    let Some(envelope) = envelope else { break Ok(()) };
+                    match wire::write_frame(&mut writer, &envelope).await {
+                        Err(WireError::TooLarge { len, max }) => {
+                            println!("⚠️ Кадр для {} не отправлен: {} байт больше предела {}", peer, len, max);
                         }
+                        Err(e) => break Err(e),
+                        Ok(()) => {}
+                    }
+                }
+                _ = ticker.tick() => {
+                    if last_seen.lock().unwrap().elapsed() > keepalive * SILENT_INTERVALS {
+                        break Err(WireError::Io(std::io::ErrorKind::TimedOut.into()));
+                    }
+                    let nonce = inner.nonce.fetch_add(1, Ordering::Relaxed);
+                    *last_ping.lock().unwrap() = Some((nonce, Instant::now()));
+                    let ping = Envelope::sign(&signer, &Payload::Ping { nonce });
+                    if let Err(e) = wire::write_frame(&mut writer, &ping).await {
+                        break Err(e);
+                    }
+                }
+            }
+        };
+        incoming.abort();
+        processing.abort();
+        inner.links.lock().unwrap().remove(&id);
+        inner.peers.lock().unwrap().disconnected(&peer);
+        result
+    }
+
+    /// Обменяться приветствиями: обе стороны сначала отправляют своё, потом читают чужое.
+    /// Возвращает половины сокета, шлюз пира, его закреплённый ключ и адрес,
+    /// который пир, по его словам, слушает.
+    async fn handshake(
+        &self,
+        stream: TcpStream,
+        signer: &Identity,
+        keys: &KeyRegistry,
+    ) -> Result<(OwnedReadHalf, OwnedWriteHalf, String, String, String), WireError> {
+        let listen = self.inner.as_ref().map(|i| i.config.listen.clone()).unwrap_or_default();
+        let (mut reader, mut writer) = stream.into_split();
+        let hello = Payload::Hello { version: PROTOCOL_VERSION, listen };
+        wire::write_frame(&mut writer, &Envelope::sign(signer, &hello)).await?;
+        let first = timeout(self.keepalive(), wire::read_frame_within(&mut reader, MAX_HELLO_FRAME))
+            .await
+            .map_err(|_| WireError::Handshake("пир не поздоровался вовремя".into()))??
+            .ok_or_else(|| WireError::Handshake("соединение закрыто до приветствия".into()))?;
+        if let Some(theirs) = first.hello_version().filter(|v| *v != PROTOCOL_VERSION) {
+            return Err(WireError::Version { ours: PROTOCOL_VERSION, theirs });
+        }
+        if self.is_local(&first.from) {
+            return Err(WireError::Refused("соединение с самим собой".into()));
+        }
+        match first.open(keys)? {
+            Payload::Hello { listen, .. } => Ok((reader, writer, first.from, first.public_key, listen)),
+            other => Err(WireError::Handshake(format!("первый кадр — {}, а не hello", other.name()))),
+        }
+    }
+
+    /// 📥 Читать кадры пира, пока соединение не закроется или границы кадров не потеряются.
+    /// Проверку связи и обмен адресами обслуживает сам, остальное передаёт в `inbox`.
+    async fn read_loop(&self, reader: &mut OwnedReadHalf, state: ReaderState, world: &World) -> Result<(), WireError> {
+        let Some(inner) = &self.inner else {
+            return Ok(());
+        };
+        let (peer, key) = (state.peer.as_str(), state.key.as_str());
+        loop {
+            let envelope = match wire::read_frame(reader).await {
+                Ok(Some(envelope)) => envelope,
+                Ok(None) => return Ok(()),
+                Err(e) => {
+                    let penalty = match &e {
+                        WireError::TooLarge { .. } => peers::PENALTY_OVERSIZED,
+                        WireError::Malformed(_) => peers::PENALTY_MALFORMED,
+                        _ => 0,
+                    };
+                    if penalty > 0 {
+                        self.penalize(peer, key, penalty, &e.to_string());
                     }
+                    if e.is_fatal() {
+                        return Err(e);
+                    }
+                    println!("🚫 Кадр от {} отклонён: {}", peer, e);
+                    continue;
+                }
+            };
+            *state.last_seen.lock().unwrap() = Instant::now();
+            let payload = match envelope.open(&world.keys) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    println!("🚫 Кадр от {} ({}) отклонён: {}", envelope.from, peer, e);
+                    let penalty = match e {
+                        WireError::Identity(_) => peers::PENALTY_BAD_SIGNATURE,
+                        _ => peers::PENALTY_MALFORMED,
+                    };
+                    self.penalize(peer, key, penalty, &e.to_string());
+                    continue;
+                }
+            };
 
-                    sleep(Duration::from_secs(10)).await;
+            let mut rtt = None;
+            match payload {
+                Payload::Hello { .. } => {
+                    self.penalize(peer, key, peers::PENALTY_REJECTED, "повторное приветствие");
+                    return Err(WireError::Handshake("повторное приветствие".into()));
+                }
+                Payload::Ping { nonce } => {
+                    let _ = state.reply.try_send(Envelope::sign(&state.signer, &Payload::Pong { nonce }));
+                }
+                Payload::Pong { nonce } => {
+                    if let Some((sent, at)) = *state.last_ping.lock().unwrap() {
+                        rtt = (sent == nonce).then(|| at.elapsed());
+                    }
+                }
+                Payload::GetPeers => {
+                    let addrs = inner.peers.lock().unwrap().gossip(peer);
+                    let _ = state.reply.try_send(Envelope::sign(&state.signer, &Payload::Peers { addrs }));
+                }
+                Payload::Peers { addrs } => {
+                    if addrs.len() > GOSSIP_LIMIT {
+                        self.penalize(peer, key, peers::PENALTY_MALFORMED, "слишком длинный список адресов");
+                    }
+                    let mut table = inner.peers.lock().unwrap();
+                    let learned =
+                        addrs.iter().take(GOSSIP_LIMIT).filter(|addr| table.learn(addr, PeerSource::Gossip)).count();
+                    if learned > 0 {
+                        println!("📇 {} новых адресов пиров от {}", learned, peer);
+                    }
+                }
+                payload => {
+                    if state.inbox.try_send((envelope.from, payload)).is_err() {
+                        self.count_dropped();
+                    }
                 }
             }
-        });
- 
+            inner.peers.lock().unwrap().seen(peer, world.clock.tick(), rtt);
+        }
+    }
+
+    /// Применить проверенный кадр пира
+    async fn apply(
+        &self,
+        payload: Payload,
+        via: &str,
+        reply: &mpsc::Sender<Envelope>,
+        world: &World,
+    ) -> Result<(), String> {
+        match payload {
+            // приветствие, проверка связи и адреса разбираются ещё при чтении
+            Payload::Hello { .. }
+            | Payload::Ping { .. }
+            | Payload::Pong { .. }
+            | Payload::GetPeers
+            | Payload::Peers { .. } => {}
+
+            // ✉️ сообщение ноды другого процесса уходит на нашу шину
+            Payload::BusMessage { message, keys } => {
+                self.accept_keys(&keys, world)?;
+                if self.is_local(&message.from) {
+                    return Err(format!("сообщение от имени нашей ноды {}", message.from));
+                }
+                message.verify(&world.keys).map_err(|e| e.to_string())?;
+                // энергия живёт в учёте своего процесса: списать её у чужой ноды
+                // нельзя, поэтому переводы и предложения помощи извне не принимаются
+                if message.msg_type == MessageType::EnergyTransfer {
+                    return Err(format!("перевод энергии от ноды другого процесса {}", message.from));
+                }
+                // подпись ставит сам пир — значение проверяется отдельно
+                message.check_value()?;
+                self.seen(&message.from, via, world, None);
+                world.net.send(message).await;
+            }
+
+            // 🔗 пир просит нашу key_chain
+            Payload::BlocksRequest { from_height } => {
+                let Some(signer) = gateway(world).await else {
+                    return Ok(());
+                };
+                let first = world.nodes.lock().await.first().cloned();
+                let Some(first) = first else {
+                    return Ok(());
+                };
+                let chain = first.lock().await.key_chain.clone();
+                let (blocks, height, tip) = {
+                    let chain = chain.lock().await;
+                    let blocks = chain.blocks_from(from_height, MAX_SYNC_BLOCKS).map_err(|e| e.to_string())?;
+                    (blocks, chain.height(), chain.last_hash())
+                };
+                let keys = vouch(&world.keys, blocks.iter().map(|b| b.validator.as_str()));
+                let response = Payload::BlocksResponse { from_height, blocks, keys, height, tip };
+                if reply.try_send(Envelope::sign(&signer, &response)).is_err() {
+                    self.count_dropped();
+                }
+            }
+
+            // 🔗 цепь пира: каждая наша нода сливает её по правилу выбора форка
+            Payload::BlocksResponse { from_height, blocks, keys, height, tip } => {
+                self.accept_keys(&keys, world)?;
+                let Some((from_height, blocks)) = self.collect_branch(via, from_height, blocks, height, &tip, reply, world).await?
+                else {
+                    return Ok(());
+                };
+                let mut adopted = 0;
+                let mut height = 0;
+                for n in world.nodes.lock().await.clone() {
+                    let node = n.lock().await;
+                    // начало цепи, которое пир не прислал, берётся у самой ноды
+                    let mut full: Vec<Block> = {
+                        let own = node.key_chain.lock().await;
+                        own.blocks.iter().take(from_height as usize).cloned().collect()
+                    };
+                    full.extend(blocks.iter().cloned());
+                    let incoming = Chain::from_blocks("peer_key", full);
+                    height = incoming.blocks.len();
+                    match node.sync_chain(ChainKind::Key, &incoming).await {
+                        Ok(Some(_)) => adopted += 1,
+                        Ok(None) => {}
+                        Err(e) => return Err(format!("key_chain: {}", e)),
+                    }
+                }
+                if adopted > 0 {
+                    println!("🔗 key_chain от {} принята {} нодами (высота {})", via, adopted, height);
+                }
+            }
+
+            // 🧠 нейроны нод другого процесса
+            Payload::NeuronsSync { nodes, keys } => {
+                self.accept_keys(&keys, world)?;
+                for (name, neurons) in nodes {
+                    if self.is_local(&name) {
+                        return Err(format!("нейроны от имени нашей ноды {}", name));
+                    }
+                    self.seen(&name, via, world, Some(neurons));
+                }
+                println!("🧠 Получена синхронизация нейронов от {}", via);
+            }
+        }
+        Ok(())
+    }
+
+    /// 🧩 Собрать ветку `key_chain` пира `via` из страниц. `Some` — ветка целиком
+    /// (высота начала и блоки), её можно сливать. Иначе запрошена следующая
+    /// страница; ветка, не продолжающая нашу цепь, запрашивается вдвое раньше
+    /// (форк глубже нашей вершины); `None` без запроса — у пира нет ничего нового.
+    #[allow(clippy::too_many_arguments)]
+    async fn collect_branch(
+        &self,
+        via: &str,
+        from_height: u64,
+        blocks: Vec<Block>,
+        height: u64,
+        tip: &str,
+        reply: &mpsc::Sender<Envelope>,
+        world: &World,
+    ) -> Result<Option<(u64, Vec<Block>)>, String> {
+        let Some(inner) = &self.inner else {
+            return Ok(None);
+        };
+        let page = blocks.len();
+        if page > MAX_SYNC_BLOCKS {
+            return Err(format!("{} блоков в одном ответе, предел {}", page, MAX_SYNC_BLOCKS));
+        }
+        let (start, branch) = {
+            let mut branches = inner.branches.lock().unwrap();
+            let mut branch = branches
+                .remove(via)
+                .filter(|(start, branch)| start + branch.len() as u64 == from_height)
+                .unwrap_or((from_height, Vec::new()));
+            branch.1.extend(blocks);
+            branch
+        };
+        if branch.len() > MAX_BRANCH {
+            return Err(format!("ветка длиннее {} блоков", MAX_BRANCH));
+        }
+
+        let first = world.nodes.lock().await.first().cloned();
+        let Some(first) = first else {
+            return Ok(None);
+        };
+        let chain = first.lock().await.key_chain.clone();
+        let diverged = {
+            let chain = chain.lock().await;
+            match branch.first() {
+                Some(block) => start > 0 && chain.hash_at(start - 1).as_deref() != Some(block.prev_hash.as_str()),
+                None => height <= start && height > 0 && chain.hash_at(height - 1).as_deref() != Some(tip),
+            }
+        };
+        if diverged {
+            self.request_blocks(start.min(height) / 2, reply, world).await;
+            return Ok(None);
+        }
+        let next = start + branch.len() as u64;
+        if page > 0 && next < height {
+            inner.branches.lock().unwrap().insert(via.to_string(), (start, branch));
+            self.request_blocks(next, reply, world).await;
+            return Ok(None);
+        }
+        Ok((!branch.is_empty()).then_some((start, branch)))
+    }
+
+    async fn request_blocks(&self, from_height: u64, reply: &mpsc::Sender<Envelope>, world: &World) {
+        let Some(signer) = gateway(world).await else {
+            return;
+        };
+        if reply.try_send(Envelope::sign(&signer, &Payload::BlocksRequest { from_height })).is_err() {
+            self.count_dropped();
+        }
+    }
+
+    /// Ключи, за которые ручается пир. Ключи своих нод мы знаем сами — пир может лишь повторить их.
+    fn accept_keys(&self, keys: &BTreeMap<String, String>, world: &World) -> Result<(), String> {
+        for (name, key) in keys.iter() {
+            let known = match self.is_local(name) {
+                true => world.keys.check(name, key),
+                false => world.keys.pin(name, key),
+            };
+            known.map_err(|e| e.to_string())?;
+        }
+        Ok(())
     }
-}
 
-async fn handle_connection(mut socket: TcpStream, node: Arc<Mutex<Node>>) {
-    let mut buffer = vec![0u8; 4096];
-    if let Ok(n) = socket.read(&mut buffer).await {
-        if n == 0 {
+    fn seen(&self, name: &str, via: &str, world: &World, neurons: Option<Vec<Neuron>>) {
+        let Some(inner) = &self.inner else {
             return;
+        };
+        let mut remote = inner.remote.lock().unwrap();
+        let node = remote.entry(name.to_string()).or_default();
+        node.via = via.to_string();
+        node.last_seen_tick = world.clock.tick();
+        if let Some(neurons) = neurons {
+            node.neurons = neurons;
         }
+    }
+
+    fn keepalive(&self) -> Duration {
+        let secs = self.inner.as_ref().map(|inner| inner.config.keepalive_secs).unwrap_or(1);
+        Duration::from_secs(secs)
+    }
+}
+
+/// Что нужно задаче чтения одного соединения
+struct ReaderState {
+    peer: String,
+    /// ключ шлюза пира из приветствия
+    key: String,
+    signer: Identity,
+    reply: mpsc::Sender<Envelope>,
+    inbox: mpsc::Sender<(String, Payload)>,
+    last_seen: Arc<Mutex<Instant>>,
+    /// последний наш `Ping`: nonce и время отправки — для замера RTT
+    last_ping: Arc<Mutex<Option<(u64, Instant)>>>,
+}
+
+/// Нода-шлюз, от имени которой процесс подписывает кадры, — первая живая нода
+async fn gateway(world: &World) -> Option<Identity> {
+    let first = world.nodes.lock().await.first().cloned()?;
+    let identity = first.lock().await.identity.clone();
+    Some(identity)
+}
+
+/// Известные ключи нод `names` — пир закрепит их за именами (`KeyRegistry::pin`)
+fn vouch<'a>(keys: &KeyRegistry, names: impl IntoIterator<Item = &'a str>) -> BTreeMap<String, String> {
+    names
+        .into_iter()
+        .filter_map(|name| keys.get(name).map(|key| (name.to_string(), key)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::interaction::{MessageType, HELP_AMOUNT, MAX_TRANSFER};
+    use crate::world::World;
+    use crate::rng::Seed;
+
+    fn networked(prefix: &str) -> Config {
+        let mut config = Config::default();
+        config.network.enabled = true;
+        config.network.name_prefix = prefix.into();
+        config.simulation.initial_nodes = 2;
+        config
+    }
 
-        let data = String::from_utf8_lossy(&buffer[..n]);
-        if let Ok(json_msg) = serde_json::from_str::<serde_json::Value>(&data) {
-            if json_msg["type"] == "neurons_sync" {
-                let neurons_json = json_msg["data"].as_str().unwrap_or("");
-                let mut n = node.lock().await;
-                n.import_neurons_json(neurons_json);
-                println!("🧠 Получена синхронизация нейронов");
-                return;
+    /// Дописать `count` блоков в `key_chain` первой ноды мира
+    async fn extend_key_chain(world: &World, count: u64) {
+        let first = world.nodes.lock().await.first().cloned().unwrap();
+        let node = first.lock().await;
+        let mut chain = node.key_chain.lock().await;
+        for i in 0..count {
+            chain.add_block(format!("d{}", i), "k".into(), &node.identity, i as i64).unwrap();
+        }
+    }
+
+    async fn key_heights(world: &World) -> Vec<u64> {
+        let mut heights = Vec::new();
+        for n in world.nodes.lock().await.iter() {
+            let chain = n.lock().await.key_chain.clone();
+            heights.push(chain.lock().await.height());
+        }
+        heights
+    }
+
+    /// Гонять кадры между процессами `a` и `b`, пока оба молчат; сколько страниц блоков пришло в `b`
+    async fn pump(a: &World, b: &World, from_height: u64) -> usize {
+        let (to_a, mut inbox_a) = mpsc::channel(4);
+        let (to_b, mut inbox_b) = mpsc::channel(4);
+        let request = Payload::BlocksRequest { from_height };
+        a.network.apply(request, "b_node0", &to_b, a).await.unwrap();
+        let mut pages = 0;
+        loop {
+            let Ok(envelope) = inbox_b.try_recv() else {
+                return pages;
+            };
+            let payload = envelope.open(&b.keys).unwrap();
+            if let Payload::BlocksResponse { blocks, .. } = &payload {
+                assert!(blocks.len() <= MAX_SYNC_BLOCKS);
+                pages += 1;
             }
+            b.network.apply(payload, &envelope.from, &to_a, b).await.unwrap();
+            let Ok(envelope) = inbox_a.try_recv() else {
+                return pages;
+            };
+            let payload = envelope.open(&a.keys).unwrap();
+            a.network.apply(payload, &envelope.from, &to_b, a).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn long_chain_arrives_in_pages() {
+        let a = crate::test_world(&networked("a_")).await;
+        let b = crate::test_world(&networked("b_")).await;
+        let length = MAX_SYNC_BLOCKS as u64 * 2 + 10;
+        extend_key_chain(&a, length).await;
+
+        // спрашивают от своей вершины — только генезис
+        let pages = pump(&a, &b, 1).await;
+        assert_eq!(pages, 3);
+        assert_eq!(key_heights(&b).await, vec![length + 1; 2]);
+
+        // уже совпадающие цепи: одна пустая страница и ничего больше
+        assert_eq!(pump(&a, &b, length + 1).await, 1);
+    }
+
+    #[tokio::test]
+    async fn fork_below_tip_is_found_by_stepping_back() {
+        let a = crate::test_world(&networked("a_")).await;
+        let b = crate::test_world(&networked("b_")).await;
+        extend_key_chain(&a, 40).await;
+        // у `b` своя ветка от генезиса
+        extend_key_chain(&b, 3).await;
+        let ours = key_heights(&b).await[0];
+
+        pump(&a, &b, ours).await;
+        assert_eq!(key_heights(&b).await, vec![41; 2]);
+        let first = b.nodes.lock().await.first().cloned().unwrap();
+        let chain = first.lock().await.key_chain.clone();
+        let tip = chain.lock().await.last_hash();
+        let first = a.nodes.lock().await.first().cloned().unwrap();
+        let chain = first.lock().await.key_chain.clone();
+        assert_eq!(tip, chain.lock().await.last_hash());
+    }
+
+    /// Кадр ноды чужого процесса, подписанный её собственным ключом
+    fn forged(msg_type: MessageType, value: f64) -> Payload {
+        let identity = Identity::derive("b_node0", &Seed::new(9).stream("node/b_node0"));
+        let message = Message::new("b_node0", Some("a_node0"), msg_type, value, None).signed(&identity);
+        let keys = BTreeMap::from([(identity.name.clone(), identity.public_key())]);
+        Payload::BusMessage { message, keys }
+    }
+
+    #[tokio::test]
+    async fn apply_rejects_forged_values() {
+        let mut config = Config::default();
+        config.network.enabled = true;
+        config.network.name_prefix = "a_".into();
+        let world = crate::test_world(&config).await;
+        let network = world.network.clone();
+        let (tx, _rx) = mpsc::channel(4);
+
+        for value in [-1000.0, f64::NAN, f64::INFINITY] {
+            let result = network.apply(forged(MessageType::HelpRequest, value), "peer", &tx, &world).await;
+            assert!(result.is_err(), "значение {} принято", value);
+        }
+        // перевод энергии извне не принимается ни с каким значением
+        for value in [HELP_AMOUNT, MAX_TRANSFER + 1.0, 1e9] {
+            let result = network.apply(forged(MessageType::EnergyTransfer, value), "peer", &tx, &world).await;
+            assert!(result.is_err(), "перевод {} принят", value);
         }
+        assert!(world.net.drain().await.is_empty());
+
+        network.apply(forged(MessageType::HelpRequest, 0.0), "peer", &tx, &world).await.unwrap();
+        assert_eq!(world.net.drain().await.len(), 1);
     }
-         
-}
\ No newline at end of file
+}
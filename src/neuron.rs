@@ -1,6 +1,7 @@
 use serde::{Serialize, Deserialize};
+use rand::Rng;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Neuron {
     pub id: u64,
     pub value: f64,
@@ -8,11 +9,11 @@ pub struct Neuron {
 }
 
 impl Neuron {
-    pub fn new(id: u64, input_size: usize) -> Self {
+    pub fn new<R: Rng + ?Sized>(id: u64, input_size: usize, rng: &mut R) -> Self {
         Self {
             id,
             value: 0.0,
-            weights: (0..input_size).map(|_| rand::random::<f64>() * 2.0 - 1.0).collect(),
+            weights: (0..input_size).map(|_| rng.gen::<f64>() * 2.0 - 1.0).collect(),
         }
     }
 
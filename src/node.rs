@@ -1,22 +1,32 @@
 use crate::synapse::SynapseChain;
-use crate::chain::Chain;
 use crate::neuron::Neuron;
 use crate::energy::Energy;
 use crate::wallet::Wallet; 
-use rand::{Rng, SeedableRng};
-use rand::rngs::StdRng;
-use serde_json;
+use crate::rng::{self, SimRng};
+use rand::Rng;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use log::info;
 use serde_json::json;   
 use crate::interaction::NetworkBus;
 use crate::interaction::Message;
+use crate::config::{ConsensusConfig, NodeConfig};
+use crate::data_dir::DataRoot;
+use crate::consensus::{self, ContributionProof};
+use crate::chain::{Block, Chain, ChainError, Reorg};
+use crate::clock::SimClock;
+use crate::events::{ChainKind, Event, EventLog};
+use crate::energy_ledger::{EnergyFlow, EnergyLedger};
+use crate::token_ledger::{TokenLedger, TokenReason};
+use crate::identity::{Identity, KeyRegistry};
+use crate::payload;
+
+/// Файлы в каталоге ноды (см. `DataRoot`)
+const SYNAPSES_FILE: &str = "synapses.json";
+const NEURONS_FILE: &str = "neurons.json";
+const WALLET_FILE: &str = "wallet.json";
+const MEMORY_FILE: &str = "memory.json";
+pub const IDENTITY_FILE: &str = "identity.json";
 
-const DECAY_PER_TICK: f64 = 1.0;
-const REPLICATION_THRESHOLD: f64 = 1.0; // вместо 80
-const REPRODUCTION_COST: f64 = 0.0;
-const MUTATION_RATE: f64 = 0.05;
 
 
 
@@ -35,27 +45,81 @@ pub struct Node {
     pub connections: Arc<Mutex<Vec<String>>>, // ✅ добавляем
     pub neurons: Arc<Mutex<Vec<Neuron>>>,     // ✅ добавляем
     pub wallet: Wallet,
-    pub rng: Arc<Mutex<StdRng>>,
+    pub rng: Arc<Mutex<SimRng>>,
+    pub children_spawned: u64,
+    pub params: NodeConfig,
+    pub log: EventLog,
+    /// часы симуляции — метки времени новых блоков
+    pub clock: SimClock,
+    /// ключ Ed25519, которым нода подписывает блоки и сообщения
+    pub identity: Identity,
+    /// открытые ключи всех нод — по ним проверяются чужие блоки и сообщения
+    pub keys: KeyRegistry,
+    /// корень данных, где лежит каталог ноды (наследуется потомками)
+    pub data: DataRoot,
 }
 
 impl Node {
     
     // === Создание новой ноды ===
-    pub fn new(name: &str) -> Arc<Mutex<Node>> {
+    /// `rng` — собственный поток случайности ноды (см. `Seed::stream`),
+    /// `params` — константы жизненного цикла из конфига,
+    /// `log` — журнал, куда пишутся изменения энергии, кошелька и цепей,
+    /// `ledger` — учёт энергии популяции, `tokens` — книга токенов,
+    /// `clock` — часы симуляции для меток времени блоков,
+    /// `keys` — реестр ключей: выдаёт ключ новой ноде и записывает его,
+    /// `data` — корень данных: цепи, нейроны и синапсы ноды открываются
+    /// из её каталога, если он уже есть и не принадлежал умершей ноде
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: &str,
+        rng: SimRng,
+        params: NodeConfig,
+        log: EventLog,
+        ledger: EnergyLedger,
+        tokens: TokenLedger,
+        clock: SimClock,
+        keys: KeyRegistry,
+        data: DataRoot,
+    ) -> Arc<Mutex<Node>> {
+        // каталог умершей ноды с тем же именем не открывается (см. `DataRoot::claim`)
+        data.claim(name);
+        // ключ — из каталога ноды, если он там есть; иначе новый (см. `KeyRegistry::issue`),
+        // и он сразу сохраняется рядом с цепями, которые им подписаны
+        let identity = match data.read_json::<Identity>(name, IDENTITY_FILE) {
+            Some(identity) if identity.name == name => identity,
+            _ => {
+                let identity = keys.issue(name, &rng);
+                if let Err(e) = data.write_json(name, IDENTITY_FILE, &identity) {
+                    println!("⚠️ {}: ключ не сохранён: {}", name, e);
+                }
+                identity
+            }
+        };
+        keys.register(&identity);
         Arc::new(Mutex::new(Node {
             name: name.to_string(),
-            energy: Arc::new(Mutex::new(Energy::new(name))),
+            energy: Arc::new(Mutex::new(Energy::new(name, log.clone(), ledger))),
             efficiency: 1.0,
             altruism: 0.5,
             resilience: 0.5,
             experience: 0.0,
-            data_chain: Arc::new(Mutex::new(Chain::new(&format!("{}_data", name)))),
-            key_chain: Arc::new(Mutex::new(Chain::new(&format!("{}_key", name)))),
-            synapse_chain: Arc::new(Mutex::new(SynapseChain::new())),
+            data_chain: Arc::new(Mutex::new(data.open_chain(name, ChainKind::Data))),
+            key_chain: Arc::new(Mutex::new(data.open_chain(name, ChainKind::Key))),
+            synapse_chain: Arc::new(Mutex::new(
+                data.read_json(name, SYNAPSES_FILE).unwrap_or_else(SynapseChain::new),
+            )),
             connections: Arc::new(Mutex::new(vec![])),
-            neurons: Arc::new(Mutex::new(vec![])),
-            wallet: Wallet::new(),
-            rng: Arc::new(Mutex::new(StdRng::from_entropy())),
+            neurons: Arc::new(Mutex::new(data.read_json(name, NEURONS_FILE).unwrap_or_default())),
+            wallet: Wallet::new(name, log.clone(), tokens),
+            rng: Arc::new(Mutex::new(rng)),
+            children_spawned: 0,
+            params,
+            log,
+            clock,
+            identity,
+            keys,
+            data,
         }))
         /* Self {
             name: name.to_string(),
@@ -74,264 +138,273 @@ impl Node {
         } */
     }
 
-    /// Экспортируем нейроны в JSON для обмена между нодами
-    pub async fn export_neurons_json(&self) -> String {
-        let neurons = self.neurons.lock().await;
-        serde_json::to_string(&*neurons).unwrap_or_else(|_| "[]".into())
-    }
-
-    /// Импортируем нейроны из JSON (обновляем внутреннюю структуру)
-    pub async fn import_neurons_json(&mut self, json_data: &str) {
-        if let Ok(neurons) = serde_json::from_str::<Vec<Neuron>>(json_data) {
-            println!("🧬 Импортировано {} нейронов", neurons.len());
-            *self.neurons.lock().await = neurons;
-        } else {
-            println!("⚠️ Ошибка при импорте нейронов");
-        }
-    }
-    pub fn mine_block(&mut self) {
-        println!("⛏️  Node {} mined a new block!", self.name);
-        // Здесь можешь добавить работу с цепочкой, энергией и т.п.
-        self.wallet.deposit(2.0); // награда за блок
-        println!("💰 {} получил 2 токена за добычу блока!", self.name);
-
-    }
     pub async fn get_chain_summary(&self) -> Vec<String> {
         let chain = self.data_chain.lock().await; // асинхронный захват блокировки
 
-            chain
-                .blocks
-                .iter()
-                .map(|b| format!("Block {}: {}", b.index, b.hash))
-                .collect()
+        // хэши — из индекса хранилища, сегменты не читаются
+        (0..chain.height())
+            .filter_map(|height| chain.hash_at(height).map(|hash| format!("Block {}: {}", height, hash)))
+            .collect()
     }
     
-    pub async fn latest_block_json(&self) -> String {
-        if let Some(last_block) = self.data_chain.lock().await.blocks.last() {
-            serde_json::to_string(&json!({
-                "index": last_block.index,
-                "data_root": last_block.data_root,
-                "key_root": last_block.key_root,
-                "validator": last_block.validator,
-                "hash": last_block.hash
-            })).unwrap()
-        } else {
-            "{}".into()
-        }
-    }
 
-    // === Клон для сети ===
-    pub fn clone_for_net(&self) -> Self {
-        Self {
-            name: self.name.clone(),
-            energy: Arc::clone(&self.energy),
+    /// 📜 Записать в журнал появление ноды со всем её стартовым состоянием
+    /// (и отметить её в манифесте корня данных — он ведётся и без журнала)
+    pub async fn log_spawn(&self, parent: Option<&str>) {
+        self.data.record_birth(&self.name, parent, self.clock.tick());
+        if !self.log.is_enabled() {
+            return;
+        }
+        self.log.emit(Event::NodeSpawned {
+            node: self.name.clone(),
+            parent: parent.map(|p| p.to_string()),
+            energy: self.energy.lock().await.level,
+            balance: self.wallet.get_balance().await,
             efficiency: self.efficiency,
             altruism: self.altruism,
             resilience: self.resilience,
             experience: self.experience,
-            data_chain: Arc::clone(&self.data_chain),
-            key_chain: Arc::clone(&self.key_chain),
-            synapse_chain: Arc::clone(&self.synapse_chain),
-            connections: Arc::clone(&self.connections),
-            neurons: Arc::clone(&self.neurons), 
-            wallet: self.wallet.clone(),
-            rng: Arc::clone(&self.rng),
-        }
-    }
-    // === Симуляция добычи данных ===
-    pub async fn mine_data(&self) -> (String, String) {
-        let data_root = format!("{:x}", rand::random::<u64>());
-        let key_root = format!("{:x}", rand::random::<u64>());
-        (data_root, key_root)
+            data_chain_height: self.data_chain.lock().await.blocks.len(),
+            key_chain_height: self.key_chain.lock().await.blocks.len(),
+        });
     }
 
-    // === Завершение блока ===
-    pub async fn finalize_keyblock(&self, data_root: String, key_root: String, winner: &str) {
-        let mut kchain = self.key_chain.lock().await;
-        kchain.add_block(data_root, key_root, winner.to_string());
-        println!("✅ Валидатор {} закрыл KeyBlock", winner);
-    }
-
-    // === Получение последних блоков для синхронизации ===
-    pub async fn last_blocks_json(&self) -> String {
-        let d = self.data_chain.lock().await;
-        let k = self.key_chain.lock().await;
-        serde_json::json!({
-            "data_chain": d.blocks,
-            "key_chain": k.blocks,
-        })
-        .to_string()
-    }
-
-    // === Получение блоков от сети ===
-    pub async fn add_blocks_from_json(&self, json_str: String) {
-        let parsed: serde_json::Value = serde_json::from_str(&json_str).unwrap_or_default();
-        let mut d = self.data_chain.lock().await;
-        let mut k = self.key_chain.lock().await;
-        if let Some(data_blocks) = parsed["data_chain"].as_array() {
-            for b in data_blocks {
-                if let Some(val) = b["validator"].as_str() {
-                    d.add_block("sync".into(), "sync".into(), val.to_string());
-                }
-            }
+    /// 💾 Сохранить синапсы, нейроны, кошелёк и память ноды в её каталог.
+    /// Цепи сюда не входят — они пишутся в хранилище блок за блоком.
+    pub async fn save_files(&self) {
+        if !self.data.is_enabled() {
+            return;
         }
-        if let Some(key_blocks) = parsed["key_chain"].as_array() {
-            for b in key_blocks {
-                if let Some(val) = b["validator"].as_str() {
-                    k.add_block("sync".into(), "sync".into(), val.to_string());
-                }
-            }
+        let synapses = self.synapse_chain.lock().await.clone();
+        let neurons = self.neurons.lock().await.clone();
+        let wallet = json!({
+            "balance": self.wallet.get_balance().await,
+            "tick": self.clock.tick(),
+        });
+        let memory = json!({
+            "experience": self.experience,
+            "efficiency": self.efficiency,
+            "altruism": self.altruism,
+            "resilience": self.resilience,
+            "connections": *self.connections.lock().await,
+        });
+
+        let written = self
+            .data
+            .write_json(&self.name, SYNAPSES_FILE, &synapses)
+            .and_then(|_| self.data.write_json(&self.name, NEURONS_FILE, &neurons))
+            .and_then(|_| self.data.write_json(&self.name, WALLET_FILE, &wallet))
+            .and_then(|_| self.data.write_json(&self.name, MEMORY_FILE, &memory));
+        if let Err(e) = written {
+            println!("⚠️ {}: каталог ноды не сохранён: {}", self.name, e);
         }
-        println!("🔗 Нода {} синхронизировала блоки из сети", self.name);
     }
-    
 
-    // === Основной PoC и обучение нейронов ===
-    pub async fn try_commit_keyblock(&mut self, data_root: String, key_root: String) -> (u64, bool) {
-        let mut energy = self.energy.lock().await;
+    /// 📜 Записать в журнал текущие черты ноды
+    pub fn log_traits(&self) {
+        self.log.emit(Event::TraitsChanged {
+            node: self.name.clone(),
+            efficiency: self.efficiency,
+            altruism: self.altruism,
+            resilience: self.resilience,
+            experience: self.experience,
+        });
+    }
 
-        if energy.level <= 0.0 {
-            println!("😴 {} устал и не участвует", self.name);
-            return (0, false);
+    /// 📜 Записать в журнал последний блок цепи
+    fn log_block(&self, kind: ChainKind, chain: &Chain) {
+        if let Some(block) = chain.blocks.last() {
+            self.log_committed(kind, block);
         }
+    }
 
-        energy.consume(5.0);
-        energy.restore(0.5);
-        println!("🧠 {} обучает DataChain... (энергия = {:.2})", self.name, energy.level);
-         
-
-        let proof_value: u64 = rand::random::<u64>() % 500;
-        println!("⚔️ {} участвует в PoC, значение: {}", self.name, proof_value);
-        println!("⚙️ {} предложил commit {}", self.name, proof_value);
+    fn log_committed(&self, kind: ChainKind, block: &Block) {
+        self.log.emit(Event::BlockCommitted {
+            node: self.name.clone(),
+            chain: kind,
+            index: block.index,
+            validator: block.validator.clone(),
+            hash: block.hash.clone(),
+        });
+    }
+    // === Завершение блока ===
+    /// Принять ключевой блок, решённый консенсусом, если он подписан ключом своего валидатора
+    pub async fn finalize_keyblock(&self, block: &Block) -> Result<(), ChainError> {
+        block.check_validator(&self.keys)?;
+        let mut kchain = self.key_chain.lock().await;
+        kchain.append(block.clone())?;
+        self.log_block(ChainKind::Key, &kchain);
+        Ok(())
+    }
 
-        if proof_value > 250 {
-            println!("👑 Победитель PoC — {}", self.name);
+    /// 🧐 Проверить объявленный ключевой блок: ключ валидатора известен,
+    /// блок встаёт следующим в `key_chain` этой ноды
+    pub async fn validate_keyblock(&self, block: &Block) -> Result<(), ChainError> {
+        block.check_validator(&self.keys)?;
+        self.key_chain.lock().await.check_next(block)
+    }
 
-            let chain_len = {
-                let chain = self.data_chain.lock().await;
-                chain.blocks.len()
+    /// 🔀 Синхронизировать цепь `kind` с чужой (см. `Chain::sync`).
+    /// При смене ветки выпавшие блоки попадают в журнал как `BlockOrphaned`,
+    /// а нейроны и синапсы, записанные ими, откатываются.
+    pub async fn sync_chain(&self, kind: ChainKind, incoming: &Chain) -> Result<Option<Reorg>, ChainError> {
+        let reorg = {
+            let mut chain = match kind {
+                ChainKind::Data => self.data_chain.lock().await,
+                ChainKind::Key => self.key_chain.lock().await,
             };
+            let Some(reorg) = chain.sync(incoming, &self.keys)? else {
+                return Ok(None);
+            };
+            self.log_reorg(kind, &reorg, chain.blocks.len());
+            reorg
+        };
 
-            let mut neuron = Neuron::new(chain_len as u64, 3);
-            let inputs = vec![rand::random::<f64>(), rand::random::<f64>(), rand::random::<f64>()];
-            let expected = if inputs.iter().sum::<f64>() > 1.5 { 1.0 } else { 0.0 };
-            let lr = 0.1;
-            let loss = neuron.learn(&inputs, expected, lr);
-
+        if !reorg.orphaned.is_empty() {
             println!(
-                "🧩 Нейрон #{} => входы: {:?}, выход: {:.4}, ошибка: {:.6}",
-                neuron.id, inputs, neuron.value, loss
+                "🔀 {}: смена ветки {:?} после #{} — выпало {}, принято {}",
+                self.name,
+                kind,
+                reorg.common_ancestor,
+                reorg.orphaned.len(),
+                reorg.adopted.len()
             );
-            // === 🧬 создаём синапс между новым и предыдущим нейроном ===
-            let mut synapses = self.synapse_chain.lock().await;
-            if neuron.id > 0 {
-                let weight: f64 = rand::random();
-                synapses.connect(neuron.id - 1, neuron.id, weight);
-                synapses.save_to_file().unwrap_or_else(|e| println!("⚠️ Ошибка сохранения SynapseChain: {}", e));
-
-                println!("🔗 Создан синапс: {} -> {} (вес {:.4})", neuron.id - 1, neuron.id, weight);
-            }
-
-            let neuron_json = serde_json::to_string(&neuron).unwrap();
-
-            {
-                let mut dchain = self.data_chain.lock().await;
-                dchain.add_block(neuron_json, "key_placeholder".into(), self.name.clone());
-            }
-
-            {
-                let mut kchain = self.key_chain.lock().await;
-                kchain.add_block("data_hash".into(), key_root.clone(), self.name.clone());
+            match kind {
+                ChainKind::Data => self.rollback_neurons(&reorg.orphaned).await,
+                ChainKind::Key => self.orphan_data_blocks(&reorg.orphaned).await,
             }
+        }
+        Ok(Some(reorg))
+    }
 
-            println!("✅ Создан новый блок Data+Key (нейрон обучен)");
-
-            energy.restore(10.0);
-             
-            println!("⚡ {} восстановил энергию: {:.2}", self.name, energy.level);
-             
-
-            // Попробуем помочь соседу (случайно)
-            use rand::seq::SliceRandom;
-            let mut rng = StdRng::from_entropy();
-            let maybe_peer = ["Node-4000", "Node-4001", "Node-4002"].choose(&mut rng).unwrap();
-
-            if maybe_peer != &self.name {
-                println!("🤝 {} ищет возможность помочь {}", self.name, maybe_peer);
-                // Здесь можно будет подключить обмен по сети, а пока — просто вывод
-            }
+    /// 📜 Записать в журнал выпавшие и принятые блоки; `ChainReorg` — только если ветка сменилась
+    fn log_reorg(&self, kind: ChainKind, reorg: &Reorg, height: usize) {
+        for block in reorg.orphaned.iter() {
+            self.log.emit(Event::BlockOrphaned {
+                node: self.name.clone(),
+                chain: kind,
+                index: block.index,
+                validator: block.validator.clone(),
+                hash: block.hash.clone(),
+            });
         }
- 
-        if energy.level > 40.0 {
-            // шанс размножиться растёт с энергией
-            let chance = (energy.level / 200.0).clamp(0.05, 0.5); // 5–50%
-            if rand::random::<f64>() < chance {
-                println!("🧬 [tick] {} создаёт потомка (шанс {:.2})", self.name, chance);
-                // вызываем логику создания цепи
-                // ...
-            }
+        for block in reorg.adopted.iter() {
+            self.log_committed(kind, block);
         }
-        let mut rng = StdRng::from_entropy();
-        let commit_value: u64 = rng.gen_range(1..=1000); 
-        info!("⚔️ {} участвует в PoC, значение: {}", self.name, commit_value); 
-        
-        // ✅ возвращаем в самом конце
-        (commit_value, true)
-    }
-    pub async fn try_merge_chain_json(&mut self, json: String) {
-        if let Ok(other_chain) = serde_json::from_str::<crate::chain::Chain>(&json) {
-            // 🔒 Получаем доступ к заблокированной цепочке
-            let mut current_chain = self.data_chain.lock().await;
-
-            if other_chain.blocks.len() > current_chain.blocks.len() {
-                println!(
-                    "🔄 Обновляем локальную цепочку: {} → {} блоков",
-                    current_chain.blocks.len(),
-                    other_chain.blocks.len()
-                );
-
-                *current_chain = other_chain;
-                let _ = current_chain.save_to_file();
-            } else {
-                println!(
-                    "✅ Локальная цепочка актуальна ({} блоков)",
-                    current_chain.blocks.len()
-                );
-            }
-        } else {
-            println!("⚠️ Ошибка при десериализации цепочки для синхронизации");
+        if !reorg.orphaned.is_empty() {
+            self.log.emit(Event::ChainReorg {
+                node: self.name.clone(),
+                chain: kind,
+                common_ancestor: reorg.common_ancestor,
+                orphaned: reorg.orphaned.len(),
+                adopted: reorg.adopted.len(),
+                height,
+            });
         }
     }
 
-    // === Энергообмен между нодами ===
-    pub async fn share_energy(&mut self, target: &mut Node) {
-
-        let mut my_energy = self.energy.lock().await;
-        let mut target_energy = target.energy.lock().await;
+    /// Ключевые блоки выпали — вместе с ними выпадают блоки DataChain, ссылающиеся
+    /// на них через `key_root`. Такие блоки идут по возрастанию высоты,
+    /// поэтому DataChain обрезается с первого из них.
+    async fn orphan_data_blocks(&self, orphaned_keys: &[Block]) {
+        let orphaned = {
+            let mut dchain = self.data_chain.lock().await;
+            let Some(from) = dchain
+                .blocks
+                .iter()
+                .find(|b| orphaned_keys.iter().any(|k| k.hash == b.key_root))
+                .map(|b| b.index)
+            else {
+                return;
+            };
+            let orphaned = match dchain.truncate_from(from) {
+                Ok(orphaned) => orphaned,
+                Err(e) => {
+                    println!("⚠️ {}: не удалось откатить DataChain: {}", self.name, e);
+                    return;
+                }
+            };
+            let reorg = Reorg { common_ancestor: from - 1, orphaned, adopted: Vec::new() };
+            self.log_reorg(ChainKind::Data, &reorg, dchain.blocks.len());
+            reorg.orphaned
+        };
+        self.rollback_neurons(&orphaned).await;
+    }
 
-        // Минимальный порог для помощи
-        if my_energy.level < 30.0 {
-            println!("💤 {} слишком слаб, чтобы делиться энергией", self.name);
+    /// ↩️ Убрать нейроны, записанные выпавшими блоками DataChain, и синапсы к ним.
+    /// Нейрон узнаётся по корню записи о нём (`payload::neuron_root`).
+    async fn rollback_neurons(&self, orphaned: &[Block]) {
+        let roots: Vec<&str> = orphaned.iter().map(|b| b.data_root.as_str()).collect();
+        let mut neurons = self.neurons.lock().await;
+        let (removed, kept): (Vec<Neuron>, Vec<Neuron>) = neurons.drain(..).partition(|n| {
+            roots.contains(&payload::neuron_root(&self.name, n.id, &consensus::neuron_hash(n)).as_str())
+        });
+        *neurons = kept;
+        if removed.is_empty() {
             return;
         }
+        // синапс держится, пока жив хоть один нейрон с его id
+        let gone: Vec<u64> = removed
+            .iter()
+            .map(|r| r.id)
+            .filter(|id| !neurons.iter().any(|n| n.id == *id))
+            .collect();
+        let mut synapses = self.synapse_chain.lock().await;
+        let synapses_before = synapses.synapses.len();
+        synapses.synapses.retain(|s| !gone.contains(&s.from_id) && !gone.contains(&s.to_id));
+        println!(
+            "↩️ {}: откат {} нейронов и {} синапсов из выпавших блоков",
+            self.name,
+            removed.len(),
+            synapses_before - synapses.synapses.len()
+        );
+    }
 
-        // Помогаем, если у цели меньше 20
-        if target_energy.level < 20.0 {
-            let transfer = (my_energy.level * 0.1).min(10.0);
-            my_energy.consume(transfer);
-            target_energy.restore(transfer);
+    // === Proof-of-Contribution ===
+    /// 📜 Доказательство вклада в раунд `height`: энергия, опыт и качество
+    /// обучения нейрона на общей задаче раунда (см. `consensus::train`)
+    pub async fn contribute(&self, height: u64, prev_hash: &str, config: &ConsensusConfig) -> ContributionProof {
+        let energy = self.energy.lock().await.level.max(0.0);
+        let (neuron, quality) =
+            consensus::train(&self.name, prev_hash, height, self.experience, self.efficiency, config);
+
+        // уставшая нода не участвует
+        let weight = if energy > 0.0 {
+            config.energy_weight * energy
+                + config.experience_weight * self.experience.max(0.0)
+                + config.quality_weight * quality
+        } else {
+            0.0
+        };
+        println!("⚔️ {} участвует в PoC #{}: вес {:.2} (качество {:.3})", self.name, height, weight, quality);
 
-            println!(
-                "🔋 {} передал {:.2} энергии ноде {} (теперь у {}: {:.2}, у {}: {:.2})",
-                self.name,
-                transfer,
-                target.name,
-                self.name,
-                my_energy.level,
-                target.name,
-                target_energy.level
-            );
+        ContributionProof {
+            node: self.name.clone(),
+            height,
+            prev_hash: prev_hash.to_string(),
+            energy,
+            experience: self.experience,
+            quality,
+            weight,
+            neuron_hash: consensus::neuron_hash(&neuron),
+            neuron,
+        }
+    }
+
+    /// 👑 Победитель раунда записывает обученный нейрон в свою DataChain и получает награду.
+    /// `data_root` блока — корень Меркла над записью о нейроне, `key_root` — хэш ключевого блока.
+    pub async fn reward_consensus(&self, block: &Block, neuron: Neuron, config: &ConsensusConfig) {
+        let data_root = payload::neuron_root(&self.name, neuron.id, &consensus::neuron_hash(&neuron));
+        {
+            let mut dchain = self.data_chain.lock().await;
+            match dchain.add_block(data_root, block.hash.clone(), &self.identity, block.timestamp) {
+                Ok(()) => self.log_block(ChainKind::Data, &dchain),
+                Err(e) => println!("⚠️ {}: блок DataChain не записан: {}", self.name, e),
+            }
         }
+        self.neurons.lock().await.push(neuron);
+        self.wallet.reward(config.reward, TokenReason::BlockReward).await;
+        println!("👑 Победитель PoC — {}", self.name);
     }
 
     /// Один "шаг жизни" узла — метаболизм, действие, обучение, репликация
@@ -354,7 +427,7 @@ impl Node {
         // === 1. Энергетический decay ===
         {
             let mut e = self.energy.lock().await;
-            e.level = (e.level - DECAY_PER_TICK).max(0.0);
+            e.consume(self.params.decay_per_tick, EnergyFlow::Decay);
         }
 
         // === 2. Смерть при нехватке энергии ===
@@ -370,9 +443,8 @@ impl Node {
             e.level
         };
 
-         
-        let mut rng = StdRng::from_entropy(); // создаём RNG уже после await
-        if rng.gen::<f64>() < self.altruism && energy_level > 1.0 {
+        let roll: f64 = self.rng.lock().await.gen();
+        if roll < self.altruism && energy_level > 1.0 {
             // сотрудничество — передать немного энергии
             let node_list_copy: Vec<Arc<Mutex<Node>>> = {
                 let nodes_locked = nodes_ref.lock().await;
@@ -391,7 +463,7 @@ impl Node {
 
             if !candidates.is_empty() {
                 let target_arc = {
-                    let mut rng = StdRng::from_entropy();
+                    let mut rng = self.rng.lock().await;
                     candidates[rng.gen_range(0..candidates.len())].clone()
                 };
 
@@ -400,17 +472,15 @@ impl Node {
                 }
                 let target_name = target_arc.lock().await.name.clone();
                 let msg = Message::new_energy_transfer(&self.name, &target_name, 5.0);
-                net.send(msg).await;
-
-                let mut my_energy = self.energy.lock().await;
-                my_energy.level = (my_energy.level - 1.0).max(0.0);
+                // энергия спишется у нас, когда получатель примет перевод
+                net.post(msg, &self.identity).await;
                 println!("🔋 {} shared energy with {}", self.name, target_name);
             }
         } else {
             // работа — получить награду
-            let reward = rng.gen_range(2.0..5.0) * (1.0 + self.efficiency);
+            let reward = self.rng.lock().await.gen_range(2.0..5.0) * (1.0 + self.efficiency);
             let mut e = self.energy.lock().await;
-            e.level += reward;
+            e.gain(reward, EnergyFlow::Work);
             action = format!("worked +{:.2}", reward);
         }
         
@@ -421,15 +491,15 @@ impl Node {
         let energy_val = self.energy.lock().await.level;
         println!(
             "🔎 [DEBUG] {} energy before replication check = {:.2} (threshold = {:.2})",
-            self.name, energy_val, REPLICATION_THRESHOLD
+            self.name, energy_val, self.params.replication_threshold
         );
-        if energy_val > REPLICATION_THRESHOLD {
+        if energy_val > self.params.replication_threshold {
              
             let child = self.spawn_child().await;
             {
                 
                 let mut e = self.energy.lock().await;
-                e.level = (e.level - REPRODUCTION_COST).max(0.0);
+                e.consume(self.params.reproduction_cost, EnergyFlow::Reproduction);
                 
             }
             let (child_name, eff, alt) = {
@@ -460,34 +530,109 @@ impl Node {
         let energy = self.energy.lock().await.level;
         if energy > 150.0 {
             self.altruism = (self.altruism + 0.002).min(1.0);
+            self.log_traits();
         } else if energy < 50.0 {
             self.altruism = (self.altruism - 0.002).max(0.0);
+            self.log_traits();
         }
     }
-    async fn spawn_child(&self) -> Arc<Mutex<Node>> {
+    async fn spawn_child(&mut self) -> Arc<Mutex<Node>> {
 
         println!("↪ [tick] node={} before action energy={:.2} altruism={:.2} efficiency={:.2}",  self.name, self.energy.lock().await.level, self.altruism, self.efficiency);
  
-        let child_name = format!("{}_child_{}", self.name, chrono::Utc::now().timestamp_millis());
-
-        // `Node::new` уже возвращает Arc<Mutex<Node>>
-        let child = Node::new(&child_name);
+        // имя потомка детерминировано: родитель + порядковый номер
+        self.children_spawned += 1;
+        let child_name = format!("{}_child_{}", self.name, self.children_spawned);
+
+        // потомок получает свой поток RNG, выведенный из родительского
+        let ledger = self.energy.lock().await.ledger.clone();
+        let mut rng = self.rng.lock().await;
+        let child = Node::new(
+            &child_name,
+            rng::fork(&mut rng),
+            self.params,
+            self.log.clone(),
+            ledger,
+            self.wallet.ledger.clone(),
+            self.clock.clone(),
+            self.keys.clone(),
+            self.data.clone(),
+        );
 
         {
             let parent_energy = { self.energy.lock().await.level };
 
-            // затем создаём RNG
-            let mut rng = StdRng::from_entropy();
             let extra_energy = rng.gen_range(5.0..15.0);
 
             // теперь lock child и применяем
             let mut child_guard = child.lock().await;
-            child_guard.altruism = (self.altruism + rng.gen_range(-MUTATION_RATE..MUTATION_RATE)).clamp(0.0, 1.0);
-            let mut child_energy = child_guard.energy.lock().await;
-            child_energy.level = parent_energy * 0.3;
-            child_energy.level += extra_energy;
+            let rate = self.params.mutation_rate;
+            let mutation = if rate > 0.0 { rng.gen_range(-rate..rate) } else { 0.0 };
+            child_guard.altruism = (self.altruism + mutation).clamp(0.0, 1.0);
+            {
+                // стартовое состояние потомка целиком попадёт в событие NodeSpawned
+                let mut child_energy = child_guard.energy.lock().await;
+                child_energy.level = parent_energy * 0.3;
+                child_energy.level += extra_energy;
+                child_energy.born();
+            }
+            child_guard.log_spawn(Some(&self.name)).await;
         }
+        drop(rng);
         println!("↩ [tick] node={} after action energy={:.2}", self.name, self.energy.lock().await.level);  
         child
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StorageConfig;
+    use crate::rng::Seed;
+
+    async fn spawn(name: &str, keys: &KeyRegistry, data: &DataRoot) -> Identity {
+        let node = Node::new(
+            name,
+            Seed::new(5).stream(&format!("node/{}", name)),
+            NodeConfig::default(),
+            EventLog::disabled(),
+            EnergyLedger::new(),
+            TokenLedger::new(SimClock::new(0, 1000)),
+            SimClock::new(0, 1000),
+            keys.clone(),
+            data.clone(),
+        );
+        let identity = node.lock().await.identity.clone();
+        identity
+    }
+
+    #[tokio::test]
+    async fn seeded_keys_repeat_without_network() {
+        let keys = KeyRegistry::new();
+        let first = spawn("node0", &keys, &DataRoot::disabled()).await;
+        let again = spawn("node0", &KeyRegistry::new(), &DataRoot::disabled()).await;
+        assert_eq!(first.public_key(), again.public_key());
+        assert_eq!(keys.get("node0"), Some(first.public_key()));
+    }
+
+    #[tokio::test]
+    async fn network_keys_are_random_and_kept_in_node_dir() {
+        let dir = std::env::temp_dir().join(format!("organism-identity-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let storage = StorageConfig { enabled: true, dir: dir.clone(), fsync: false, ..StorageConfig::default() };
+        let data = DataRoot::open(&storage).unwrap();
+
+        let seeded = spawn("node0", &KeyRegistry::new(), &DataRoot::disabled()).await;
+        let random = spawn("node0", &KeyRegistry::new().with_random_keys(true), &data).await;
+        assert_ne!(random.public_key(), seeded.public_key());
+
+        // нода открывается заново из своего каталога — с тем же ключом
+        let keys = KeyRegistry::new().with_random_keys(true);
+        let reopened = spawn("node0", &keys, &data).await;
+        assert_eq!(reopened.public_key(), random.public_key());
+        assert_eq!(keys.get("node0"), Some(random.public_key()));
+
+        let other = spawn("node1", &keys, &data).await;
+        assert_ne!(other.public_key(), random.public_key());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
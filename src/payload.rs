@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::chain::Block;
+use crate::consensus::ContributionProof;
+use crate::energy_ledger::EnergyTransfer;
+use crate::merkle::{self, Hash, ProofStep};
+use crate::token_ledger::Entry;
+
+/// 📦 Элемент содержимого блока. Лист дерева Меркла — хэш его компактного JSON.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PayloadItem {
+    /// нейрон, обученный победителем раунда
+    Neuron { node: String, id: u64, neuron_hash: String },
+    /// одна сторона перевода энергии
+    EnergyTransfer(EnergyTransfer),
+    /// проводка из книги токенов
+    TokenTx(Entry),
+}
+
+impl PayloadItem {
+    /// Каноническая запись элемента — ровно эти байты хэшируются в лист
+    pub fn encode(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn leaf(&self) -> Hash {
+        merkle::leaf_hash(self.encode().as_bytes())
+    }
+}
+
+/// Содержимое ключевого блока: `data_root` — корень над `items`,
+/// `key_root` — корень над доказательствами вклада раунда
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BlockBody {
+    pub items: Vec<PayloadItem>,
+    pub proofs: Vec<ContributionProof>,
+}
+
+impl BlockBody {
+    pub fn data_root(&self) -> String {
+        merkle::root_hex(&self.item_leaves())
+    }
+
+    pub fn key_root(&self) -> String {
+        let leaves: Vec<Hash> = self
+            .proofs
+            .iter()
+            .map(|p| merkle::leaf_hash(&serde_json::to_vec(p).unwrap_or_default()))
+            .collect();
+        merkle::root_hex(&leaves)
+    }
+
+    fn item_leaves(&self) -> Vec<Hash> {
+        self.items.iter().map(PayloadItem::leaf).collect()
+    }
+}
+
+/// 🧾 Доказательство включения элемента в блок. Аудитору достаточно заголовка
+/// блока из цепи: SHA-256(0x00 ‖ `encoded`), пройденный по `steps`, должен дать `data_root`.
+#[derive(Clone, Debug, Serialize)]
+pub struct InclusionProof {
+    pub block_index: u64,
+    pub block_hash: String,
+    pub data_root: String,
+    pub item_index: usize,
+    pub item: PayloadItem,
+    /// `item` в канонической записи (порядок полей в JSON ответа может отличаться)
+    pub encoded: String,
+    pub leaf: String,
+    pub steps: Vec<ProofStep>,
+}
+
+impl InclusionProof {
+    /// Лист из `encoded` (это должна быть запись `item`), путь от него — до `data_root`
+    pub fn verify(&self) -> bool {
+        let leaf = merkle::leaf_hash(self.encoded.as_bytes());
+        self.item.encode() == self.encoded
+            && hex::encode(leaf) == self.leaf
+            && merkle::verify(leaf, &self.steps, &self.data_root)
+    }
+}
+
+/// 🗄️ Содержимое ключевых блоков по хэшу блока. Заголовки хранятся в цепях нод,
+/// а содержимое — один раз на организм: `key_chain` у всех нод общая.
+#[derive(Clone, Default)]
+pub struct BlockBodies {
+    inner: Arc<Mutex<BTreeMap<String, BlockBody>>>,
+}
+
+impl BlockBodies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Хранилище из контрольной точки
+    pub fn from_bodies(bodies: BTreeMap<String, BlockBody>) -> Self {
+        Self { inner: Arc::new(Mutex::new(bodies)) }
+    }
+
+    pub fn bodies(&self) -> BTreeMap<String, BlockBody> {
+        self.inner.lock().unwrap().clone()
+    }
+
+    pub fn insert(&self, block_hash: &str, body: BlockBody) {
+        self.inner.lock().unwrap().insert(block_hash.to_string(), body);
+    }
+
+    pub fn get(&self, block_hash: &str) -> Option<BlockBody> {
+        self.inner.lock().unwrap().get(block_hash).cloned()
+    }
+
+    /// Забыть содержимое блока, который так и не вошёл в цепь
+    pub fn remove(&self, block_hash: &str) -> Option<BlockBody> {
+        self.inner.lock().unwrap().remove(block_hash)
+    }
+
+    /// Доказательство для элемента `index` блока; `None`, если содержимого нет
+    /// или оно не сходится с корнем в заголовке
+    pub fn prove(&self, block: &Block, index: usize) -> Option<InclusionProof> {
+        let body = self.get(&block.hash)?;
+        if body.data_root() != block.data_root {
+            return None;
+        }
+        let leaves = body.item_leaves();
+        let steps = merkle::prove(&leaves, index)?;
+        Some(InclusionProof {
+            block_index: block.index,
+            block_hash: block.hash.clone(),
+            data_root: block.data_root.clone(),
+            item_index: index,
+            item: body.items[index].clone(),
+            encoded: body.items[index].encode(),
+            leaf: hex::encode(leaves[index]),
+            steps,
+        })
+    }
+
+    /// Найти проводку токенов с номером `seq` в телах блоков с хэшами `hashes`:
+    /// хэш блока и номер элемента в нём
+    pub fn find_token_tx(&self, hashes: impl IntoIterator<Item = String>, seq: u64) -> Option<(String, usize)> {
+        let bodies = self.inner.lock().unwrap();
+        hashes.into_iter().find_map(|hash| {
+            let body = bodies.get(&hash)?;
+            let index = body
+                .items
+                .iter()
+                .position(|item| matches!(item, PayloadItem::TokenTx(entry) if entry.seq == seq))?;
+            Some((hash, index))
+        })
+    }
+}
+
+/// Запись о нейроне для DataChain: один лист, поэтому `data_root` блока — хэш этого листа
+pub fn neuron_root(node: &str, id: u64, neuron_hash: &str) -> String {
+    let item = PayloadItem::Neuron { node: node.to_string(), id, neuron_hash: neuron_hash.to_string() };
+    merkle::root_hex(&[item.leaf()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::Identity;
+    use crate::rng::Seed;
+
+    fn neuron(id: u64) -> PayloadItem {
+        PayloadItem::Neuron { node: "node0".into(), id, neuron_hash: format!("{:064x}", id) }
+    }
+
+    /// Блок с заголовком над `body`, содержимое которого лежит в хранилище
+    fn stored(body: BlockBody) -> (BlockBodies, Block) {
+        let signer = Identity::derive("node0", &Seed::new(1).stream("node/node0"));
+        let block = Block::new(1, Block::genesis().hash, 1, body.data_root(), body.key_root(), &signer, 1.0);
+        let bodies = BlockBodies::new();
+        bodies.insert(&block.hash, body);
+        (bodies, block)
+    }
+
+    #[test]
+    fn every_item_proves_against_the_header() {
+        let body = BlockBody { items: (0..5).map(neuron).collect(), proofs: Vec::new() };
+        let (bodies, block) = stored(body);
+        for index in 0..5 {
+            let proof = bodies.prove(&block, index).unwrap();
+            assert_eq!(proof.data_root, block.data_root);
+            assert!(proof.verify(), "элемент {}", index);
+        }
+        assert!(bodies.prove(&block, 5).is_none());
+    }
+
+    #[test]
+    fn tampered_proof_is_rejected() {
+        let body = BlockBody { items: (0..3).map(neuron).collect(), proofs: Vec::new() };
+        let (bodies, block) = stored(body);
+        let proof = bodies.prove(&block, 1).unwrap();
+
+        // подменённый элемент: запись и лист больше не сходятся
+        let mut item = proof.clone();
+        item.item = neuron(7);
+        assert!(!item.verify());
+        let mut encoded = proof.clone();
+        encoded.encoded = neuron(7).encode();
+        encoded.item = neuron(7);
+        assert!(!encoded.verify());
+
+        let mut sibling = proof.clone();
+        sibling.steps[0].hash = hex::encode(merkle::leaf_hash(b"other"));
+        assert!(!sibling.verify());
+    }
+
+    #[test]
+    fn body_must_match_the_header() {
+        let (bodies, block) = stored(BlockBody { items: vec![neuron(1)], proofs: Vec::new() });
+        bodies.insert(&block.hash, BlockBody { items: vec![neuron(2)], proofs: Vec::new() });
+        assert!(bodies.prove(&block, 0).is_none());
+    }
+
+    #[test]
+    fn neuron_root_is_its_single_leaf() {
+        assert_eq!(neuron_root("node0", 3, &format!("{:064x}", 3)), hex::encode(neuron(3).leaf()));
+    }
+}
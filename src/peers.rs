@@ -0,0 +1,386 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use crate::config::NetworkConfig;
+
+/// Сколько адресов отдаётся в одном ответе `Peers` и принимается из него
+pub const GOSSIP_LIMIT: usize = 32;
+
+/// Больше стольких адресов таблица не помнит — остальные из сплетен отбрасываются
+const MAX_KNOWN: usize = 256;
+
+/// После стольких неудачных подключений подряд адрес из сплетен забывается
+const MAX_FAILURES: u32 = 5;
+
+/// Штрафы за нарушения; при `network.ban_threshold` пир банится
+pub const PENALTY_MALFORMED: u32 = 10;
+pub const PENALTY_BAD_SIGNATURE: u32 = 20;
+pub const PENALTY_REJECTED: u32 = 20;
+pub const PENALTY_OVERSIZED: u32 = 50;
+
+/// Откуда пир известен
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerSource {
+    /// из `network.peers` — стартовые пиры, не забываются
+    Seed,
+    /// из ответа `Peers` другого пира
+    Gossip,
+    /// подключился к нам сам
+    Inbound,
+}
+
+struct PeerEntry {
+    source: PeerSource,
+    /// нода-шлюз из последнего приветствия
+    gateway: Option<String>,
+    /// открытый ключ шлюза, закреплённый при приветствии, — по нему считаются штрафы
+    key: Option<String>,
+    /// открытые соединения с пиром (их может быть два, если подключились встречно)
+    sessions: u32,
+    dialing: bool,
+    next_dial: Instant,
+    failures: u32,
+    rtt: Option<Duration>,
+    last_seen_tick: Option<u64>,
+}
+
+/// Штраф пира с ключом шлюза
+#[derive(Default)]
+struct Ban {
+    score: u32,
+    until: Option<Instant>,
+}
+
+impl Ban {
+    fn active(&self, now: Instant) -> bool {
+        self.until.is_some_and(|until| until > now)
+    }
+}
+
+impl PeerEntry {
+    fn new(source: PeerSource, now: Instant) -> Self {
+        Self {
+            source,
+            gateway: None,
+            key: None,
+            sessions: 0,
+            dialing: false,
+            next_dial: now,
+            failures: 0,
+            rtt: None,
+            last_seen_tick: None,
+        }
+    }
+}
+
+/// Строка таблицы для `GET /peers`
+#[derive(Clone, Debug, Serialize)]
+pub struct PeerInfo {
+    pub addr: String,
+    pub source: PeerSource,
+    pub gateway: Option<String>,
+    pub connected: bool,
+    pub rtt_ms: Option<u64>,
+    pub last_seen_tick: Option<u64>,
+    pub failures: u32,
+    pub ban_score: u32,
+    /// сколько секунд ещё длится бан
+    pub banned_secs: Option<u64>,
+}
+
+/// 📇 Таблица пиров процесса: стартовые адреса, адреса из сплетен и подключившиеся
+/// сами. Ключ — адрес, который пир слушает. Решает, к кому подключаться
+/// (не больше `network.max_peers` пиров), считает штрафы и баны.
+///
+/// Адрес пир сообщает о себе сам, поэтому штрафы и баны привязаны не к нему,
+/// а к открытому ключу шлюза из приветствия: сменив заявленный адрес, бан
+/// не обойти, а заявив чужой адрес — не подставить под бан его хозяина.
+pub struct PeerTable {
+    own: String,
+    max_peers: usize,
+    ban_threshold: u32,
+    ban_for: Duration,
+    retry: Duration,
+    peers: BTreeMap<String, PeerEntry>,
+    /// штрафы по ключам шлюзов
+    bans: BTreeMap<String, Ban>,
+}
+
+impl PeerTable {
+    pub fn new(config: &NetworkConfig) -> Self {
+        let now = Instant::now();
+        let peers = config
+            .peers
+            .iter()
+            .filter(|addr| **addr != config.listen)
+            .map(|addr| (addr.clone(), PeerEntry::new(PeerSource::Seed, now)))
+            .collect();
+        Self {
+            own: config.listen.clone(),
+            max_peers: config.max_peers,
+            ban_threshold: config.ban_threshold,
+            ban_for: Duration::from_secs(config.ban_secs),
+            retry: Duration::from_secs(config.keepalive_secs),
+            peers,
+            bans: BTreeMap::new(),
+        }
+    }
+
+    /// Запомнить адрес; `false`, если он свой, некорректный, уже известен или таблица полна
+    pub fn learn(&mut self, addr: &str, source: PeerSource) -> bool {
+        if addr == self.own || addr.parse::<SocketAddr>().is_err() || self.peers.contains_key(addr) {
+            return false;
+        }
+        if self.peers.len() >= MAX_KNOWN {
+            return false;
+        }
+        self.peers.insert(addr.to_string(), PeerEntry::new(source, Instant::now()));
+        true
+    }
+
+    /// 📥 Под каким адресом учесть подключившегося к нам пира. Заявленному адресу
+    /// верим, только если он на том же IP, что и сокет, не наш и не закреплён
+    /// за другим ключом; иначе пир учитывается по адресу сокета. Неизвестный
+    /// адрес проходит те же проверки, что и в `learn`.
+    pub fn inbound(&mut self, listen: &str, remote: &str, key: &str) -> Result<String, String> {
+        let same_host = match (listen.parse::<SocketAddr>(), remote.parse::<SocketAddr>()) {
+            (Ok(listen), Ok(remote)) => listen.ip() == remote.ip(),
+            _ => false,
+        };
+        let claimed = same_host
+            && listen != self.own
+            && self.peers.get(listen).is_none_or(|p| p.key.as_deref().is_none_or(|k| k == key));
+        let addr = if claimed { listen } else { remote };
+        if !self.peers.contains_key(addr) && !self.learn(addr, PeerSource::Inbound) {
+            return Err(format!("адрес {} не принят: таблица пиров полна", addr));
+        }
+        Ok(addr.to_string())
+    }
+
+    fn banned(&self, key: Option<&str>, now: Instant) -> bool {
+        key.and_then(|key| self.bans.get(key)).is_some_and(|ban| ban.active(now))
+    }
+
+    fn connected_count(&self) -> usize {
+        self.peers.values().filter(|p| p.sessions > 0 || p.dialing).count()
+    }
+
+    /// 📞 Кому звонить сейчас: не подключённые, не забаненные, с истёкшей паузой —
+    /// не больше свободных мест. Выбранные помечаются как «дозваниваемся».
+    pub fn dial_candidates(&mut self) -> Vec<String> {
+        let now = Instant::now();
+        let free = self.max_peers.saturating_sub(self.connected_count());
+        let chosen: Vec<String> = self
+            .peers
+            .iter()
+            .filter(|(_, p)| p.sessions == 0 && !p.dialing && !self.banned(p.key.as_deref(), now) && p.next_dial <= now)
+            .map(|(addr, _)| addr.clone())
+            .take(free)
+            .collect();
+        for addr in chosen.iter() {
+            if let Some(peer) = self.peers.get_mut(addr) {
+                peer.dialing = true;
+            }
+        }
+        chosen
+    }
+
+    /// Подключение не удалось: пауза растёт вдвое, безнадёжный адрес из сплетен забывается
+    pub fn dial_failed(&mut self, addr: &str) {
+        let Some(peer) = self.peers.get_mut(addr) else {
+            return;
+        };
+        peer.dialing = false;
+        peer.failures += 1;
+        if peer.source != PeerSource::Seed && peer.failures >= MAX_FAILURES {
+            self.peers.remove(addr);
+            return;
+        }
+        peer.next_dial = Instant::now() + self.retry * 2u32.pow(peer.failures.min(5));
+    }
+
+    /// Можно ли открыть соединение с пиром `addr`, шлюз которого поздоровался ключом `key`
+    pub fn admit(&self, addr: &str, key: &str) -> Result<(), String> {
+        let now = Instant::now();
+        if self.banned(Some(key), now) {
+            return Err(format!("пир {} забанен", addr));
+        }
+        let peer = self.peers.get(addr);
+        let already = peer.is_some_and(|p| p.sessions > 0 || p.dialing);
+        if !already && self.connected_count() >= self.max_peers {
+            return Err(format!("достигнут предел в {} пиров", self.max_peers));
+        }
+        Ok(())
+    }
+
+    /// Соединение открыто. Адрес уже должен быть в таблице (`learn`, `inbound`)
+    pub fn connected(&mut self, addr: &str, gateway: &str, key: &str, tick: u64) {
+        let Some(peer) = self.peers.get_mut(addr) else {
+            return;
+        };
+        peer.dialing = false;
+        peer.failures = 0;
+        peer.sessions += 1;
+        peer.gateway = Some(gateway.to_string());
+        peer.key = Some(key.to_string());
+        peer.last_seen_tick = Some(tick);
+    }
+
+    /// Соединение закрыто; следующий звонок — не раньше чем через интервал keepalive
+    pub fn disconnected(&mut self, addr: &str) {
+        if let Some(peer) = self.peers.get_mut(addr) {
+            peer.sessions = peer.sessions.saturating_sub(1);
+            peer.next_dial = Instant::now() + self.retry;
+        }
+    }
+
+    /// Пир подал голос; `rtt` — если это ответ на наш `Ping`
+    pub fn seen(&mut self, addr: &str, tick: u64, rtt: Option<Duration>) {
+        if let Some(peer) = self.peers.get_mut(addr) {
+            peer.last_seen_tick = Some(tick);
+            if rtt.is_some() {
+                peer.rtt = rtt;
+            }
+        }
+    }
+
+    /// ⚖️ Начислить штраф пиру с ключом шлюза `key`. `true` — пир только что
+    /// забанен: соединения с ним надо закрыть. По истечении бана штраф обнуляется.
+    pub fn penalize(&mut self, key: &str, score: u32) -> bool {
+        let now = Instant::now();
+        if !self.bans.contains_key(key) && self.bans.len() >= MAX_KNOWN {
+            // место под новый ключ — за счёт тех, кто сейчас не забанен
+            self.bans.retain(|_, ban| ban.active(now));
+            if self.bans.len() >= MAX_KNOWN {
+                return false;
+            }
+        }
+        let ban = self.bans.entry(key.to_string()).or_default();
+        if ban.active(now) {
+            return false;
+        }
+        if ban.until.take().is_some() {
+            ban.score = 0;
+        }
+        ban.score += score;
+        if ban.score < self.ban_threshold {
+            return false;
+        }
+        ban.until = Some(now + self.ban_for);
+        true
+    }
+
+    /// Адреса для ответа на `GetPeers`: живые и не забаненные, кроме спрашивающего
+    pub fn gossip(&self, except: &str) -> Vec<String> {
+        let now = Instant::now();
+        self.peers
+            .iter()
+            .filter(|(addr, p)| {
+                *addr != except && !self.banned(p.key.as_deref(), now) && (p.sessions > 0 || p.failures == 0)
+            })
+            .map(|(addr, _)| addr.clone())
+            .take(GOSSIP_LIMIT)
+            .collect()
+    }
+
+    /// Адреса пиров, с которыми открыто соединение
+    pub fn connected_addrs(&self) -> Vec<String> {
+        self.peers.iter().filter(|(_, p)| p.sessions > 0).map(|(addr, _)| addr.clone()).collect()
+    }
+
+    pub fn list(&self) -> Vec<PeerInfo> {
+        let now = Instant::now();
+        self.peers
+            .iter()
+            .map(|(addr, p)| {
+                let ban = p.key.as_ref().and_then(|key| self.bans.get(key));
+                PeerInfo {
+                    addr: addr.clone(),
+                    source: p.source,
+                    gateway: p.gateway.clone(),
+                    connected: p.sessions > 0,
+                    rtt_ms: p.rtt.map(|rtt| rtt.as_millis() as u64),
+                    last_seen_tick: p.last_seen_tick,
+                    failures: p.failures,
+                    ban_score: ban.map_or(0, |b| b.score),
+                    banned_secs: ban
+                        .and_then(|b| b.until)
+                        .filter(|until| *until > now)
+                        .map(|until| (until - now).as_secs()),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(seeds: &[&str]) -> PeerTable {
+        let config = NetworkConfig {
+            listen: "10.0.0.1:4000".into(),
+            peers: seeds.iter().map(|s| s.to_string()).collect(),
+            ban_threshold: 100,
+            ..NetworkConfig::default()
+        };
+        PeerTable::new(&config)
+    }
+
+    #[test]
+    fn inbound_cannot_claim_an_address_on_another_host() {
+        let mut table = table(&["10.0.0.2:4000"]);
+        // пир с 10.0.0.9 выдаёт себя за стартового пира
+        let addr = table.inbound("10.0.0.2:4000", "10.0.0.9:51000", "evil").unwrap();
+        assert_eq!(addr, "10.0.0.9:51000");
+        table.connected(&addr, "x_node0", "evil", 1);
+
+        let seed = table.list().into_iter().find(|p| p.addr == "10.0.0.2:4000").unwrap();
+        assert!(!seed.connected);
+        assert_eq!(seed.gateway, None);
+
+        // свой адрес тоже не заявить
+        assert_eq!(table.inbound("10.0.0.1:4000", "10.0.0.1:52000", "evil").unwrap(), "10.0.0.1:52000");
+    }
+
+    #[test]
+    fn claimed_address_keeps_its_key() {
+        let mut table = table(&[]);
+        let addr = table.inbound("10.0.0.3:4000", "10.0.0.3:51000", "honest").unwrap();
+        assert_eq!(addr, "10.0.0.3:4000");
+        table.connected(&addr, "b_node0", "honest", 1);
+
+        // другой ключ с того же хоста под тем же адресом не записывается
+        assert_eq!(table.inbound("10.0.0.3:4000", "10.0.0.3:52000", "other").unwrap(), "10.0.0.3:52000");
+        assert_eq!(table.inbound("10.0.0.3:4000", "10.0.0.3:53000", "honest").unwrap(), "10.0.0.3:4000");
+    }
+
+    #[test]
+    fn ban_follows_the_key_not_the_address() {
+        let mut table = table(&["10.0.0.2:4000"]);
+        assert!(!table.penalize("evil", PENALTY_OVERSIZED));
+        assert!(table.penalize("evil", PENALTY_OVERSIZED));
+
+        // новый заявленный адрес бан не снимает
+        let addr = table.inbound("10.0.0.9:4001", "10.0.0.9:51000", "evil").unwrap();
+        assert!(table.admit(&addr, "evil").is_err());
+        // хозяин адреса, который злоумышленник мог заявить, не пострадал
+        assert!(table.admit("10.0.0.2:4000", "seed").is_ok());
+    }
+
+    #[test]
+    fn full_table_refuses_unknown_inbound() {
+        let mut table = table(&[]);
+        for i in 0..MAX_KNOWN {
+            assert!(table.learn(&format!("10.1.{}.{}:4000", i / 250, i % 250 + 1), PeerSource::Gossip));
+        }
+        assert!(table.inbound("10.0.0.7:4000", "10.0.0.7:51000", "late").is_err());
+        assert_eq!(table.list().len(), MAX_KNOWN);
+        // подключение без записи в таблицу ничего не добавляет
+        table.connected("10.0.0.7:4000", "c_node0", "late", 1);
+        assert_eq!(table.list().len(), MAX_KNOWN);
+    }
+}
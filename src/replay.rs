@@ -0,0 +1,366 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::events::{ChainKind, DeathCause, Event, LogRecord};
+use crate::token_amount::TokenAmount;
+
+/// Параметры воспроизведения журнала: `--replay путь [--until N]`
+#[derive(Clone, Debug)]
+pub struct ReplayOptions {
+    pub path: PathBuf,
+    /// последний тик, события которого применяются (`None` — весь журнал)
+    pub until: Option<u64>,
+}
+
+/// Нода, восстановленная из журнала
+#[derive(Clone, Debug, Serialize)]
+pub struct NodeReplay {
+    pub parent: Option<String>,
+    pub born_at: u64,
+    pub energy: f64,
+    pub balance: TokenAmount,
+    pub efficiency: f64,
+    pub altruism: f64,
+    pub resilience: f64,
+    pub experience: f64,
+    pub data_chain_height: usize,
+    pub key_chain_height: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DeathRecord {
+    pub node: String,
+    pub tick: u64,
+    pub energy: f64,
+    pub cause: DeathCause,
+}
+
+/// ⏪ Состояние организма, собранное из журнала событий
+#[derive(Clone, Debug, Serialize)]
+pub struct ReplayState {
+    /// тик последнего применённого события
+    pub tick: u64,
+    pub events_applied: u64,
+    pub population: usize,
+    pub fund: TokenAmount,
+    pub aggressiveness: f64,
+    pub last_decision: Option<String>,
+    /// высота общей key_chain по раундам консенсуса
+    pub key_height: u64,
+    pub last_winner: Option<String>,
+    /// объявленные, но не принятые ключевые блоки
+    pub blocks_rejected: u64,
+    pub births: u64,
+    /// сбои доставки, внесённые симулятором сети
+    pub net_faults: u64,
+    /// исполненные просьбы о помощи и запросы, закрытые без ответа
+    pub help_settled: u64,
+    pub requests_expired: u64,
+    /// разделения сети, действующие на момент `tick`
+    pub partitions: Vec<usize>,
+    pub nodes: BTreeMap<String, NodeReplay>,
+    pub deaths: Vec<DeathRecord>,
+}
+
+impl Default for ReplayState {
+    fn default() -> Self {
+        Self {
+            tick: 0,
+            events_applied: 0,
+            population: 0,
+            fund: TokenAmount::ZERO,
+            aggressiveness: 1.0, // как у нового Brain
+            last_decision: None,
+            key_height: 1, // генезис
+            last_winner: None,
+            blocks_rejected: 0,
+            births: 0,
+            net_faults: 0,
+            help_settled: 0,
+            requests_expired: 0,
+            partitions: Vec::new(),
+            nodes: BTreeMap::new(),
+            deaths: Vec::new(),
+        }
+    }
+}
+
+impl ReplayState {
+    /// Применить одну запись журнала
+    pub fn apply(&mut self, record: LogRecord) {
+        let tick = record.tick;
+        match record.event {
+            Event::NodeSpawned {
+                node,
+                parent,
+                energy,
+                balance,
+                efficiency,
+                altruism,
+                resilience,
+                experience,
+                data_chain_height,
+                key_chain_height,
+            } => {
+                if parent.is_some() {
+                    self.births += 1;
+                }
+                self.key_height = self.key_height.max(key_chain_height as u64);
+                self.nodes.insert(
+                    node,
+                    NodeReplay {
+                        parent,
+                        born_at: tick,
+                        energy,
+                        balance,
+                        efficiency,
+                        altruism,
+                        resilience,
+                        experience,
+                        data_chain_height,
+                        key_chain_height,
+                    },
+                );
+            }
+            Event::NodeDied { node, energy, cause } => {
+                self.nodes.remove(&node);
+                self.deaths.push(DeathRecord { node, tick, energy, cause });
+            }
+            Event::TraitsChanged { node, efficiency, altruism, resilience, experience } => {
+                if let Some(n) = self.nodes.get_mut(&node) {
+                    n.efficiency = efficiency;
+                    n.altruism = altruism;
+                    n.resilience = resilience;
+                    n.experience = experience;
+                }
+            }
+            Event::EnergyConsumed { node, level, .. }
+            | Event::EnergyRestored { node, level, .. }
+            | Event::EnergySet { node, level, .. } => {
+                if let Some(n) = self.nodes.get_mut(&node) {
+                    n.energy = level;
+                }
+            }
+            Event::WalletReward { node, balance, .. } | Event::WalletSpend { node, balance, .. } => {
+                if let Some(n) = self.nodes.get_mut(&node) {
+                    n.balance = balance;
+                }
+            }
+            Event::FundAdd { total, .. } | Event::FundWithdraw { total, .. } => self.fund = total,
+            Event::BlockCommitted { node, chain, index, .. } => {
+                if let Some(n) = self.nodes.get_mut(&node) {
+                    let height = index as usize + 1;
+                    match chain {
+                        ChainKind::Data => n.data_chain_height = height,
+                        ChainKind::Key => n.key_chain_height = height,
+                    }
+                }
+            }
+            // выпавшие блоки учтены итоговой высотой в `ChainReorg`
+            Event::BlockOrphaned { .. } => {}
+            Event::ChainReorg { node, chain, height, .. } => {
+                if let Some(n) = self.nodes.get_mut(&node) {
+                    match chain {
+                        ChainKind::Data => n.data_chain_height = height,
+                        ChainKind::Key => n.key_chain_height = height,
+                    }
+                }
+            }
+            Event::ConsensusRound { height, winner, .. } => {
+                self.key_height = height + 1;
+                self.last_winner = Some(winner);
+            }
+            // высота растёт только с `ConsensusRound`
+            Event::BlockProposed { .. } | Event::BlockFinalized { .. } => {}
+            Event::BlockRejected { .. } => self.blocks_rejected += 1,
+            Event::BrainDecision { action, .. } => self.last_decision = Some(action),
+            Event::BrainAdapted { aggressiveness, .. } => self.aggressiveness = aggressiveness,
+            // сами переводы уже записаны событиями энергии
+            Event::EnergyRedistributed { .. } => {}
+            // сам перевод уже записан событиями энергии
+            Event::HelpSettled { .. } => self.help_settled += 1,
+            Event::RequestExpired { .. } => self.requests_expired += 1,
+            Event::NetFault { .. } => self.net_faults += 1,
+            Event::PartitionStarted { index, .. } => self.partitions.push(index),
+            Event::PartitionHealed { index } => self.partitions.retain(|i| *i != index),
+            Event::Restored { fund, aggressiveness } => {
+                // дальше идут NodeSpawned всех восстановленных нод
+                self.nodes.clear();
+                self.fund = fund;
+                self.aggressiveness = aggressiveness;
+            }
+        }
+        self.tick = tick;
+        self.events_applied += 1;
+        self.population = self.nodes.len();
+    }
+}
+
+/// ⏪ Пересобрать состояние из журнала до тика `until` включительно
+pub fn replay(path: &Path, until: Option<u64>) -> Result<ReplayState, String> {
+    let file = File::open(path).map_err(|e| format!("журнал {}: {}", path.display(), e))?;
+    let mut state = ReplayState::default();
+
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("журнал {}: {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: LogRecord = serde_json::from_str(&line)
+            .map_err(|e| format!("журнал {}, строка {}: {}", path.display(), i + 1, e))?;
+        if until.is_some_and(|t| record.tick > t) {
+            break;
+        }
+        state.apply(record);
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimClock;
+    use crate::energy_ledger::EnergyFlow;
+    use crate::events::EventLog;
+
+    fn spawned(node: &str, parent: Option<&str>, energy: f64) -> Event {
+        Event::NodeSpawned {
+            node: node.into(),
+            parent: parent.map(|p| p.into()),
+            energy,
+            balance: TokenAmount::ZERO,
+            efficiency: 1.0,
+            altruism: 0.5,
+            resilience: 0.5,
+            experience: 0.0,
+            data_chain_height: 1,
+            key_chain_height: 1,
+        }
+    }
+
+    fn tokens(s: &str) -> TokenAmount {
+        s.parse().unwrap()
+    }
+
+    /// Короткий журнал: две ноды, потомок, раунд консенсуса, смерть и движение фонда
+    fn write_log(path: &Path) {
+        let clock = SimClock::new(0, 100);
+        let log = EventLog::open(path, clock.clone(), None).unwrap();
+        log.emit(spawned("node0", None, 100.0));
+        log.emit(spawned("node1", None, 100.0));
+
+        clock.set_tick(1);
+        log.emit(Event::EnergyConsumed { node: "node0".into(), amount: 20.0, level: 80.0, flow: EnergyFlow::Decay });
+        log.emit(Event::WalletReward { node: "node1".into(), amount: tokens("2.5"), balance: tokens("2.5") });
+        log.emit(Event::FundAdd { amount: tokens("1.5"), total: tokens("1.5") });
+
+        clock.set_tick(2);
+        log.emit(spawned("node2", Some("node0"), 40.0));
+        log.emit(Event::ConsensusRound {
+            height: 1,
+            winner: "node1".into(),
+            weight: 1.0,
+            total_weight: 2.0,
+            participants: 3,
+            hash: "h1".into(),
+        });
+        log.emit(Event::BlockCommitted {
+            node: "node1".into(),
+            chain: ChainKind::Key,
+            index: 1,
+            validator: "node1".into(),
+            hash: "h1".into(),
+        });
+
+        clock.set_tick(3);
+        log.emit(Event::NodeDied { node: "node0".into(), energy: 4.0, cause: DeathCause::Starved });
+        log.emit(Event::FundWithdraw { amount: tokens("1"), total: tokens("0.5") });
+        log.emit(Event::BrainAdapted { result: 1.0, aggressiveness: 0.8 });
+        log.flush();
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("organism-replay-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("events.jsonl")
+    }
+
+    #[test]
+    fn full_log_rebuilds_population_balances_and_fund() {
+        let path = scratch("full");
+        write_log(&path);
+
+        let state = replay(&path, None).unwrap();
+        assert_eq!(state.tick, 3);
+        assert_eq!(state.events_applied, 11);
+        assert_eq!(state.population, 2);
+        assert_eq!(state.nodes.keys().collect::<Vec<_>>(), ["node1", "node2"]);
+        assert_eq!(state.nodes["node1"].balance, tokens("2.5"));
+        assert_eq!(state.nodes["node1"].key_chain_height, 2);
+        assert_eq!(state.nodes["node2"].parent.as_deref(), Some("node0"));
+        assert_eq!(state.nodes["node2"].born_at, 2);
+        assert_eq!(state.fund, tokens("0.5"));
+        assert_eq!(state.births, 1);
+        assert_eq!(state.key_height, 2);
+        assert_eq!(state.last_winner.as_deref(), Some("node1"));
+        assert_eq!(state.aggressiveness, 0.8);
+        assert_eq!(state.deaths.len(), 1);
+        assert_eq!((state.deaths[0].node.as_str(), state.deaths[0].tick), ("node0", 3));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn until_stops_after_the_given_tick() {
+        let path = scratch("until");
+        write_log(&path);
+
+        let state = replay(&path, Some(1)).unwrap();
+        assert_eq!(state.tick, 1);
+        assert_eq!(state.events_applied, 5);
+        assert_eq!(state.population, 2);
+        assert_eq!(state.nodes["node0"].energy, 80.0);
+        assert_eq!(state.nodes["node1"].balance, tokens("2.5"));
+        assert_eq!(state.fund, tokens("1.5"));
+        assert_eq!(state.births, 0);
+        assert_eq!(state.key_height, 1);
+        assert!(state.deaths.is_empty());
+
+        // тик 2 применяется целиком, смерть на тике 3 — ещё нет
+        let state = replay(&path, Some(2)).unwrap();
+        assert_eq!(state.population, 3);
+        assert_eq!(state.key_height, 2);
+        assert_eq!(state.fund, tokens("1.5"));
+
+        assert_eq!(replay(&path, Some(0)).unwrap().population, 2);
+        assert_eq!(replay(&path, Some(100)).unwrap().events_applied, 11);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn restored_run_replaces_the_population() {
+        let mut state = ReplayState::default();
+        state.apply(LogRecord { seq: 1, tick: 0, event: spawned("node0", None, 100.0) });
+        state.apply(LogRecord { seq: 2, tick: 5, event: Event::Restored { fund: tokens("7"), aggressiveness: 0.5 } });
+        state.apply(LogRecord { seq: 3, tick: 5, event: spawned("node9", None, 60.0) });
+        assert_eq!(state.nodes.keys().collect::<Vec<_>>(), ["node9"]);
+        assert_eq!(state.fund, tokens("7"));
+        assert_eq!(state.aggressiveness, 0.5);
+        assert_eq!(state.population, 1);
+    }
+
+    #[test]
+    fn broken_logs_are_reported() {
+        let path = scratch("broken");
+        assert!(replay(&path, None).unwrap_err().contains("events.jsonl"));
+        write_log(&path);
+        let mut text = std::fs::read_to_string(&path).unwrap();
+        text.push_str("{\"seq\": 12, \"tick\": 4, \"event\": \"nonsense\"}\n");
+        std::fs::write(&path, text).unwrap();
+        assert!(replay(&path, None).unwrap_err().contains("строка 12"));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,41 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use sha2::{Digest, Sha256};
+
+/// 🎲 Генератор случайных чисел, которым пользуются все подсистемы.
+/// ChaCha8 даёт одинаковую последовательность на любой платформе.
+pub type SimRng = ChaCha8Rng;
+
+/// Главный seed симуляции — из него выводятся потоки для нод и подсистем.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Seed(u64);
+
+impl Seed {
+    pub fn new(value: u64) -> Self {
+        Self(value)
+    }
+
+    /// Случайный seed (когда пользователь его не задал)
+    pub fn random() -> Self {
+        Self(rand::thread_rng().gen())
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+
+    /// Отдельный поток RNG для подсистемы или ноды.
+    /// Один и тот же `label` при одном seed всегда даёт одну и ту же последовательность.
+    pub fn stream(&self, label: &str) -> SimRng {
+        let mut hasher = Sha256::new();
+        hasher.update(self.0.to_le_bytes());
+        hasher.update(label.as_bytes());
+        let digest: [u8; 32] = hasher.finalize().into();
+        SimRng::from_seed(digest)
+    }
+}
+
+/// Дочерний поток RNG, выведенный из родительского (для потомков нод)
+pub fn fork(parent: &mut SimRng) -> SimRng {
+    SimRng::from_seed(parent.gen())
+}
@@ -0,0 +1,517 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+
+use crate::brain::BrainSnapshot;
+use crate::checkpoint::{self, Checkpoint, CheckpointError, CheckpointHandle, CheckpointReply, StreamState};
+use crate::economy_cycle::EconomyCycle;
+use crate::energy_evolution::EnergyEvolution;
+use crate::events::Event;
+use crate::interaction::{handle_message, Directory, Message, MessageType};
+use crate::config::{CheckpointConfig, ConsensusConfig, EconomyConfig};
+use crate::consensus;
+use crate::rng::{Seed, SimRng};
+use crate::world::World;
+
+/// Как идёт время симуляции
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RunMode {
+    /// один тик = `tick_ms` реального времени
+    #[serde(rename = "realtime")]
+    RealTime,
+    /// тики идут подряд без пауз
+    #[serde(rename = "fast")]
+    AsFastAsPossible,
+}
+
+impl RunMode {
+    /// Имя режима в конфиге
+    pub fn name(&self) -> &'static str {
+        match self {
+            RunMode::RealTime => "realtime",
+            RunMode::AsFastAsPossible => "fast",
+        }
+    }
+}
+
+/// Фазы глобального тика — ровно в этом порядке
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    HelpSignals,
+    NodeTicks,
+    Consensus,
+    Evolution,
+    Economy,
+    Brain,
+    Delivery,
+    Snapshot,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 8] = [
+        Phase::HelpSignals,
+        Phase::NodeTicks,
+        Phase::Consensus,
+        Phase::Evolution,
+        Phase::Economy,
+        Phase::Brain,
+        Phase::Delivery,
+        Phase::Snapshot,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::HelpSignals => "help",
+            Phase::NodeTicks => "nodes",
+            Phase::Consensus => "consensus",
+            Phase::Evolution => "evolution",
+            Phase::Economy => "economy",
+            Phase::Brain => "brain",
+            Phase::Delivery => "delivery",
+            Phase::Snapshot => "snapshot",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Phase> {
+        Phase::ALL.into_iter().find(|p| p.name() == name)
+    }
+}
+
+/// Настройки планировщика: режим и период каждой фазы (в тиках, 0 = выключена)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulerConfig {
+    pub mode: RunMode,
+    pub tick_ms: u64,
+    pub help_every: u64,
+    pub nodes_every: u64,
+    pub consensus_every: u64,
+    pub evolution_every: u64,
+    pub economy_every: u64,
+    pub brain_every: u64,
+    pub delivery_every: u64,
+    pub snapshot_every: u64,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            mode: RunMode::RealTime,
+            tick_ms: 1000,
+            help_every: 5,
+            nodes_every: 10,
+            consensus_every: 10,
+            evolution_every: 10,
+            economy_every: 15,
+            brain_every: 5,
+            delivery_every: 1,
+            snapshot_every: 10,
+        }
+    }
+}
+
+impl SchedulerConfig {
+    pub fn period(&self, phase: Phase) -> u64 {
+        match phase {
+            Phase::HelpSignals => self.help_every,
+            Phase::NodeTicks => self.nodes_every,
+            Phase::Consensus => self.consensus_every,
+            Phase::Evolution => self.evolution_every,
+            Phase::Economy => self.economy_every,
+            Phase::Brain => self.brain_every,
+            Phase::Delivery => self.delivery_every,
+            Phase::Snapshot => self.snapshot_every,
+        }
+    }
+
+    /// Фазы тика `tick` в порядке исполнения
+    pub fn due(&self, tick: u64) -> Vec<Phase> {
+        Phase::ALL
+            .into_iter()
+            .filter(|phase| {
+                let every = self.period(*phase);
+                every > 0 && tick.is_multiple_of(every)
+            })
+            .collect()
+    }
+}
+
+/// ⏲️ Центральный планировщик: один пронумерованный глобальный тик
+/// прогоняет фазы организма в фиксированном порядке.
+pub struct Scheduler {
+    pub config: SchedulerConfig,
+    pub economy: EconomyConfig,
+    pub consensus: ConsensusConfig,
+    pub checkpoint: CheckpointConfig,
+    pub world: World,
+    seed: Seed,
+    evolution_rng: SimRng,
+    economy_rng: SimRng,
+    handle: CheckpointHandle,
+    requests: mpsc::Receiver<CheckpointReply>,
+}
+
+impl Scheduler {
+    pub fn new(
+        config: SchedulerConfig,
+        economy: EconomyConfig,
+        consensus: ConsensusConfig,
+        checkpoint: CheckpointConfig,
+        seed: Seed,
+        world: World,
+    ) -> Self {
+        let (handle, requests) = CheckpointHandle::channel();
+        Self {
+            config,
+            economy,
+            consensus,
+            checkpoint,
+            world,
+            seed,
+            evolution_rng: seed.stream("evolution"),
+            economy_rng: seed.stream("economy"),
+            handle,
+            requests,
+        }
+    }
+
+    /// Продолжить потоки RNG с места, сохранённого в контрольной точке
+    pub fn restore_streams(&mut self, streams: StreamState) {
+        self.evolution_rng = streams.evolution;
+        self.economy_rng = streams.economy;
+    }
+
+    /// Через этот хэндл API просит снять контрольную точку между тиками
+    pub fn checkpoint_handle(&self) -> CheckpointHandle {
+        self.handle.clone()
+    }
+
+    /// 💾 Сохранить мир в `checkpoint.dir` и убрать лишние старые файлы
+    pub async fn save_checkpoint(&self) -> Result<PathBuf, CheckpointError> {
+        let streams = StreamState {
+            evolution: self.evolution_rng.clone(),
+            economy: self.economy_rng.clone(),
+        };
+        let snapshot = Checkpoint::capture(self.seed.value(), &self.world, streams).await;
+        let path = self.checkpoint.dir.join(Checkpoint::file_name(snapshot.tick));
+        snapshot.save(&path)?;
+
+        if let Err(e) = checkpoint::prune(&self.checkpoint.dir, self.checkpoint.keep) {
+            eprintln!("⚠️ Не удалось удалить старые контрольные точки: {}", e);
+        }
+        println!("💾 Контрольная точка тика {} → {}", snapshot.tick, path.display());
+        Ok(path)
+    }
+
+    /// Ответить на запрос `POST /checkpoint`
+    async fn answer_checkpoint(&self, reply: CheckpointReply) {
+        let result = self
+            .save_checkpoint()
+            .await
+            .map(|path| (self.tick(), path))
+            .map_err(|e| e.to_string());
+        let _ = reply.send(result);
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.world.clock.tick()
+    }
+
+    /// Выполнить один глобальный тик
+    pub async fn step(&mut self) {
+        let tick = self.tick() + 1;
+        self.world.clock.set_tick(tick);
+
+        // 🔋 Отсчёт для проверки сохранения энергии — до первого тика
+        let ledger = self.world.energy.clone();
+        if !ledger.has_baseline() {
+            ledger.set_baseline(self.world.total_energy().await);
+        }
+
+        for phase in self.config.due(tick) {
+            self.run_phase(phase, tick).await;
+        }
+
+        // ⚖️ Всё изменение энергии за тик должно быть объяснено потоками
+        if let Some(drift) = ledger.close_tick(tick, self.world.total_energy().await) {
+            eprintln!(
+                "⚠️ [EnergyLedger] тик {}: необъяснённый дрейф {:+.6} (ожидалось {:.4}, есть {:.4})",
+                drift.tick, drift.drift, drift.expected, drift.actual
+            );
+        }
+
+        // 📜 События тика — на диск до контрольной точки
+        self.world.log.flush();
+        self.world.net.messages().flush();
+
+        // 💾 Автоматическая контрольная точка — уже после всех фаз тика
+        let every = self.checkpoint.every;
+        if every > 0 && tick.is_multiple_of(every) {
+            if let Err(e) = self.save_checkpoint().await {
+                eprintln!("❌ {}", e);
+            }
+        }
+    }
+
+    /// Бесконечный цикл тиков в выбранном режиме
+    pub async fn run(mut self) {
+        println!(
+            "⏲️ Планировщик запущен: режим {:?}, тик = {} мс",
+            self.config.mode, self.config.tick_ms
+        );
+        match self.config.mode {
+            RunMode::RealTime => {
+                let mut ticker = interval(Duration::from_millis(self.config.tick_ms));
+                ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                loop {
+                    tokio::select! {
+                        _ = ticker.tick() => self.step().await,
+                        Some(reply) = self.requests.recv() => self.answer_checkpoint(reply).await,
+                    }
+                }
+            }
+            RunMode::AsFastAsPossible => loop {
+                self.step().await;
+                while let Ok(reply) = self.requests.try_recv() {
+                    self.answer_checkpoint(reply).await;
+                }
+                // даём шанс API и другим задачам
+                tokio::task::yield_now().await;
+            },
+        }
+    }
+
+    async fn run_phase(&mut self, phase: Phase, tick: u64) {
+        let world = self.world.clone();
+        match phase {
+            // 🔁 Сигнал помощи от очередной ноды — запрос, который ждёт
+            // предложений `simulation.request_timeout_ticks` тиков
+            Phase::HelpSignals => {
+                let nodes = world.nodes.lock().await;
+                if nodes.is_empty() {
+                    return;
+                }
+                let idx = (tick / self.config.help_every) as usize % nodes.len();
+                let sender = nodes[idx].lock().await;
+                let msg = Message::new(
+                    &sender.name,
+                    None,
+                    MessageType::HelpRequest,
+                    0.0,
+                    Some("Мне нужна энергия ⚡"),
+                );
+                // исход виден по событиям и `GET /bus`, сам ответ фаза не ждёт
+                let (id, _) = world.net.open_request(msg, &sender.identity, tick).await;
+                println!("📡 {} отправил сигнал помощи #{}", sender.name, id);
+            }
+
+            // 🧬 Жизненный цикл нод: метаболизм, действие, репликация, отбор
+            Phase::NodeTicks => {
+                let mut brain = world.brain.write().await;
+                brain.evolve_network(world.nodes.clone(), world.fund.clone(), world.net.clone()).await;
+            }
+
+            // 🗳️ Раунд Proof-of-Contribution: один ключевой блок на высоту у всех нод
+            Phase::Consensus => {
+                let nodes = world.nodes.lock().await.clone();
+                consensus::run_round(&nodes, &self.consensus, &world).await;
+            }
+
+            // 🌱 Эволюция
+            Phase::Evolution => {
+                let mut guard = world.nodes.lock().await;
+                EnergyEvolution::evolve(&mut guard, &mut self.evolution_rng).await;
+                println!("💓 Пульс организма (эволюция прошла)");
+            }
+
+            // 💫 Экономика
+            Phase::Economy => {
+                EconomyCycle::step(world.nodes.clone(), world.fund.clone(), &mut self.economy_rng, &self.economy).await;
+            }
+
+            // 🧠 Сознание
+            Phase::Brain => {
+                let mut brain = world.brain.write().await;
+                brain.step(world.nodes.clone(), world.fund.clone(), world.net.clone()).await;
+            }
+
+            // 📬 Доставка накопившихся сообщений: шина разбирает их по входящим
+            // очередям — адресное ноде `to`, широковещательное всем подписчикам
+            // темы, — затем каждая нода по порядку обрабатывает свою очередь.
+            // С симулятором сети сообщение сначала проходит через него и приходит
+            // в одной из следующих фаз доставки — или не приходит вовсе.
+            Phase::Delivery => {
+                // ⌛ запросы, не получившие ответа к сроку
+                for expired in world.net.expire(tick) {
+                    println!("⌛ Запрос #{} от {} закрыт без ответа", expired.id, expired.from);
+                    // голоса за блок не собрались к сроку — блок не принят
+                    if let Some(proposal) = world.ballots.expire(expired.id) {
+                        consensus::reject(proposal, &world, "голосование не уложилось в срок");
+                    }
+                    world.log.emit(Event::RequestExpired {
+                        request: expired.id,
+                        from: expired.from,
+                        message: format!("{:?}", expired.msg_type),
+                        replies: expired.replies,
+                    });
+                }
+
+                let messages = world.net.drain().await;
+                if messages.is_empty() && !world.netsim.is_enabled() {
+                    return;
+                }
+                let nodes = world.nodes.lock().await.clone();
+                if nodes.is_empty() {
+                    return;
+                }
+                // ноды, родившиеся с прошлой доставки, подключаются к шине, умершие — уходят
+                let mut names = Vec::with_capacity(nodes.len());
+                for node in nodes.iter() {
+                    names.push(node.lock().await.name.clone());
+                }
+                world.net.sync_members(&names);
+
+                // 🌐 сообщения своих нод уходят и пирам; пакет подписывает первая нода
+                let gateway = match world.network.is_enabled() {
+                    true => Some(nodes[0].lock().await.identity.clone()),
+                    false => None,
+                };
+                for msg in messages {
+                    // поддельное или изменённое в пути сообщение не доставляется
+                    if let Err(e) = msg.verify(&world.keys) {
+                        println!("🚫 Сообщение от {} отклонено: {}", msg.from, e);
+                        continue;
+                    }
+                    world.net.messages().record(&msg);
+                    if let Some(gateway) = &gateway {
+                        if world.network.is_local(&msg.from) {
+                            world.network.forward(&msg, gateway, &world.keys);
+                        }
+                        // адресованное ноде другого процесса доставит её процесс
+                        if msg.to.as_deref().is_some_and(|to| !world.network.is_local(to)) {
+                            continue;
+                        }
+                    }
+                    for to in world.net.recipients(&msg) {
+                        if world.netsim.is_enabled() {
+                            world.netsim.send(msg.clone(), &to, tick);
+                        } else {
+                            world.net.deliver(&to, msg.clone());
+                        }
+                    }
+                }
+
+                // 📡 сообщения, которым симулятор разрешил прийти к этому тику
+                for (msg, to) in world.netsim.due(tick) {
+                    if !world.net.deliver(&to, msg.clone()) {
+                        world.netsim.recipient_gone(&msg, &to);
+                    }
+                }
+
+                let directory: Directory = names.iter().cloned().zip(nodes.iter().cloned()).collect();
+                for (node, name) in nodes.iter().zip(names.iter()) {
+                    for msg in world.net.take(name) {
+                        handle_message(node.clone(), msg, &world, &directory).await;
+                    }
+                }
+            }
+
+            // 📸 Снимок состояния мозга для API и файлы нод на диске
+            Phase::Snapshot => {
+                let snapshot = BrainSnapshot::from_brain_lock(&world.brain).await;
+                println!(
+                    "📸 [Snapshot] обновлён: память = {} событий, агрессивность = {:.2}",
+                    snapshot.recent_memory.len(),
+                    snapshot.aggressiveness
+                );
+                *world.snapshot.write().await = snapshot;
+
+                // 💾 каталоги нод в корне данных (если он включён)
+                if world.data.is_enabled() {
+                    for node in world.nodes.lock().await.iter() {
+                        node.lock().await.save_files().await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn periods(every: [u64; 8]) -> SchedulerConfig {
+        let [help, nodes, consensus, evolution, economy, brain, delivery, snapshot] = every;
+        SchedulerConfig {
+            help_every: help,
+            nodes_every: nodes,
+            consensus_every: consensus,
+            evolution_every: evolution,
+            economy_every: economy,
+            brain_every: brain,
+            delivery_every: delivery,
+            snapshot_every: snapshot,
+            ..SchedulerConfig::default()
+        }
+    }
+
+    #[test]
+    fn phases_run_in_fixed_order_on_their_periods() {
+        let config = periods([1; 8]);
+        assert_eq!(config.due(7), Phase::ALL.to_vec());
+        for phase in Phase::ALL {
+            assert_eq!(Phase::from_name(phase.name()), Some(phase));
+        }
+
+        let config = periods([2, 3, 0, 6, 4, 5, 1, 0]);
+        use Phase::*;
+        assert_eq!(config.due(1), vec![Delivery]);
+        assert_eq!(config.due(2), vec![HelpSignals, Delivery]);
+        assert_eq!(config.due(6), vec![HelpSignals, NodeTicks, Evolution, Delivery]);
+        assert_eq!(config.due(12), vec![HelpSignals, NodeTicks, Evolution, Economy, Delivery]);
+        assert_eq!(config.due(30), vec![HelpSignals, NodeTicks, Evolution, Brain, Delivery]);
+        // период 0 выключает фазу насовсем
+        assert!((1..=60).all(|tick| !config.due(tick).contains(&Consensus) && !config.due(tick).contains(&Snapshot)));
+
+        let counts: Vec<usize> = Phase::ALL
+            .iter()
+            .map(|phase| (1..=60).filter(|tick| config.due(*tick).contains(phase)).count())
+            .collect();
+        assert_eq!(counts, vec![30, 20, 0, 10, 15, 12, 60, 0]);
+    }
+
+    async fn scheduler(every: [u64; 8]) -> Scheduler {
+        let mut config = Config::default();
+        config.simulation.initial_nodes = 3;
+        config.scheduler = periods(every);
+        let world = crate::test_world(&config).await;
+        Scheduler::new(
+            config.scheduler.clone(),
+            config.economy,
+            config.consensus,
+            config.checkpoint.clone(),
+            Seed::new(1),
+            world,
+        )
+    }
+
+    #[tokio::test]
+    async fn delivery_runs_after_the_phases_that_send() {
+        // сигнал помощи разбирается доставкой в том же тике
+        let mut s = scheduler([1, 0, 0, 0, 0, 0, 1, 0]).await;
+        s.step().await;
+        let stats = s.world.net.stats();
+        assert_eq!((s.tick(), stats.requests, stats.broadcast), (1, 1, 1));
+
+        // без доставки сигналы копятся в исходящей очереди, по одному за период
+        let mut s = scheduler([3, 0, 0, 0, 0, 0, 0, 0]).await;
+        for _ in 0..10 {
+            s.step().await;
+        }
+        let stats = s.world.net.stats();
+        assert_eq!((s.tick(), stats.requests, stats.queued, stats.broadcast), (10, 3, 3, 0));
+    }
+}
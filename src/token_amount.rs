@@ -0,0 +1,258 @@
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+
+/// Минимальных единиц в одном токене (6 знаков после запятой)
+pub const MINOR_PER_TOKEN: u64 = 1_000_000;
+const DECIMALS: usize = 6;
+
+/// 🪙 Количество токенов в целых минимальных единицах.
+/// Сложение и вычитание проверяются (`checked_*`), поэтому повторные
+/// списания по 0.5 не копят ошибку округления, а сравнение баланса с ценой точное.
+/// Записывается точной десятичной строкой: `"12.5"`, `"0.000001"`; читается
+/// и из строки, и из обычного числа (`12.5` в JSON и TOML).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TokenAmount(u64);
+
+impl TokenAmount {
+    pub const ZERO: TokenAmount = TokenAmount(0);
+
+    pub const fn from_minor(minor: u64) -> Self {
+        Self(minor)
+    }
+
+    /// Целое число токенов
+    pub const fn whole(tokens: u64) -> Self {
+        Self(tokens * MINOR_PER_TOKEN)
+    }
+
+    /// Из десятичного числа с округлением до минимальной единицы.
+    /// `None` для отрицательных, бесконечных и слишком больших значений.
+    pub fn from_f64(tokens: f64) -> Option<Self> {
+        let minor = (tokens * MINOR_PER_TOKEN as f64).round();
+        if minor.is_finite() && minor >= 0.0 && minor <= u64::MAX as f64 {
+            Some(Self(minor as u64))
+        } else {
+            None
+        }
+    }
+
+    /// Только для отображения и статистики — в расчётах балансов не используется
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / MINOR_PER_TOKEN as f64
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+
+    /// Для накопительных итогов, где переполнение u64 недостижимо
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    /// `{:.2}` округляет как у `f64`, без точности — все значащие знаки
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(precision) = f.precision() {
+            return write!(f, "{:.*}", precision, self.to_f64());
+        }
+        let whole = self.0 / MINOR_PER_TOKEN;
+        let frac = self.0 % MINOR_PER_TOKEN;
+        if frac == 0 {
+            write!(f, "{}", whole)
+        } else {
+            let digits = format!("{:0width$}", frac, width = DECIMALS);
+            write!(f, "{}.{}", whole, digits.trim_end_matches('0'))
+        }
+    }
+}
+
+impl Sum for TokenAmount {
+    /// Сумма с насыщением: на реальных балансах переполнение u64 недостижимо
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Self::saturating_add)
+    }
+}
+
+impl Serialize for TokenAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // строка, а не f64: через f64 `0.000001` превратилось бы в `1e-6`,
+        // а суммы больше ~9·10⁹ токенов потеряли бы младшие знаки
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TokenAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TokenAmountVisitor)
+    }
+}
+
+struct TokenAmountVisitor;
+
+impl<'de> Visitor<'de> for TokenAmountVisitor {
+    type Value = TokenAmount;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "неотрицательное количество токенов")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<TokenAmount, E> {
+        v.checked_mul(MINOR_PER_TOKEN)
+            .map(TokenAmount)
+            .ok_or_else(|| E::custom(format!("слишком много токенов: {}", v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<TokenAmount, E> {
+        if v < 0 {
+            return Err(E::custom(format!("количество токенов не может быть отрицательным: {}", v)));
+        }
+        self.visit_u64(v as u64)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<TokenAmount, E> {
+        // десятичная запись f64 без экспоненты — сначала точный разбор, затем округление
+        if let Ok(amount) = v.to_string().parse() {
+            return Ok(amount);
+        }
+        TokenAmount::from_f64(v).ok_or_else(|| {
+            E::custom(format!("количество токенов должно быть конечным неотрицательным числом: {}", v))
+        })
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<TokenAmount, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
+impl std::str::FromStr for TokenAmount {
+    type Err = String;
+
+    /// Точный разбор десятичной записи: `"12"`, `"0.5"`, `"3.000001"`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || format!("некорректное количество токенов '{}'", s);
+        let (whole, frac) = s.trim().split_once('.').unwrap_or((s.trim(), ""));
+        if whole.is_empty() || frac.len() > DECIMALS || !frac.chars().all(|c| c.is_ascii_digit()) {
+            return Err(bad());
+        }
+        let whole: u64 = whole.parse().map_err(|_| bad())?;
+        let frac: u64 = if frac.is_empty() {
+            0
+        } else {
+            format!("{:0<width$}", frac, width = DECIMALS).parse().map_err(|_| bad())?
+        };
+        whole
+            .checked_mul(MINOR_PER_TOKEN)
+            .and_then(|m| m.checked_add(frac))
+            .map(TokenAmount)
+            .ok_or_else(bad)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<TokenAmount, String> {
+        s.parse()
+    }
+
+    #[test]
+    fn decimals_are_parsed_exactly() {
+        assert_eq!(parse("12"), Ok(TokenAmount::whole(12)));
+        assert_eq!(parse("0.5"), Ok(TokenAmount::from_minor(500_000)));
+        assert_eq!(parse("3.000001"), Ok(TokenAmount::from_minor(3_000_001)));
+        assert_eq!(parse(" 0.25 "), Ok(TokenAmount::from_minor(250_000)));
+        assert_eq!(parse("0"), Ok(TokenAmount::ZERO));
+        // десятичная запись переживает круг через Display
+        for s in ["12", "0.5", "3.000001", "18446744073709.551615"] {
+            assert_eq!(parse(s).unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn malformed_amounts_are_rejected() {
+        for s in ["", ".5", "1.0000001", "0.1234567", "-1", "-0.5", "1,5", "1.2.3", "abc", "1e3", "0.5x"] {
+            assert!(parse(s).is_err(), "'{}'", s);
+        }
+    }
+
+    #[test]
+    fn overflow_is_rejected() {
+        // u64::MAX минимальных единиц — ещё можно
+        assert_eq!(parse("18446744073709.551615"), Ok(TokenAmount::from_minor(u64::MAX)));
+        assert!(parse("18446744073709.551616").is_err());
+        assert!(parse("18446744073710").is_err());
+        assert!(parse("99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn checked_arithmetic_stops_at_the_edges() {
+        let half = TokenAmount::from_minor(500_000);
+        assert_eq!(half.checked_add(half), Some(TokenAmount::whole(1)));
+        assert_eq!(TokenAmount::whole(1).checked_sub(half), Some(half));
+        assert_eq!(half.checked_sub(half), Some(TokenAmount::ZERO));
+        assert_eq!(half.checked_sub(TokenAmount::whole(1)), None);
+        assert_eq!(TokenAmount::from_minor(u64::MAX).checked_add(TokenAmount::from_minor(1)), None);
+
+        // сто списаний по 0.01 дают ровно 1 — без накопленной ошибки f64
+        let cent = parse("0.01").unwrap();
+        let mut balance = TokenAmount::whole(1);
+        for _ in 0..100 {
+            balance = balance.checked_sub(cent).unwrap();
+        }
+        assert!(balance.is_zero());
+        assert_eq!(balance.checked_sub(cent), None);
+
+        assert_eq!(TokenAmount::from_minor(u64::MAX).saturating_add(half), TokenAmount::from_minor(u64::MAX));
+        assert_eq!(half.saturating_sub(TokenAmount::whole(1)), TokenAmount::ZERO);
+    }
+
+    #[test]
+    fn json_accepts_only_non_negative_finite_amounts() {
+        assert_eq!(serde_json::from_str::<TokenAmount>("2.5").unwrap(), TokenAmount::from_minor(2_500_000));
+        assert_eq!(serde_json::from_str::<TokenAmount>("3").unwrap(), TokenAmount::whole(3));
+        assert_eq!(serde_json::from_str::<TokenAmount>("\"0.000001\"").unwrap(), TokenAmount::from_minor(1));
+        assert!(serde_json::from_str::<TokenAmount>("-1").is_err());
+        assert!(serde_json::from_str::<TokenAmount>("-0.5").is_err());
+        assert!(serde_json::from_str::<TokenAmount>("1e30").is_err());
+        assert_eq!(TokenAmount::from_f64(f64::NAN), None);
+        assert_eq!(serde_json::to_string(&TokenAmount::from_minor(2_500_000)).unwrap(), "\"2.5\"");
+        assert_eq!(toml::Value::Float(0.5).try_into::<TokenAmount>().unwrap(), TokenAmount::from_minor(500_000));
+    }
+
+    #[test]
+    fn json_keeps_every_minor_unit() {
+        let amounts = [
+            (TokenAmount::from_minor(1), "\"0.000001\""),
+            (TokenAmount::from_minor(u64::MAX), "\"18446744073709.551615\""),
+            (TokenAmount::from_minor(9_007_199_254_740_993), "\"9007199254.740993\""),
+            (TokenAmount::ZERO, "\"0\""),
+        ];
+        for (amount, json) in amounts {
+            assert_eq!(serde_json::to_string(&amount).unwrap(), json);
+            assert_eq!(serde_json::from_str::<TokenAmount>(json).unwrap(), amount);
+            // и через `json!` / `serde_json::Value`, как в ответах API
+            let value = serde_json::json!({ "balance": amount });
+            assert_eq!(serde_json::from_value::<TokenAmount>(value["balance"].clone()).unwrap(), amount);
+        }
+        // обычное число тоже читается точно, пока оно представимо в f64
+        assert_eq!(serde_json::from_str::<TokenAmount>("0.000001").unwrap(), TokenAmount::from_minor(1));
+        assert_eq!(serde_json::from_str::<TokenAmount>("0.1").unwrap(), TokenAmount::from_minor(100_000));
+    }
+}
@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::clock::SimClock;
+use crate::token_amount::TokenAmount;
+
+/// Счёт в книге токенов
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Account {
+    /// системный счёт эмиссии: отсюда приходят все новые токены
+    Mint,
+    /// системный счёт сжигания: сюда уходят все списания
+    Burn,
+    /// `NetworkFund`
+    Fund,
+    /// кошелёк ноды
+    Wallet(String),
+}
+
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Account::Mint => write!(f, "mint"),
+            Account::Burn => write!(f, "burn"),
+            Account::Fund => write!(f, "fund"),
+            Account::Wallet(owner) => write!(f, "wallet:{}", owner),
+        }
+    }
+}
+
+/// 🪙 Причина движения токенов
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenReason {
+    /// награда майнеру (`POST /mine/:id`, `Node::mine_block`)
+    BlockReward,
+    /// доля случайного валидатора
+    ValidatorReward,
+    /// отчисление в фонд за добытый блок
+    MiningFundCut,
+    /// плата ноде за `POST /update/:id`
+    UpdateFee,
+    /// отчисление в фонд за `POST /update/:id`
+    UpdateFundCut,
+    /// награда за успешную эволюцию
+    EvolutionReward,
+    /// плата за содержание в цикле экономики
+    Upkeep,
+    /// плата за помощь слабой ноде
+    HelpCost,
+    /// фонд обменял токены на энергию сети
+    FundInjection,
+    /// перевод между кошельками
+    Transfer,
+    /// остаток кошелька умершей ноды
+    Death,
+}
+
+/// Одна проводка: кто заплатил, кто получил, сколько и зачем
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub seq: u64,
+    pub tick: u64,
+    pub from: Account,
+    pub to: Account,
+    pub amount: TokenAmount,
+    pub reason: TokenReason,
+}
+
+/// Сколько последних проводок держать в памяти для `/tokens/ledger`
+const RECENT_ENTRIES: usize = 1000;
+
+/// Итоги книги — попадают в контрольную точку
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TokenTotals {
+    pub seq: u64,
+    pub minted: TokenAmount,
+    pub burned: TokenAmount,
+    /// оборот по причинам
+    pub by_reason: BTreeMap<TokenReason, TokenAmount>,
+}
+
+/// 📗 Книга токенов с двойной записью.
+/// Каждое движение — проводка со счёта на счёт; эмиссия идёт со счёта `Mint`,
+/// сжигание — на счёт `Burn`, поэтому предложение в любой момент равно
+/// `minted − burned`. Клонирование дешёвое.
+#[derive(Clone, Debug)]
+pub struct TokenLedger {
+    inner: Arc<Mutex<LedgerState>>,
+    clock: SimClock,
+}
+
+#[derive(Debug, Default)]
+struct LedgerState {
+    totals: TokenTotals,
+    recent: VecDeque<Entry>,
+    /// проводки, ещё не попавшие в ключевой блок
+    pending: Vec<Entry>,
+}
+
+impl TokenLedger {
+    pub fn new(clock: SimClock) -> Self {
+        Self::from_totals(clock, TokenTotals::default(), Vec::new())
+    }
+
+    /// Продолжить книгу с сохранённых итогов и проводок, ждущих блока
+    pub fn from_totals(clock: SimClock, totals: TokenTotals, pending: Vec<Entry>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(LedgerState { totals, recent: VecDeque::new(), pending })),
+            clock,
+        }
+    }
+
+    /// Записать проводку с номером текущего тика
+    pub fn record(&self, from: Account, to: Account, amount: TokenAmount, reason: TokenReason) {
+        if amount.is_zero() {
+            return;
+        }
+        let Ok(mut state) = self.inner.lock() else {
+            return;
+        };
+        let totals = &mut state.totals;
+        totals.seq += 1;
+        if from == Account::Mint {
+            totals.minted = totals.minted.saturating_add(amount);
+        }
+        if to == Account::Burn {
+            totals.burned = totals.burned.saturating_add(amount);
+        }
+        let turnover = totals.by_reason.entry(reason).or_default();
+        *turnover = turnover.saturating_add(amount);
+
+        let entry = Entry { seq: totals.seq, tick: self.clock.tick(), from, to, amount, reason };
+        if state.recent.len() == RECENT_ENTRIES {
+            state.recent.pop_front();
+        }
+        state.recent.push_back(entry.clone());
+        state.pending.push(entry);
+    }
+
+    /// Забрать проводки для нового ключевого блока
+    pub fn take_pending(&self) -> Vec<Entry> {
+        self.inner.lock().map(|mut s| std::mem::take(&mut s.pending)).unwrap_or_default()
+    }
+
+    /// Вернуть проводки отклонённого блока: они попадут в следующий, раньше новых
+    pub fn requeue(&self, entries: Vec<Entry>) {
+        if let Ok(mut state) = self.inner.lock() {
+            let newer = std::mem::replace(&mut state.pending, entries);
+            state.pending.extend(newer);
+        }
+    }
+
+    /// Проводки, ещё не попавшие в блок (для контрольной точки)
+    pub fn pending(&self) -> Vec<Entry> {
+        self.inner.lock().map(|s| s.pending.clone()).unwrap_or_default()
+    }
+
+    pub fn totals(&self) -> TokenTotals {
+        self.inner.lock().map(|s| s.totals.clone()).unwrap_or_default()
+    }
+
+    /// Токенов в обращении: выпущено минус сожжено
+    pub fn supply(&self) -> TokenAmount {
+        let totals = self.totals();
+        totals.minted.saturating_sub(totals.burned)
+    }
+
+    /// Последние `limit` проводок, от старых к новым
+    pub fn recent(&self, limit: usize) -> Vec<Entry> {
+        self.inner
+            .lock()
+            .map(|s| {
+                let skip = s.recent.len().saturating_sub(limit);
+                s.recent.iter().skip(skip).cloned().collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::rng::Seed;
+    use crate::scheduler::Scheduler;
+
+    /// Обороты счетов по проводкам: (приход, расход)
+    fn turnover(entries: &[Entry]) -> BTreeMap<Account, (TokenAmount, TokenAmount)> {
+        let mut turnover: BTreeMap<Account, (TokenAmount, TokenAmount)> = BTreeMap::new();
+        for entry in entries {
+            let debit = &mut turnover.entry(entry.from.clone()).or_default().1;
+            *debit = debit.checked_add(entry.amount).unwrap();
+            let credit = &mut turnover.entry(entry.to.clone()).or_default().0;
+            *credit = credit.checked_add(entry.amount).unwrap();
+        }
+        turnover
+    }
+
+    #[test]
+    fn every_entry_debits_and_credits_and_supply_is_minted_minus_burned() {
+        let ledger = TokenLedger::new(SimClock::new(0, 100));
+        let wallet = |name: &str| Account::Wallet(name.into());
+        ledger.record(Account::Mint, wallet("a"), TokenAmount::whole(10), TokenReason::BlockReward);
+        ledger.record(Account::Mint, Account::Fund, TokenAmount::whole(2), TokenReason::MiningFundCut);
+        ledger.record(wallet("a"), wallet("b"), TokenAmount::whole(4), TokenReason::Transfer);
+        ledger.record(wallet("b"), Account::Burn, TokenAmount::from_minor(1_500_000), TokenReason::Upkeep);
+        ledger.record(Account::Fund, Account::Burn, TokenAmount::whole(1), TokenReason::FundInjection);
+        // нулевая проводка не пишется
+        ledger.record(wallet("a"), Account::Burn, TokenAmount::ZERO, TokenReason::Upkeep);
+
+        let totals = ledger.totals();
+        assert_eq!(totals.seq, 5);
+        assert_eq!(totals.minted, TokenAmount::whole(12));
+        assert_eq!(totals.burned, TokenAmount::from_minor(2_500_000));
+        assert_eq!(ledger.supply(), TokenAmount::from_minor(9_500_000));
+        assert_eq!(totals.by_reason[&TokenReason::Transfer], TokenAmount::whole(4));
+
+        let entries = ledger.recent(100);
+        assert_eq!(entries.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+        let turnover = turnover(&entries);
+        let credits: TokenAmount = turnover.values().map(|t| t.0).sum();
+        let debits: TokenAmount = turnover.values().map(|t| t.1).sum();
+        assert_eq!(credits, debits);
+        assert_eq!(turnover[&Account::Mint], (TokenAmount::ZERO, totals.minted));
+        assert_eq!(turnover[&Account::Burn], (totals.burned, TokenAmount::ZERO));
+        let balance = |account: Account| {
+            let (credit, debit) = turnover[&account];
+            credit.checked_sub(debit).unwrap()
+        };
+        assert_eq!(balance(wallet("a")), TokenAmount::whole(6));
+        assert_eq!(balance(wallet("b")), TokenAmount::from_minor(2_500_000));
+        assert_eq!(balance(Account::Fund), TokenAmount::whole(1));
+
+        // в блок уходят все проводки один раз, отклонённые возвращаются первыми
+        let pending = ledger.take_pending();
+        assert_eq!(pending.len(), 5);
+        ledger.record(Account::Mint, wallet("b"), TokenAmount::whole(1), TokenReason::ValidatorReward);
+        ledger.requeue(pending);
+        assert_eq!(ledger.pending().iter().map(|e| e.seq).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[tokio::test]
+    async fn wallets_and_fund_hold_the_whole_supply() {
+        let mut config = Config::default();
+        config.simulation.initial_nodes = 4;
+        config.population.cull_above = 12;
+        config.population.cull_keep = 8;
+        let world = crate::test_world(&config).await;
+        let mut scheduler = Scheduler::new(
+            config.scheduler.clone(),
+            config.economy,
+            config.consensus,
+            config.checkpoint.clone(),
+            Seed::new(1),
+            world.clone(),
+        );
+        for _ in 0..40 {
+            scheduler.step().await;
+        }
+
+        let mut held = *world.fund.lock().await.total.lock().await;
+        for node in world.nodes.lock().await.iter() {
+            held = held.checked_add(node.lock().await.wallet.get_balance().await).unwrap();
+        }
+        let totals = world.tokens.totals();
+        assert!(totals.seq > 0, "за 40 тиков не было ни одной проводки");
+        assert_eq!(held, world.tokens.supply());
+        assert_eq!(world.tokens.supply(), totals.minted.saturating_sub(totals.burned));
+    }
+}
@@ -1,55 +1,191 @@
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use crate::events::{Event, EventLog};
+use crate::token_amount::TokenAmount;
+use crate::token_ledger::{Account, TokenLedger, TokenReason};
 
 #[derive(Clone)]
 pub struct Wallet {
-    pub balance: Arc<Mutex<f64>>,
+    pub balance: Arc<Mutex<TokenAmount>>,
+    pub owner: String,
+    pub log: EventLog,
+    pub ledger: TokenLedger,
 }
 
 impl Wallet {
-    pub fn new() -> Self {
+    pub fn new(owner: &str, log: EventLog, ledger: TokenLedger) -> Self {
         Self {
-            balance: Arc::new(Mutex::new(0.0)),
+            balance: Arc::new(Mutex::new(TokenAmount::ZERO)),
+            owner: owner.to_string(),
+            log,
+            ledger,
         }
     }
 
-    pub async fn deposit(&self, amount: f64) {
-        let mut balance = self.balance.lock().await;
-        *balance += amount;
+    /// Счёт кошелька в книге токенов
+    pub fn account(&self) -> Account {
+        Account::Wallet(self.owner.clone())
     }
 
-    pub async fn withdraw(&self, amount: f64) -> bool {
-        let mut balance = self.balance.lock().await;
-        if *balance >= amount {
-            *balance -= amount;
-            true
-        } else {
-            false
-        }
-    }
-
-    /// 💰 Начислить токены
-    pub async fn reward(&self, amount: f64) {
+    /// 💰 Начислить токены (эмиссия)
+    pub async fn reward(&self, amount: TokenAmount, reason: TokenReason) -> bool {
         let mut b = self.balance.lock().await;
-        *b += amount;
+        let Some(new_balance) = b.checked_add(amount) else {
+            println!("⚠️ Кошелёк {} переполнен — начисление {} отклонено", self.owner, amount);
+            return false;
+        };
+        *b = new_balance;
+        self.ledger.record(Account::Mint, self.account(), amount, reason);
+        self.log_reward(amount, *b);
         println!("💎 Кошелёк пополнен на {:.2} токенов (всего: {:.2})", amount, *b);
+        true
     }
 
-    /// 💸 Списать токены
-    pub async fn spend(&self, amount: f64) -> bool {
+    /// 💸 Списать токены (сжигание)
+    pub async fn spend(&self, amount: TokenAmount, reason: TokenReason) -> bool {
         let mut b = self.balance.lock().await;
-        if *b >= amount {
-            *b -= amount;
-            println!("💸 Списано {:.2} токенов (остаток: {:.2})", amount, *b);
-            true
+        match b.checked_sub(amount) {
+            Some(new_balance) => {
+                *b = new_balance;
+                self.ledger.record(self.account(), Account::Burn, amount, reason);
+                self.log_spend(amount, *b);
+                println!("💸 Списано {:.2} токенов (остаток: {:.2})", amount, *b);
+                true
+            }
+            None => {
+                println!("⚠️ Недостаточно средств (нужно {:.2}, есть {:.2})", amount, *b);
+                false
+            }
+        }
+    }
+
+    /// 🔁 Перевести токены другому кошельку. Предложение не меняется.
+    pub async fn transfer_to(&self, to: &Wallet, amount: TokenAmount, reason: TokenReason) -> bool {
+        if amount.is_zero() || self.owner == to.owner {
+            return false;
+        }
+
+        // блокируем в порядке имён, чтобы встречные переводы не ждали друг друга вечно
+        let (mut from_balance, mut to_balance) = if self.owner < to.owner {
+            let a = self.balance.lock().await;
+            let b = to.balance.lock().await;
+            (a, b)
         } else {
-            println!("⚠️ Недостаточно средств (нужно {:.2}, есть {:.2})", amount, *b);
-            false
+            let b = to.balance.lock().await;
+            let a = self.balance.lock().await;
+            (a, b)
+        };
+
+        let Some(new_from) = from_balance.checked_sub(amount) else {
+            println!("⚠️ Недостаточно средств для перевода (нужно {:.2}, есть {:.2})", amount, *from_balance);
+            return false;
+        };
+        let Some(new_to) = to_balance.checked_add(amount) else {
+            println!("⚠️ Кошелёк {} переполнен — перевод отклонён", to.owner);
+            return false;
+        };
+        *from_balance = new_from;
+        *to_balance = new_to;
+        self.ledger.record(self.account(), to.account(), amount, reason);
+        self.log_spend(amount, *from_balance);
+        to.log_reward(amount, *to_balance);
+        println!("🔁 {} → {}: {:.2} токенов", self.owner, to.owner, amount);
+        true
+    }
+
+    /// ⚰️ Сжечь остаток кошелька умершей ноды
+    pub async fn close(&self) {
+        let mut b = self.balance.lock().await;
+        if !b.is_zero() {
+            let amount = *b;
+            *b = TokenAmount::ZERO;
+            self.ledger.record(self.account(), Account::Burn, amount, TokenReason::Death);
+            self.log_spend(amount, TokenAmount::ZERO);
         }
     }
 
+    fn log_reward(&self, amount: TokenAmount, balance: TokenAmount) {
+        self.log.emit(Event::WalletReward { node: self.owner.clone(), amount, balance });
+    }
+
+    fn log_spend(&self, amount: TokenAmount, balance: TokenAmount) {
+        self.log.emit(Event::WalletSpend { node: self.owner.clone(), amount, balance });
+    }
+
     /// Проверить баланс
-    pub async fn get_balance(&self) -> f64 {
+    pub async fn get_balance(&self) -> TokenAmount {
         *self.balance.lock().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimClock;
+
+    fn wallets() -> (TokenLedger, Wallet, Wallet) {
+        let ledger = TokenLedger::new(SimClock::new(0, 100));
+        let a = Wallet::new("a", EventLog::disabled(), ledger.clone());
+        let b = Wallet::new("b", EventLog::disabled(), ledger.clone());
+        (ledger, a, b)
+    }
+
+    #[tokio::test]
+    async fn transfer_moves_tokens_without_changing_supply() {
+        let (ledger, a, b) = wallets();
+        assert!(a.reward(TokenAmount::whole(10), TokenReason::BlockReward).await);
+        assert!(a.transfer_to(&b, TokenAmount::from_minor(4_500_000), TokenReason::Transfer).await);
+
+        assert_eq!(a.get_balance().await, TokenAmount::from_minor(5_500_000));
+        assert_eq!(b.get_balance().await, TokenAmount::from_minor(4_500_000));
+        assert_eq!(ledger.supply(), TokenAmount::whole(10));
+        let last = ledger.recent(1).pop().unwrap();
+        assert_eq!((last.from, last.to, last.reason), (a.account(), b.account(), TokenReason::Transfer));
+
+        // самому себе и нулевой перевод не проводятся
+        assert!(!a.transfer_to(&a, TokenAmount::whole(1), TokenReason::Transfer).await);
+        assert!(!a.transfer_to(&b, TokenAmount::ZERO, TokenReason::Transfer).await);
+        assert_eq!(ledger.totals().seq, 2);
+    }
+
+    #[tokio::test]
+    async fn insufficient_funds_change_nothing() {
+        let (ledger, a, b) = wallets();
+        assert!(b.reward(TokenAmount::whole(3), TokenReason::EvolutionReward).await);
+        assert!(!b.transfer_to(&a, TokenAmount::from_minor(3_000_001), TokenReason::Transfer).await);
+        assert!(!b.spend(TokenAmount::whole(4), TokenReason::Upkeep).await);
+        assert!(!a.spend(TokenAmount::from_minor(1), TokenReason::Upkeep).await);
+
+        assert_eq!(a.get_balance().await, TokenAmount::ZERO);
+        assert_eq!(b.get_balance().await, TokenAmount::whole(3));
+        assert_eq!(ledger.totals().seq, 1);
+        assert_eq!(ledger.supply(), TokenAmount::whole(3));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn opposite_transfers_do_not_deadlock() {
+        let (ledger, a, b) = wallets();
+        a.reward(TokenAmount::whole(100), TokenReason::BlockReward).await;
+        b.reward(TokenAmount::whole(100), TokenReason::BlockReward).await;
+
+        // встречные переводы берут блокировки в одном порядке — по именам
+        let mut tasks = Vec::new();
+        for i in 0..200 {
+            let (from, to) = if i % 2 == 0 { (a.clone(), b.clone()) } else { (b.clone(), a.clone()) };
+            tasks.push(tokio::spawn(async move {
+                from.transfer_to(&to, TokenAmount::whole(1), TokenReason::Transfer).await
+            }));
+        }
+        let all = async {
+            for task in tasks {
+                assert!(task.await.unwrap());
+            }
+        };
+        tokio::time::timeout(std::time::Duration::from_secs(10), all).await.expect("встречные переводы зависли");
+
+        assert_eq!(a.get_balance().await, TokenAmount::whole(100));
+        assert_eq!(b.get_balance().await, TokenAmount::whole(100));
+        assert_eq!(ledger.supply(), TokenAmount::whole(200));
+        assert_eq!(ledger.totals().by_reason[&TokenReason::Transfer], TokenAmount::whole(200));
+    }
+}
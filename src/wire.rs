@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::chain::Block;
+use crate::identity::{self, Identity, IdentityError, KeyRegistry};
+use crate::interaction::Message;
+use crate::neuron::Neuron;
+
+/// Версия протокола; пир с другой версией отключается после приветствия
+pub const PROTOCOL_VERSION: u32 = 4;
+
+/// Кадр длиннее этого не читается и не отправляется
+pub const MAX_FRAME: u32 = 16 * 1024 * 1024;
+
+/// Предел для приветствия: пир ещё не проверен, и под его кадр не выделяется много памяти
+pub const MAX_HELLO_FRAME: u32 = 4 * 1024;
+
+/// Больше стольких блоков в одном `BlocksResponse` не отправляется — остальное
+/// запрашивается следующими страницами
+pub const MAX_SYNC_BLOCKS: usize = 256;
+
+/// 📨 Содержимое кадра. `keys` — ключи нод, за которые ручается отправитель:
+/// получатель закрепляет их за именами (`KeyRegistry::pin`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Payload {
+    /// первый кадр соединения с каждой стороны
+    Hello { version: u32, listen: String },
+    /// проверка связи; ответ — `Pong` с тем же `nonce`
+    Ping { nonce: u64 },
+    Pong { nonce: u64 },
+    /// нейроны всех нод процесса
+    NeuronsSync { nodes: BTreeMap<String, Vec<Neuron>>, keys: BTreeMap<String, String> },
+    /// запрос блоков `key_chain`, начиная с высоты `from_height` (0 — вся цепь)
+    BlocksRequest { from_height: u64 },
+    /// страница `key_chain`: не больше `MAX_SYNC_BLOCKS` блоков с `from_height`;
+    /// `height` и `tip` — длина и хэш вершины всей цепи отвечающего
+    BlocksResponse { from_height: u64, blocks: Vec<Block>, keys: BTreeMap<String, String>, height: u64, tip: String },
+    /// сообщение шины ноды процесса
+    BusMessage { message: Message, keys: BTreeMap<String, String> },
+    /// запрос известных пиру адресов; ответ — `Peers`
+    GetPeers,
+    Peers { addrs: Vec<String> },
+}
+
+impl Payload {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Payload::Hello { .. } => "hello",
+            Payload::Ping { .. } => "ping",
+            Payload::Pong { .. } => "pong",
+            Payload::NeuronsSync { .. } => "neurons_sync",
+            Payload::BlocksRequest { .. } => "blocks_request",
+            Payload::BlocksResponse { .. } => "blocks_response",
+            Payload::BusMessage { .. } => "bus_message",
+            Payload::GetPeers => "get_peers",
+            Payload::Peers { .. } => "peers",
+        }
+    }
+}
+
+/// ✉️ Кадр: подписанное содержимое. `payload` передаётся строкой JSON —
+/// подпись ставится ровно под этими байтами и именем отправителя.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Envelope {
+    pub from: String,
+    pub public_key: String,
+    pub payload: String,
+    pub signature: String,
+}
+
+impl Envelope {
+    pub fn sign(identity: &Identity, payload: &Payload) -> Self {
+        let payload = serde_json::to_string(payload).unwrap_or_default();
+        Self {
+            from: identity.name.clone(),
+            public_key: identity.public_key(),
+            signature: identity.sign(&signed_bytes(&identity.name, &payload)),
+            payload,
+        }
+    }
+
+    /// 🔍 Проверить подпись, закрепить ключ отправителя и разобрать содержимое
+    pub fn open(&self, keys: &KeyRegistry) -> Result<Payload, WireError> {
+        identity::verify(&self.public_key, &signed_bytes(&self.from, &self.payload), &self.signature)
+            .map_err(WireError::Identity)?;
+        keys.pin(&self.from, &self.public_key).map_err(WireError::Identity)?;
+        serde_json::from_str(&self.payload).map_err(|e| WireError::Malformed(e.to_string()))
+    }
+
+    /// Версия из приветствия — читается до разбора, чтобы пир с другой
+    /// раскладкой кадров получил понятную ошибку, а не «не разобрано»
+    pub fn hello_version(&self) -> Option<u32> {
+        let value: serde_json::Value = serde_json::from_str(&self.payload).ok()?;
+        if value["type"] != "hello" {
+            return None;
+        }
+        value["version"].as_u64().map(|v| v as u32)
+    }
+}
+
+fn signed_bytes(from: &str, payload: &str) -> Vec<u8> {
+    serde_json::to_vec(&(PROTOCOL_VERSION, from, payload)).unwrap_or_default()
+}
+
+/// Почему кадр не принят
+#[derive(Debug)]
+pub enum WireError {
+    Io(std::io::Error),
+    /// заявленная длина кадра больше `MAX_FRAME`
+    TooLarge { len: u64, max: u32 },
+    /// кадр не разбирается как JSON ожидаемой формы
+    Malformed(String),
+    /// пир говорит на другой версии протокола
+    Version { ours: u32, theirs: u32 },
+    /// соединение началось не с приветствия или приветствие повторилось
+    Handshake(String),
+    /// пир забанен, мест нет или это соединение с самим собой
+    Refused(String),
+    Identity(IdentityError),
+}
+
+impl WireError {
+    /// После такой ошибки границы кадров потеряны или пир несовместим — соединение закрывается.
+    /// Неразобранный или неподписанный кадр просто пропускается.
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self, WireError::Malformed(_) | WireError::Identity(_))
+    }
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::Io(e) => write!(f, "ошибка соединения: {}", e),
+            WireError::TooLarge { len, max } => write!(f, "кадр {} байт больше предела {} байт", len, max),
+            WireError::Malformed(e) => write!(f, "кадр не разобран: {}", e),
+            WireError::Version { ours, theirs } => {
+                write!(f, "пир говорит на версии протокола {}, мы — на {}", theirs, ours)
+            }
+            WireError::Handshake(e) => write!(f, "нарушено приветствие: {}", e),
+            WireError::Refused(e) => write!(f, "соединение отклонено: {}", e),
+            WireError::Identity(e) => write!(f, "подпись кадра не принята: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+impl From<std::io::Error> for WireError {
+    fn from(e: std::io::Error) -> Self {
+        WireError::Io(e)
+    }
+}
+
+/// 📥 Прочитать кадр: длина (u32, big-endian) и JSON конверта.
+/// `None` — пир закрыл соединение между кадрами.
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<Envelope>, WireError> {
+    read_frame_within(reader, MAX_FRAME).await
+}
+
+/// Прочитать кадр не длиннее `max` байт (приветствие — `MAX_HELLO_FRAME`)
+pub async fn read_frame_within<R: AsyncRead + Unpin>(reader: &mut R, max: u32) -> Result<Option<Envelope>, WireError> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_be_bytes(len);
+    if len > max {
+        return Err(WireError::TooLarge { len: len as u64, max });
+    }
+    let mut body = vec![0; len as usize];
+    reader.read_exact(&mut body).await?;
+    serde_json::from_slice(&body).map(Some).map_err(|e| WireError::Malformed(e.to_string()))
+}
+
+/// 📤 Записать кадр; слишком большой конверт не отправляется
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, envelope: &Envelope) -> Result<(), WireError> {
+    let body = serde_json::to_vec(envelope).map_err(|e| WireError::Malformed(e.to_string()))?;
+    if body.len() as u64 > MAX_FRAME as u64 {
+        return Err(WireError::TooLarge { len: body.len() as u64, max: MAX_FRAME });
+    }
+    writer.write_all(&(body.len() as u32).to_be_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Seed;
+
+    #[tokio::test]
+    async fn hello_is_read_under_a_small_limit() {
+        let signer = Identity::derive("b_node0", &Seed::new(3).stream("node/b_node0"));
+        let hello = Payload::Hello { version: PROTOCOL_VERSION, listen: "127.0.0.1:4001".into() };
+        let mut small = Vec::new();
+        write_frame(&mut small, &Envelope::sign(&signer, &hello)).await.unwrap();
+        let envelope = read_frame_within(&mut small.as_slice(), MAX_HELLO_FRAME).await.unwrap().unwrap();
+        assert_eq!(envelope.hello_version(), Some(PROTOCOL_VERSION));
+
+        // огромное «приветствие» отвергается по заголовку, до выделения памяти под тело
+        let mut large = Vec::new();
+        large.extend_from_slice(&(MAX_FRAME).to_be_bytes());
+        match read_frame_within(&mut large.as_slice(), MAX_HELLO_FRAME).await {
+            Err(WireError::TooLarge { len, max }) => assert_eq!((len, max), (MAX_FRAME as u64, MAX_HELLO_FRAME)),
+            other => panic!("ожидался TooLarge, получено {:?}", other.map(|e| e.map(|e| e.from))),
+        }
+    }
+}
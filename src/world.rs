@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::brain::{Brain, BrainSnapshot};
+use crate::clock::SimClock;
+use crate::consensus::Ballots;
+use crate::data_dir::DataRoot;
+use crate::economy::NetworkFund;
+use crate::energy_ledger::EnergyLedger;
+use crate::events::EventLog;
+use crate::identity::KeyRegistry;
+use crate::payload::BlockBodies;
+use crate::interaction::NetworkBus;
+use crate::netsim::NetSim;
+use crate::network::Network;
+use crate::node::Node;
+use crate::rng::SimRng;
+use crate::token_ledger::TokenLedger;
+
+/// 🌍 Общие ссылки на всё состояние организма.
+/// Клонирование дешёвое — копируются только `Arc`.
+#[derive(Clone)]
+pub struct World {
+    pub nodes: Arc<Mutex<Vec<Arc<Mutex<Node>>>>>,
+    pub fund: Arc<Mutex<NetworkFund>>,
+    pub brain: Arc<RwLock<Brain>>,
+    pub snapshot: Arc<RwLock<BrainSnapshot>>,
+    pub net: Arc<NetworkBus>,
+    pub clock: SimClock,
+    pub log: EventLog,
+    /// учёт энергии, общий для всех нод
+    pub energy: EnergyLedger,
+    /// книга токенов: кошельки, фонд, эмиссия и сжигание
+    pub tokens: TokenLedger,
+    /// открытые ключи всех нод
+    pub keys: KeyRegistry,
+    /// содержимое ключевых блоков (элементы и доказательства раунда)
+    pub bodies: BlockBodies,
+    /// корень данных: каталоги нод и манифест
+    pub data: DataRoot,
+    /// связь с другими процессами организма (выключена без `[network]`)
+    pub network: Network,
+    /// симулятор сети между нодами (выключен без `[netsim]`)
+    pub netsim: NetSim,
+    /// объявленные ключевые блоки, ждущие голосов
+    pub ballots: Ballots,
+    /// поток случайности API (`seed.stream("api")`): выбор валидатора в `/mine`
+    pub api_rng: Arc<Mutex<SimRng>>,
+}
+
+impl World {
+    /// Суммарная энергия живых нод
+    pub async fn total_energy(&self) -> f64 {
+        let nodes = self.nodes.lock().await;
+        let mut total = 0.0;
+        for node in nodes.iter() {
+            let node = node.lock().await;
+            total += node.energy.lock().await.level;
+        }
+        total
+    }
+
+    /// 📋 Штатное завершение: отметить в манифесте всех живых нод остановленными
+    pub async fn record_shutdown(&self) {
+        let tick = self.clock.tick();
+        let nodes = self.nodes.lock().await;
+        for node in nodes.iter() {
+            self.data.record_stop(&node.lock().await.name, tick);
+        }
+    }
+}
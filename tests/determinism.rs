@@ -0,0 +1,80 @@
+//! 🎲 Прогон с тем же seed повторяется байт в байт: контрольная точка,
+//! журнал событий, вершины цепей и отчёт
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+const SEED: &str = "42";
+/// короткий прогон на маленькой популяции — тест должен идти секунды, а не минуты
+const TICKS: u64 = 30;
+const NODES: &str = "4";
+
+/// Чистый рабочий каталог прогона: журналы и контрольные точки пишутся относительно него
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("organism-determinism-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn headless(dir: &PathBuf) -> Child {
+    let ticks = TICKS.to_string();
+    Command::new(env!("CARGO_BIN_EXE_organism"))
+        .args(["--headless", "--fast", "--seed", SEED, "--ticks", &ticks, "--report", "report.json"])
+        .args(["--set", &format!("simulation.initial_nodes={}", NODES)])
+        .args(["--set", "population.cull_above=12", "--set", "population.cull_keep=8"])
+        .args(["--set", &format!("checkpoint.every={}", TICKS)])
+        .args(["--set", "events.enabled=true"])
+        // каждый прогон — в своём свежем каталоге данных
+        .args(["--set", "storage.enabled=true", "--set", "storage.fsync=false"])
+        .current_dir(dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap()
+}
+
+fn read(dir: &Path, file: &str) -> String {
+    fs::read_to_string(dir.join(file)).unwrap_or_else(|e| panic!("{}: {}", dir.join(file).display(), e))
+}
+
+/// Хэши последних блоков ключевой и data-цепей каждой ноды
+fn chain_tips(checkpoint: &serde_json::Value) -> Vec<(String, String, String)> {
+    let tip = |chain: &serde_json::Value| chain["blocks"].as_array().unwrap().last().unwrap()["hash"].as_str().unwrap().to_string();
+    checkpoint["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|node| (node["name"].as_str().unwrap().to_string(), tip(&node["key_chain"]), tip(&node["data_chain"])))
+        .collect()
+}
+
+#[test]
+fn same_seed_gives_the_same_world() {
+    let dirs = [scratch("a"), scratch("b")];
+    let runs: Vec<Child> = dirs.iter().map(headless).collect();
+    for mut run in runs {
+        assert!(run.wait().unwrap().success());
+    }
+
+    let report: serde_json::Value = serde_json::from_str(&read(&dirs[0], "report.json")).unwrap();
+    assert_eq!(report["ticks_run"], TICKS, "прогон оборвался раньше");
+
+    let checkpoint = format!("checkpoints/checkpoint_{:010}.json", TICKS);
+    let checkpoints: Vec<serde_json::Value> =
+        dirs.iter().map(|dir| serde_json::from_str(&read(dir, &checkpoint)).unwrap()).collect();
+    let tips = chain_tips(&checkpoints[0]);
+    assert!(!tips.is_empty(), "в контрольной точке нет нод");
+    assert_eq!(tips, chain_tips(&checkpoints[1]), "вершины цепей прогонов с seed {} различаются", SEED);
+    assert!(checkpoints[0] == checkpoints[1], "контрольные точки прогонов с seed {} различаются", SEED);
+
+    let events = read(&dirs[0], "events.jsonl");
+    assert!(!events.is_empty(), "журнал событий пуст");
+    assert!(events == read(&dirs[1], "events.jsonl"), "журналы событий прогонов с seed {} различаются", SEED);
+    assert!(read(&dirs[0], "report.json") == read(&dirs[1], "report.json"), "отчёты прогонов с seed {} различаются", SEED);
+
+    for dir in dirs.iter() {
+        let _ = fs::remove_dir_all(dir);
+    }
+}